@@ -1,19 +1,81 @@
+use crate::actors::{ChangePeriod, DatasetLoader};
 use crate::datasets::{Dataset, RealTimeDataset};
-use log::info;
+use actix::fut::{ActorFuture, WrapFuture};
+use actix::prelude::ContextFutureSpawner;
+use actix::{Addr, AsyncContext};
+use log::{error, info};
 use std::sync::Arc;
 
 /// Actor whose role is to:
 ///  * give a pointer to a Dataset (on the GetDataset Message)
 ///  * update the pointer to a new Dataset (on the UpdateBaseSchedule Message)
+///  * slide the period of the timetable at midnight in the dataset's timezone
 pub struct DatasetActor {
     pub gtfs: Arc<Result<Dataset, anyhow::Error>>,
     pub realtime: Arc<RealTimeDataset>,
+    // Address of the DatasetLoader, building the timetables in its own threads
+    dataset_loader: Addr<DatasetLoader>,
+    sliding_period: bool,
 }
 
 impl actix::Actor for DatasetActor {
     type Context = actix::Context<Self>;
-    fn started(&mut self, _ctx: &mut Self::Context) {
+    fn started(&mut self, ctx: &mut Self::Context) {
         info!("Starting the context actor");
+        ctx.run_interval(std::time::Duration::from_secs(60), |act, ctx| {
+            act.slide_period(ctx);
+        });
+    }
+}
+
+impl DatasetActor {
+    pub fn new(
+        gtfs: Arc<Result<Dataset, anyhow::Error>>,
+        realtime: Arc<RealTimeDataset>,
+        dataset_loader: Addr<DatasetLoader>,
+    ) -> Self {
+        Self {
+            gtfs,
+            realtime,
+            dataset_loader,
+            sliding_period: false,
+        }
+    }
+
+    /// Regenerate the timetable if its period has changed, without reading the base schedule again
+    /// The timetable is built by the DatasetLoader, the current one is served in the meantime
+    fn slide_period(&mut self, ctx: &mut actix::Context<Self>) {
+        if self.sliding_period {
+            return;
+        }
+        if let Ok(dataset) = &*self.gtfs {
+            let period = dataset.current_period();
+            if period.begin != dataset.timetable.period.begin {
+                info!("sliding the timetable on {:?}", &period);
+                self.sliding_period = true;
+                let previous_dataset = self.gtfs.clone();
+                self.dataset_loader
+                    .send(ChangePeriod {
+                        dataset: previous_dataset.clone(),
+                        period,
+                    })
+                    .into_actor(self)
+                    .map(move |res, act, _ctx| {
+                        act.sliding_period = false;
+                        match res {
+                            // the dataset is not replaced if it has been reloaded in the meantime
+                            Ok(Ok(dataset)) => {
+                                if Arc::ptr_eq(&act.gtfs, &previous_dataset) {
+                                    act.gtfs = Arc::new(Ok(dataset));
+                                }
+                            }
+                            Ok(Err(e)) => error!("impossible to slide the timetable: {}", e),
+                            Err(e) => error!("impossible to slide the timetable: {}", e),
+                        }
+                    })
+                    .spawn(ctx);
+            }
+        }
     }
 }
 
@@ -45,3 +107,45 @@ impl actix::Handler<GetRealtimeDataset> for DatasetActor {
         self.realtime.clone()
     }
 }
+
+/// Summary of the data held by a DatasetActor, given by the admin api
+#[derive(Serialize, Debug, Clone)]
+pub struct DatasetState {
+    pub loaded_at: Option<chrono::DateTime<chrono::Utc>>,
+    /// Version of the base schedules (ETag, Last-Modified date or hash of the files)
+    pub source_version: Option<String>,
+    /// Error of the last base schedule loading, if the dataset is not available
+    pub error: Option<String>,
+    pub period_begin: Option<chrono::NaiveDate>,
+    pub period_end: Option<chrono::NaiveDate>,
+    pub nb_connections: usize,
+    /// Fetching time of the realtime data
+    pub realtime_datetime: Option<chrono::DateTime<chrono::Utc>>,
+    pub nb_realtime_connections: usize,
+}
+
+#[derive(actix::Message)]
+#[rtype(result = "DatasetState")]
+pub struct GetDatasetState;
+
+impl actix::Handler<GetDatasetState> for DatasetActor {
+    type Result = actix::MessageResult<GetDatasetState>;
+
+    fn handle(
+        &mut self,
+        _params: GetDatasetState,
+        _ctx: &mut actix::Context<Self>,
+    ) -> Self::Result {
+        let dataset = self.gtfs.as_ref().as_ref();
+        actix::MessageResult(DatasetState {
+            loaded_at: dataset.ok().map(|d| d.loaded_at),
+            source_version: dataset.ok().and_then(|d| d.source_version.clone()),
+            error: dataset.err().map(|e| e.to_string()),
+            period_begin: dataset.ok().map(|d| d.timetable.period.begin),
+            period_end: dataset.ok().map(|d| d.timetable.period.end()),
+            nb_connections: dataset.map_or(0, |d| d.timetable.connections.len()),
+            realtime_datetime: self.realtime.gtfs_rt.as_ref().map(|rt| rt.datetime),
+            nb_realtime_connections: self.realtime.updated_timetable.realtime_connections.len(),
+        })
+    }
+}
@@ -0,0 +1,114 @@
+use crate::datasets::{Dataset, DatasetInfo, GenerationPeriod, Period};
+use actix::{Addr, SyncArbiter};
+use anyhow::anyhow;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+
+/// Actor whose role is to read the base schedules and to build the timetables
+///
+/// It is a sync actor, run in its own threads (with a `SyncArbiter`),
+/// so the other actors are not blocked while a big GTFS is read.
+/// The number of threads limits the number of datasets loaded at the same time,
+/// to bound the memory peaks, the other loadings wait in the mailbox.
+pub struct DatasetLoader {
+    loads: Arc<LoadsCounter>,
+}
+
+impl DatasetLoader {
+    /// Start the loader in `max_concurrent_loads` threads
+    ///
+    /// The base schedules being read are counted in `loads`, shared by all the threads
+    pub fn start_threads(max_concurrent_loads: usize, loads: Arc<LoadsCounter>) -> Addr<Self> {
+        SyncArbiter::start(max_concurrent_loads.max(1), move || Self {
+            loads: loads.clone(),
+        })
+    }
+}
+
+impl actix::Actor for DatasetLoader {
+    type Context = actix::SyncContext<Self>;
+}
+
+/// Number of base schedules read at the same time by the `DatasetLoader`
+#[derive(Default)]
+pub struct LoadsCounter {
+    in_progress: AtomicUsize,
+    peak: AtomicUsize,
+}
+
+impl LoadsCounter {
+    fn start(&self) -> usize {
+        let in_progress = self.in_progress.fetch_add(1, Ordering::SeqCst) + 1;
+        self.peak.fetch_max(in_progress, Ordering::SeqCst);
+        in_progress
+    }
+
+    fn end(&self) {
+        self.in_progress.fetch_sub(1, Ordering::SeqCst);
+    }
+
+    /// Number of base schedules being read
+    pub fn in_progress(&self) -> usize {
+        self.in_progress.load(Ordering::SeqCst)
+    }
+
+    /// Maximum number of base schedules read at the same time
+    pub fn peak(&self) -> usize {
+        self.peak.load(Ordering::SeqCst)
+    }
+}
+
+/// Message to read the base schedule of a dataset and build its timetable
+pub struct LoadDataset {
+    pub dataset_info: DatasetInfo,
+    pub generation_period: GenerationPeriod,
+    pub source_version: Option<String>,
+    pub log: slog::Logger,
+}
+
+impl actix::Message for LoadDataset {
+    type Result = Result<Dataset, anyhow::Error>;
+}
+
+impl actix::Handler<LoadDataset> for DatasetLoader {
+    type Result = Result<Dataset, anyhow::Error>;
+
+    fn handle(&mut self, msg: LoadDataset, _ctx: &mut actix::SyncContext<Self>) -> Self::Result {
+        let in_progress = self.loads.start();
+        slog::info!(
+            msg.log,
+            "loading the base schedule ({} loading(s) in progress)",
+            in_progress
+        );
+        let dataset = slog_scope::scope(&msg.log, || {
+            Dataset::try_from_dataset_info(
+                msg.dataset_info,
+                &msg.generation_period,
+                msg.source_version,
+            )
+        });
+        self.loads.end();
+        dataset
+    }
+}
+
+/// Message to build the timetable of a dataset on another period, without reading its base schedule again
+pub struct ChangePeriod {
+    pub dataset: Arc<Result<Dataset, anyhow::Error>>,
+    pub period: Period,
+}
+
+impl actix::Message for ChangePeriod {
+    type Result = Result<Dataset, anyhow::Error>;
+}
+
+impl actix::Handler<ChangePeriod> for DatasetLoader {
+    type Result = Result<Dataset, anyhow::Error>;
+
+    fn handle(&mut self, msg: ChangePeriod, _ctx: &mut actix::SyncContext<Self>) -> Self::Result {
+        match &*msg.dataset {
+            Ok(dataset) => Ok(dataset.with_period(&msg.period)),
+            Err(e) => Err(anyhow!("the dataset is not available: {}", e)),
+        }
+    }
+}
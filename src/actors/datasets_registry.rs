@@ -0,0 +1,235 @@
+use crate::actors::DatasetLoader;
+use crate::datasets::{DatasetInfo, Datasets, GenerationPeriod};
+use crate::server::{create_dataset_actors, DatasetActors};
+use actix::fut::{ActorFuture, WrapFuture};
+use actix::prelude::ContextFutureSpawner;
+use actix::Addr;
+use log::{error, info};
+use openapi_schema::OpenapiSchema;
+use std::collections::BTreeMap;
+
+/// Readiness of a dataset
+#[derive(Serialize, Debug, Clone, Copy, PartialEq, Eq, OpenapiSchema)]
+#[allow(non_camel_case_types)]
+pub enum DatasetStatus {
+    /// The dataset is being loaded, its routes are not available yet
+    loading,
+    /// The dataset is loaded and served
+    ready,
+}
+
+/// Actor whose role is to:
+///  * give the actors of a dataset from its id, for the routes to be dispatched at request time
+///  * create the actors of the new or changed datasets of the configuration in the background,
+///    and stop the removed ones
+pub struct DatasetsRegistry {
+    /// The loaded datasets, by id
+    datasets: BTreeMap<String, (DatasetInfo, DatasetActors)>,
+    generation_period: GenerationPeriod,
+    dataset_loader: Addr<DatasetLoader>,
+    /// Last configuration received, the datasets created for an older configuration are dropped
+    configuration: Vec<DatasetInfo>,
+    /// The datasets being loaded, by id
+    pending: BTreeMap<String, DatasetInfo>,
+    /// Internal hosts on which the subscriptions' notifications can be pushed
+    subscription_allowed_hosts: Vec<String>,
+}
+
+impl DatasetsRegistry {
+    pub fn new(
+        generation_period: GenerationPeriod,
+        dataset_loader: Addr<DatasetLoader>,
+        subscription_allowed_hosts: Vec<String>,
+    ) -> Self {
+        Self {
+            datasets: BTreeMap::new(),
+            generation_period,
+            dataset_loader,
+            configuration: vec![],
+            pending: BTreeMap::new(),
+            subscription_allowed_hosts,
+        }
+    }
+
+    fn end_loading(&mut self, dataset_info: &DatasetInfo) {
+        if self.pending.get(&dataset_info.id) == Some(dataset_info) {
+            self.pending.remove(&dataset_info.id);
+        }
+    }
+
+    fn loading_failed(&mut self, dataset_info: &DatasetInfo) {
+        // the dataset is listed with the configuration it is still served with, if any
+        let previous = self.datasets.get(&dataset_info.id).map(|(d, _)| d.clone());
+        match previous {
+            Some(previous) => {
+                for d in self
+                    .configuration
+                    .iter_mut()
+                    .filter(|d| **d == *dataset_info)
+                {
+                    *d = previous.clone();
+                }
+            }
+            None => self.configuration.retain(|d| d != dataset_info),
+        }
+    }
+
+    fn add_dataset(&mut self, dataset_info: DatasetInfo, actors: DatasetActors) {
+        if !self.configuration.contains(&dataset_info) {
+            info!(
+                "dataset {} is not in the configuration anymore, it is dropped",
+                &dataset_info.id
+            );
+            actors.stop();
+            return;
+        }
+        info!("dataset {} is now available", &dataset_info.id);
+        if let Some((_, previous_actors)) = self
+            .datasets
+            .insert(dataset_info.id.clone(), (dataset_info, actors))
+        {
+            previous_actors.stop();
+        }
+    }
+}
+
+impl actix::Actor for DatasetsRegistry {
+    type Context = actix::Context<Self>;
+}
+
+/// Message to get the configuration and the readiness of all the datasets
+///
+/// Note: a changed dataset is given with its previous configuration until the new one is loaded
+#[derive(actix::Message)]
+#[rtype(result = "Vec<(DatasetInfo, DatasetStatus)>")]
+pub struct GetDatasets;
+
+impl actix::Handler<GetDatasets> for DatasetsRegistry {
+    type Result = actix::MessageResult<GetDatasets>;
+
+    fn handle(&mut self, _msg: GetDatasets, _ctx: &mut actix::Context<Self>) -> Self::Result {
+        actix::MessageResult(
+            self.configuration
+                .iter()
+                .map(|d| match self.datasets.get(&d.id) {
+                    Some((loaded_dataset, _)) => (loaded_dataset.clone(), DatasetStatus::ready),
+                    None => (d.clone(), DatasetStatus::loading),
+                })
+                .collect(),
+        )
+    }
+}
+
+/// A dataset found in the registry from its id
+pub enum DatasetLookup {
+    Ready(DatasetInfo, DatasetActors),
+    Loading(DatasetInfo),
+    NotFound,
+}
+
+/// Message to get the configuration and the actors of a dataset from its id
+#[derive(actix::Message)]
+#[rtype(result = "DatasetLookup")]
+pub struct GetDatasetActors(pub String);
+
+impl actix::Handler<GetDatasetActors> for DatasetsRegistry {
+    type Result = actix::MessageResult<GetDatasetActors>;
+
+    fn handle(&mut self, msg: GetDatasetActors, _ctx: &mut actix::Context<Self>) -> Self::Result {
+        let id = msg.0;
+        actix::MessageResult(match self.datasets.get(&id) {
+            Some((dataset_info, actors)) => {
+                DatasetLookup::Ready(dataset_info.clone(), actors.clone())
+            }
+            None => self
+                .configuration
+                .iter()
+                .find(|d| d.id == id)
+                .map_or(DatasetLookup::NotFound, |d| {
+                    DatasetLookup::Loading(d.clone())
+                }),
+        })
+    }
+}
+
+/// Message sent with the configuration, at startup and when it has been read again
+///
+/// The removed datasets are stopped right away, while the added or changed datasets
+/// are loaded in the background. A changed dataset is still served with its previous
+/// configuration until the new one is loaded, and keeps it if the new one cannot be loaded.
+/// A dataset already being loaded with the same configuration is not loaded again.
+#[derive(actix::Message)]
+#[rtype(result = "()")]
+pub struct UpdateDatasets(pub Datasets);
+
+impl actix::Handler<UpdateDatasets> for DatasetsRegistry {
+    type Result = ();
+
+    fn handle(&mut self, msg: UpdateDatasets, ctx: &mut actix::Context<Self>) -> Self::Result {
+        self.configuration = msg.0.datasets;
+
+        let configuration = &self.configuration;
+        let removed_datasets: Vec<String> = self
+            .datasets
+            .keys()
+            .filter(|id| configuration.iter().all(|d| &d.id != *id))
+            .cloned()
+            .collect();
+        for id in removed_datasets {
+            info!("dataset {} has been removed from the configuration", &id);
+            if let Some((_, actors)) = self.datasets.remove(&id) {
+                actors.stop();
+            }
+        }
+
+        for dataset_info in &self.configuration {
+            let unchanged = self
+                .datasets
+                .get(&dataset_info.id)
+                .map_or(false, |(d, _)| d == dataset_info);
+            if unchanged {
+                continue;
+            }
+            if self.pending.get(&dataset_info.id) == Some(dataset_info) {
+                info!("dataset {} is already being loaded", &dataset_info.id);
+                continue;
+            }
+            self.pending
+                .insert(dataset_info.id.clone(), dataset_info.clone());
+            info!(
+                "dataset {} is new or has changed, loading it",
+                &dataset_info.id
+            );
+            let replaces_dataset = self.datasets.contains_key(&dataset_info.id);
+            let dataset_info = dataset_info.clone();
+            let generation_period = self.generation_period.clone();
+            let dataset_loader = self.dataset_loader.clone();
+            let subscription_allowed_hosts = self.subscription_allowed_hosts.clone();
+            async move {
+                create_dataset_actors(
+                    dataset_info,
+                    &generation_period,
+                    &dataset_loader,
+                    replaces_dataset,
+                    &subscription_allowed_hosts,
+                )
+                .await
+            }
+            .into_actor(self)
+            .map(|(dataset_info, actors), act, _ctx| {
+                act.end_loading(&dataset_info);
+                match actors {
+                    Ok(actors) => act.add_dataset(dataset_info, actors),
+                    Err(e) => {
+                        let msg =
+                            format!("impossible to create dataset {}: {}", &dataset_info.id, e);
+                        sentry::capture_message(&msg, sentry::Level::Error);
+                        error!("{}", &msg);
+                        act.loading_failed(&dataset_info);
+                    }
+                }
+            })
+            .spawn(ctx);
+        }
+    }
+}
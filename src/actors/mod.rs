@@ -1,8 +1,24 @@
 mod dataset_handler_actor;
+mod dataset_loader;
+mod datasets_registry;
 mod realtime_update_actors;
+mod reloader_state;
+mod shutdown;
+mod subscription_actor;
 mod update_actors;
 
 // we reexport the actors
-pub use self::dataset_handler_actor::{DatasetActor, GetDataset, GetRealtimeDataset};
+pub use self::dataset_handler_actor::{
+    DatasetActor, DatasetState, GetDataset, GetDatasetState, GetRealtimeDataset,
+};
+pub use self::dataset_loader::{ChangePeriod, DatasetLoader, LoadDataset, LoadsCounter};
+pub use self::datasets_registry::{
+    DatasetLookup, DatasetStatus, DatasetsRegistry, GetDatasetActors, GetDatasets, UpdateDatasets,
+};
 pub use self::realtime_update_actors::RealTimeReloader;
+pub use self::reloader_state::{ForceReload, GetReloaderState, ReloaderState, SetPaused};
+pub use self::shutdown::Shutdown;
+pub use self::subscription_actor::{
+    NewRealTimeDataset, Subscribe, SubscriptionActor, TerminateSubscription,
+};
 pub use self::update_actors::BaseScheduleReloader;
@@ -1,10 +1,14 @@
-use crate::actors::{DatasetActor, GetDataset};
+use crate::actors::{
+    DatasetActor, ForceReload, GetDataset, GetRealtimeDataset, GetReloaderState,
+    NewRealTimeDataset, ReloaderState, SetPaused, SubscriptionActor,
+};
 use crate::datasets::{
-    Dataset, GtfsRT, RealTimeConnection, RealTimeDataset, ScheduleRelationship, UpdatedTimetable,
+    CancelledAlert, Dataset, GtfsRT, RealTimeConnection, RealTimeDataset, ScheduleRelationship,
+    UpdatedTimetable,
 };
 use crate::model_update;
 use crate::transit_realtime;
-use actix::fut::WrapFuture;
+use actix::fut::{ActorFuture, WrapFuture};
 use actix::prelude::ContextFutureSpawner;
 use actix::AsyncContext;
 use anyhow::{anyhow, Error};
@@ -12,6 +16,7 @@ use futures::future::join_all;
 use prost::Message;
 use sentry::integrations::anyhow::capture_anyhow;
 use slog::info;
+use std::collections::{BTreeMap, BTreeSet};
 use std::sync::Arc;
 
 /// Actor that once in a while reload the BaseSchedule data (GTFS)
@@ -25,12 +30,24 @@ pub struct RealTimeReloader {
     // NOte: for the moment it's a single Actor,
     // but if we have several instances of DatasetActor we could have a list of recipient here
     pub dataset_actor: actix::Addr<DatasetActor>,
+    // Address of the SubscriptionActor to notify for the subscribers to get the changes
+    pub subscription_actor: actix::Addr<SubscriptionActor>,
     pub log: slog::Logger,
+    pub state: ReloaderState,
 }
 
+const RELOADING_INTERVAL: std::time::Duration = std::time::Duration::from_secs(60);
+// a gtfs_rt provider not answering must not delay the next reloadings
+const FETCH_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(20);
+
 async fn fetch_gtfs_rt(url: &str, log: &slog::Logger) -> Result<GtfsRT, Error> {
     info!(log, "fetching a gtfs_rt");
-    let resp = reqwest::get(url)
+    let resp = reqwest::Client::builder()
+        .timeout(FETCH_TIMEOUT)
+        .build()
+        .map_err(|e| anyhow!("Unable to create the http client: {}", e))?
+        .get(url)
+        .send()
         .await
         .and_then(reqwest::Response::error_for_status)
         .map_err(|e| anyhow!("Unable to fetch GTFS: {}", e))?;
@@ -70,6 +87,75 @@ fn aggregate_rts(feed_messages: &[transit_realtime::FeedMessage]) -> Result<Gtfs
     })
 }
 
+// ids of the alerts of a feed, without the deleted ones
+fn alert_ids(feed: &transit_realtime::FeedMessage) -> BTreeSet<String> {
+    feed.entity
+        .iter()
+        .filter(|e| e.alert.is_some() && e.is_deleted != Some(true))
+        .map(|e| e.id.clone())
+        .collect()
+}
+
+// The alerts of the previous realtime data that are not in their feed anymore
+// and the alerts marked as deleted in the new feeds are cancelled
+// Note: only the feeds fetched in this cycle are compared to their previous alerts,
+// the alerts of a feed that could not be fetched are not cancelled
+fn get_cancelled_alerts(
+    previous_alerts: &BTreeMap<String, BTreeSet<String>>,
+    feeds: &[(&str, &transit_realtime::FeedMessage)],
+    now: chrono::DateTime<chrono::Utc>,
+) -> Vec<CancelledAlert> {
+    let all_previous_alerts: BTreeSet<&str> = previous_alerts
+        .values()
+        .flatten()
+        .map(String::as_str)
+        .collect();
+    let entities = || feeds.iter().flat_map(|(_, f)| f.entity.iter());
+    let current_alerts: BTreeSet<&str> = entities()
+        .filter(|e| e.alert.is_some() && e.is_deleted != Some(true))
+        .map(|e| e.id.as_str())
+        .collect();
+    // an alert can move from a feed to another, it is not cancelled
+    let removed_alerts = feeds
+        .iter()
+        .filter_map(|(url, _)| previous_alerts.get(*url))
+        .flatten()
+        .map(String::as_str)
+        .filter(|id| !current_alerts.contains(id));
+    // a deleted entity does not always contain its alert, so we also rely on the previous alerts
+    let deleted_alerts = entities()
+        .filter(|e| e.is_deleted == Some(true))
+        .filter(|e| e.alert.is_some() || all_previous_alerts.contains(e.id.as_str()))
+        .map(|e| e.id.as_str());
+
+    removed_alerts
+        .chain(deleted_alerts)
+        .collect::<BTreeSet<_>>()
+        .into_iter()
+        .map(|id| CancelledAlert {
+            id: id.to_owned(),
+            cancellation_time: now,
+        })
+        .collect()
+}
+
+// The alerts of each feed, the feeds that could not be fetched keep their previous alerts
+fn get_alerts_by_url(
+    previous_alerts: &BTreeMap<String, BTreeSet<String>>,
+    feeds: &[(&str, &transit_realtime::FeedMessage)],
+    urls: &[String],
+) -> BTreeMap<String, BTreeSet<String>> {
+    let mut alerts_by_url: BTreeMap<String, BTreeSet<String>> = previous_alerts
+        .iter()
+        .filter(|(url, _)| urls.contains(*url))
+        .map(|(url, alerts)| (url.clone(), alerts.clone()))
+        .collect();
+    for (url, feed) in feeds {
+        alerts_by_url.insert((*url).to_owned(), alert_ids(feed));
+    }
+    alerts_by_url
+}
+
 // modify the generated timetable with a given GTFS-RT
 // Since the connection are sorted by scheduled departure time we don't need to reorder the connections, we can update them in place
 // For each trip update, we only have to find the corresponding connection and update it.
@@ -84,13 +170,28 @@ fn apply_rt_update(
     };
     let mut updated_timetable = UpdatedTimetable::default();
 
-    let parsed_trip_update = model_update::get_model_update(&data.ntm, gtfs_rts, data.timezone)?;
+    let prefixes = data.feed_construction_info.dataset_info.id_prefixes();
+    let parsed_trip_update =
+        model_update::get_model_update(&data.ntm, &prefixes, gtfs_rts, &data.timezones)?;
     let mut nb_changes = 0;
     let mut cpt_incoherent_stops_id = 0;
 
     for (idx, connection) in data.timetable.connections.iter().enumerate() {
         let trip_update = parsed_trip_update.trips.get(&connection.dated_vj);
         if let Some(trip_update) = trip_update {
+            if trip_update.cancelled {
+                updated_timetable.realtime_connections.insert(
+                    idx,
+                    RealTimeConnection {
+                        dep_time: None,
+                        arr_time: None,
+                        schedule_relationship: ScheduleRelationship::Cancelled,
+                        update_time: trip_update.update_dt,
+                    },
+                );
+                nb_changes += 1;
+                continue;
+            }
             let stop_time_update = trip_update
                 .stop_time_update_by_sequence
                 .get(&connection.sequence);
@@ -113,7 +214,11 @@ fn apply_rt_update(
                     RealTimeConnection {
                         dep_time: stop_time_update.updated_departure,
                         arr_time: stop_time_update.updated_arrival,
-                        schedule_relationship: ScheduleRelationship::Scheduled,
+                        schedule_relationship: if stop_time_update.skipped {
+                            ScheduleRelationship::Skipped
+                        } else {
+                            ScheduleRelationship::Scheduled
+                        },
                         update_time: trip_update.update_dt,
                     },
                 );
@@ -148,31 +253,53 @@ impl RealTimeReloader {
             .send(GetDataset)
             .await
             .map_err(|e| anyhow!("maibox error: {}", e))?;
+        let previous_rt_dataset = self
+            .dataset_actor
+            .send(GetRealtimeDataset)
+            .await
+            .map_err(|e| anyhow!("maibox error: {}", e))?;
 
-        self.apply_rt(dataset).await
+        self.apply_rt(dataset, &previous_rt_dataset).await
     }
 
     /// fetch the gtfs-rts and apply them to the current dataset
     /// send the newly created RealTimeDataset to the DatasetActor to update it.
-    /// This method has to be called at the actor creation
-    /// and then will be scheduled to run regularely
-    pub async fn update_realtime_data(&self) {
+    /// This method is called at the actor start
+    /// and then is scheduled to run regularely
+    /// The error is logged before being returned for the state of the reloader
+    pub async fn update_realtime_data(&self) -> anyhow::Result<()> {
         sentry::Hub::current().configure_scope(|scope| {
             scope.set_tag("dataset", &self.dataset_id);
         });
         let res = self.update_realtime_data_impl().await;
-        match res {
+        match &res {
             Ok(()) => {
                 info!(self.log, "realtime reloaded");
             }
             Err(e) => {
                 slog::error!(self.log, "unable to apply realtime update due to: {}", e);
-                capture_anyhow(&e);
+                capture_anyhow(e);
             }
         }
+        res
     }
 
-    async fn apply_rt(&self, dataset: Arc<Result<Dataset, anyhow::Error>>) -> Result<(), Error> {
+    fn reload(&self, ctx: &mut actix::Context<Self>) {
+        // Note: The actor is cloned there because of lifetime issue.
+        // There should be a way to avoid this, but at the time of the writing
+        // no better solution to make the future 'static was found
+        let cloned = self.clone();
+        async move { cloned.update_realtime_data().await }
+            .into_actor(self)
+            .map(|res, act, _ctx| act.state.record(&res))
+            .wait(ctx);
+    }
+
+    async fn apply_rt(
+        &self,
+        dataset: Arc<Result<Dataset, anyhow::Error>>,
+        previous_rt_dataset: &RealTimeDataset,
+    ) -> Result<(), Error> {
         let gtfs_rts = self
             .gtfs_rt_urls
             .iter()
@@ -182,34 +309,54 @@ impl RealTimeReloader {
         let gtfs_rts = join_all(gtfs_rts)
             .await
             .into_iter()
-            .filter_map(|rt| rt.map_err(|e| slog::warn!(self.log, "{}", e)).ok())
+            .zip(self.gtfs_rt_urls.iter())
+            .filter_map(|(rt, url)| {
+                rt.map(|rt| (url.clone(), rt))
+                    .map_err(|e| slog::warn!(self.log, "{}", e))
+                    .ok()
+            })
             .collect();
 
-        let rt_dataset = self.make_rt_dataset(dataset, gtfs_rts)?;
+        let rt_dataset = Arc::new(self.make_rt_dataset(dataset, gtfs_rts, previous_rt_dataset)?);
         // we send those data as a BaseScheduleReloader message, for the DatasetActor to load those new data
         self.dataset_actor
-            .do_send(UpdateRealtime(Arc::new(rt_dataset)));
+            .do_send(UpdateRealtime(rt_dataset.clone()));
+        // and the subscribers are notified of the changes
+        self.subscription_actor
+            .do_send(NewRealTimeDataset(rt_dataset));
         Ok(())
     }
 
+    /// `gtfs_rts` are the feeds fetched in this cycle, with their url
     fn make_rt_dataset(
         &self,
         dataset: Arc<Result<Dataset, anyhow::Error>>,
-        gtfs_rts: Vec<GtfsRT>,
+        gtfs_rts: Vec<(String, GtfsRT)>,
+        previous_rt_dataset: &RealTimeDataset,
     ) -> Result<RealTimeDataset, Error> {
-        let feed_messages: Vec<transit_realtime::FeedMessage> = gtfs_rts
+        let (urls, feed_messages): (Vec<String>, Vec<transit_realtime::FeedMessage>) = gtfs_rts
+            .iter()
+            .filter_map(|(url, rt)| rt.decode_feed_message().map(|f| (url.clone(), f)))
+            .unzip();
+        let feeds: Vec<(&str, &transit_realtime::FeedMessage)> = urls
             .iter()
-            .filter_map(GtfsRT::decode_feed_message)
+            .map(String::as_str)
+            .zip(feed_messages.iter())
             .collect();
 
         let gtfs_rt = aggregate_rts(&feed_messages)?;
         let updated_timetable = apply_rt_update(&dataset, &feed_messages, &self.log)?;
+        let previous_alerts = &previous_rt_dataset.alerts_by_url;
+        let cancelled_alerts = get_cancelled_alerts(previous_alerts, &feeds, chrono::Utc::now());
+        let alerts_by_url = get_alerts_by_url(previous_alerts, &feeds, &self.gtfs_rt_urls);
 
         Ok(RealTimeDataset {
             base_schedule_dataset: dataset,
             gtfs_rt: Some(gtfs_rt),
             gtfs_rt_provider_urls: self.gtfs_rt_urls.clone(),
             updated_timetable,
+            cancelled_alerts,
+            alerts_by_url,
         })
     }
 }
@@ -220,19 +367,51 @@ impl actix::Actor for RealTimeReloader {
     fn started(&mut self, ctx: &mut Self::Context) {
         info!(self.log, "Realtime updater actor started");
 
-        ctx.run_interval(std::time::Duration::from_secs(60), |act, ctx| {
+        // the dataset is already served with its base schedule,
+        // the gtfs_rt feeds are fetched a first time in the background
+        self.reload(ctx);
+        self.state.schedule(RELOADING_INTERVAL);
+        ctx.run_interval(RELOADING_INTERVAL, |act, ctx| {
+            act.state.schedule(RELOADING_INTERVAL);
+            if act.state.paused {
+                info!(
+                    act.log,
+                    "reloading paused, the realtime data are not reloaded"
+                );
+                return;
+            }
             info!(act.log, "reloading realtime data");
-            // Note: The actor is cloned there because of lifetime issue.
-            // There should be a way to avoid this, but at the time of the writing
-            // no better solution to make the future 'static was found
-            let cloned = act.clone();
-            async move { cloned.update_realtime_data().await }
-                .into_actor(act)
-                .wait(ctx);
+            act.reload(ctx);
         });
     }
 }
 
+impl actix::Handler<GetReloaderState> for RealTimeReloader {
+    type Result = actix::MessageResult<GetReloaderState>;
+
+    fn handle(&mut self, _msg: GetReloaderState, _ctx: &mut Self::Context) -> Self::Result {
+        actix::MessageResult(self.state.clone())
+    }
+}
+
+impl actix::Handler<SetPaused> for RealTimeReloader {
+    type Result = ();
+
+    fn handle(&mut self, msg: SetPaused, _ctx: &mut Self::Context) -> Self::Result {
+        info!(self.log, "realtime reloading paused: {}", msg.0);
+        self.state.paused = msg.0;
+    }
+}
+
+impl actix::Handler<ForceReload> for RealTimeReloader {
+    type Result = ();
+
+    fn handle(&mut self, _msg: ForceReload, ctx: &mut Self::Context) -> Self::Result {
+        info!(self.log, "forced reloading of the realtime data");
+        self.reload(ctx);
+    }
+}
+
 /// Message send to a DatasetActor to update its baseschedule data
 struct UpdateRealtime(Arc<RealTimeDataset>);
 
@@ -247,3 +426,112 @@ impl actix::Handler<UpdateRealtime> for DatasetActor {
         self.realtime = params.0;
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::{get_alerts_by_url, get_cancelled_alerts};
+    use crate::datasets::CancelledAlert;
+    use crate::transit_realtime::{Alert, FeedEntity, FeedMessage};
+    use std::collections::{BTreeMap, BTreeSet};
+
+    fn alert(id: &str) -> FeedEntity {
+        FeedEntity {
+            id: id.to_owned(),
+            alert: Some(Alert::default()),
+            ..Default::default()
+        }
+    }
+
+    fn deleted(id: &str) -> FeedEntity {
+        FeedEntity {
+            id: id.to_owned(),
+            is_deleted: Some(true),
+            ..Default::default()
+        }
+    }
+
+    fn feed(entity: Vec<FeedEntity>) -> FeedMessage {
+        FeedMessage {
+            entity,
+            ..Default::default()
+        }
+    }
+
+    fn alerts(alerts_by_url: &[(&str, &[&str])]) -> BTreeMap<String, BTreeSet<String>> {
+        alerts_by_url
+            .iter()
+            .map(|(url, ids)| {
+                (
+                    url.to_string(),
+                    ids.iter().map(|id| id.to_string()).collect(),
+                )
+            })
+            .collect()
+    }
+
+    fn cancelled(ids: &[&str], now: chrono::DateTime<chrono::Utc>) -> Vec<CancelledAlert> {
+        ids.iter()
+            .map(|id| CancelledAlert {
+                id: id.to_string(),
+                cancellation_time: now,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn removed_and_deleted_alerts_are_cancelled() {
+        let now = chrono::Utc::now();
+        let previous = alerts(&[("url1", &["a", "b"]), ("url2", &["c"])]);
+        let feed1 = feed(vec![alert("a")]);
+        let feed2 = feed(vec![deleted("c"), deleted("d")]);
+        let current = vec![("url1", &feed1), ("url2", &feed2)];
+
+        // 'b' disappeared, 'c' is deleted, 'd' is not known as an alert
+        assert_eq!(
+            get_cancelled_alerts(&previous, &current, now),
+            cancelled(&["b", "c"], now)
+        );
+        assert_eq!(
+            get_alerts_by_url(&previous, &current, &["url1".to_owned(), "url2".to_owned()]),
+            alerts(&[("url1", &["a"]), ("url2", &[])])
+        );
+    }
+
+    #[test]
+    fn alerts_of_a_failing_feed_are_not_cancelled() {
+        let now = chrono::Utc::now();
+        let previous = alerts(&[("url1", &["a", "b"]), ("url2", &["c"])]);
+        // the alert 'a' moved to the second feed
+        let feed2 = feed(vec![alert("a")]);
+        // the first feed could not be fetched
+        let current = vec![("url2", &feed2)];
+
+        // only 'c' has disappeared from a fetched feed
+        assert_eq!(
+            get_cancelled_alerts(&previous, &current, now),
+            cancelled(&["c"], now)
+        );
+        // the first feed keeps its alerts until it is fetched again
+        assert_eq!(
+            get_alerts_by_url(&previous, &current, &["url1".to_owned(), "url2".to_owned()]),
+            alerts(&[("url1", &["a", "b"]), ("url2", &["a"])])
+        );
+
+        // when it is fetched again, its removed alerts are cancelled
+        let previous =
+            get_alerts_by_url(&previous, &current, &["url1".to_owned(), "url2".to_owned()]);
+        let feed1 = feed(vec![]);
+        let current = vec![("url1", &feed1), ("url2", &feed2)];
+        assert_eq!(
+            get_cancelled_alerts(&previous, &current, now),
+            cancelled(&["b"], now)
+        );
+    }
+
+    #[test]
+    fn no_cancellation_without_previous_feed() {
+        let feed1 = feed(vec![alert("a")]);
+        let current = vec![("url1", &feed1)];
+        assert!(get_cancelled_alerts(&BTreeMap::new(), &current, chrono::Utc::now()).is_empty());
+    }
+}
@@ -0,0 +1,51 @@
+use chrono::{DateTime, Utc};
+
+/// State of a reloader, given by the admin api
+#[derive(Serialize, Debug, Clone, Default)]
+pub struct ReloaderState {
+    /// Last time the data have been reloaded
+    pub last_success: Option<DateTime<Utc>>,
+    /// Last time the reloading failed
+    pub last_failure: Option<DateTime<Utc>>,
+    /// Error of the last failure
+    pub last_error: Option<String>,
+    /// Next periodic reloading, skipped if the reloader is paused
+    pub next_run: Option<DateTime<Utc>>,
+    /// The periodic reloading is paused
+    pub paused: bool,
+}
+
+impl ReloaderState {
+    /// Keep the result of a reloading
+    pub fn record<T>(&mut self, result: &Result<T, anyhow::Error>) {
+        match result {
+            Ok(_) => self.last_success = Some(Utc::now()),
+            Err(e) => {
+                self.last_failure = Some(Utc::now());
+                self.last_error = Some(e.to_string());
+            }
+        }
+    }
+
+    /// Schedule the next periodic reloading
+    pub fn schedule(&mut self, interval: std::time::Duration) {
+        self.next_run = chrono::Duration::from_std(interval)
+            .ok()
+            .map(|interval| Utc::now() + interval);
+    }
+}
+
+/// Message to get the state of a reloader
+#[derive(actix::Message)]
+#[rtype(result = "ReloaderState")]
+pub struct GetReloaderState;
+
+/// Message to pause (or resume) the periodic reloading of a reloader
+#[derive(actix::Message)]
+#[rtype(result = "()")]
+pub struct SetPaused(pub bool);
+
+/// Message to reload the data of a reloader right now, even if it is paused
+#[derive(actix::Message)]
+#[rtype(result = "()")]
+pub struct ForceReload;
@@ -0,0 +1,26 @@
+use crate::actors::{BaseScheduleReloader, DatasetActor, RealTimeReloader, SubscriptionActor};
+use actix::ActorContext;
+
+/// Message to stop an actor of a dataset, when the dataset is removed from the configuration
+///
+/// Note: the actors run periodic tasks, so they are not stopped when their addresses are dropped
+#[derive(actix::Message)]
+#[rtype(result = "()")]
+pub struct Shutdown;
+
+macro_rules! impl_shutdown {
+    ($actor:ty) => {
+        impl actix::Handler<Shutdown> for $actor {
+            type Result = ();
+
+            fn handle(&mut self, _msg: Shutdown, ctx: &mut actix::Context<Self>) -> Self::Result {
+                ctx.stop();
+            }
+        }
+    };
+}
+
+impl_shutdown!(DatasetActor);
+impl_shutdown!(BaseScheduleReloader);
+impl_shutdown!(RealTimeReloader);
+impl_shutdown!(SubscriptionActor);
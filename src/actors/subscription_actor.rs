@@ -0,0 +1,882 @@
+use crate::datasets::RealTimeDataset;
+use crate::extractors::get_siri_dataset;
+use crate::routes::{estimated_timetable, general_message, stop_monitoring};
+use crate::siri_lite::estimated_timetable::EstimatedVehicleJourney;
+use crate::siri_lite::general_message::InfoMessage;
+use crate::siri_lite::service_delivery::{MonitoredStopVisit, ServiceDelivery};
+use crate::siri_lite::shared::SiriError;
+use crate::siri_lite::subscription::{
+    EstimatedTimetableRequest, GeneralMessageRequest, HeartbeatNotification, ResponseStatus,
+    StopMonitoringRequest, SubscriptionParams, SubscriptionRequest, SubscriptionResponse,
+    SubscriptionTerminatedNotification, TerminateSubscriptionRequest,
+    TerminateSubscriptionResponse, TerminationResponseStatus,
+};
+use crate::siri_lite::{Siri, SiriResponse};
+use actix::AsyncContext;
+use chrono::NaiveDateTime;
+use slog::{info, warn};
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::net::{IpAddr, Ipv4Addr};
+use std::sync::Arc;
+
+/// Interval between 2 checks of the heartbeats and of the subscriptions' expiry
+const CHECK_INTERVAL: std::time::Duration = std::time::Duration::from_secs(10);
+/// Maximum number of subscriptions of a dataset, each notification is pushed to all of them
+const MAX_SUBSCRIPTIONS: usize = 1000;
+/// Maximum number of subscriptions of a subscriber, for one subscriber not to use all of them
+const MAX_SUBSCRIPTIONS_BY_REQUESTOR: usize = 100;
+
+#[derive(Debug, Clone)]
+enum SubscribedContent {
+    StopMonitoring(StopMonitoringRequest),
+    GeneralMessage(GeneralMessageRequest),
+    EstimatedTimetable(EstimatedTimetableRequest),
+}
+
+/// Item of a notification
+///
+/// An item is notified only if it is new, if its content has changed or if its expected times
+/// have changed more than the subscription's threshold since the last notification
+trait NotifiedItem {
+    fn key(&self) -> String;
+    fn expected_times(&self) -> Vec<Option<NaiveDateTime>>;
+    /// content of the item other than its expected times, any change of it is notified
+    fn content(&self) -> String {
+        String::new()
+    }
+}
+
+impl NotifiedItem for MonitoredStopVisit {
+    fn key(&self) -> String {
+        self.item_identifier.clone()
+    }
+    fn expected_times(&self) -> Vec<Option<NaiveDateTime>> {
+        self.monitored_vehicle_journey
+            .monitored_call
+            .as_ref()
+            .map(|c| {
+                vec![
+                    c.expected_arrival_time.as_ref().map(|d| d.0),
+                    c.expected_departure_time.as_ref().map(|d| d.0),
+                ]
+            })
+            .unwrap_or_else(Vec::new)
+    }
+}
+
+impl NotifiedItem for InfoMessage {
+    // the messages have no expected times, a message is notified when it appears or when it changes
+    fn key(&self) -> String {
+        self.info_message_identifier
+            .clone()
+            .or_else(|| self.item_identifier.clone())
+            .unwrap_or_default()
+    }
+    fn expected_times(&self) -> Vec<Option<NaiveDateTime>> {
+        vec![]
+    }
+    fn content(&self) -> String {
+        serde_json::to_string(self).unwrap_or_default()
+    }
+}
+
+impl NotifiedItem for EstimatedVehicleJourney {
+    fn key(&self) -> String {
+        self.dated_vehicle_journey_ref.clone()
+    }
+    fn expected_times(&self) -> Vec<Option<NaiveDateTime>> {
+        self.estimated_calls
+            .iter()
+            .flat_map(|c| {
+                vec![
+                    c.expected_arrival_time.as_ref().map(|d| d.0),
+                    c.expected_departure_time.as_ref().map(|d| d.0),
+                ]
+            })
+            .collect()
+    }
+}
+
+fn has_changed(
+    previous: &[Option<NaiveDateTime>],
+    current: &[Option<NaiveDateTime>],
+    threshold: chrono::Duration,
+) -> bool {
+    previous.len() != current.len()
+        || previous.iter().zip(current).any(|(p, c)| match (p, c) {
+            (Some(p), Some(c)) => {
+                let diff = (*c - *p).num_seconds().abs();
+                diff != 0 && diff >= threshold.num_seconds()
+            }
+            (None, None) => false,
+            _ => true,
+        })
+}
+
+/// An item as it was in the last notification
+struct SentItem {
+    expected_times: Vec<Option<NaiveDateTime>>,
+    content: String,
+}
+
+/// Keep only the items that are new or that have changed since the last notification
+///
+/// `sent_items` is updated with the notified items
+fn changed_items<T: NotifiedItem>(
+    items: Vec<T>,
+    sent_items: &mut HashMap<String, SentItem>,
+    threshold: chrono::Duration,
+) -> Vec<T> {
+    let mut new_sent_items = HashMap::new();
+    let mut changed = vec![];
+    for item in items {
+        let key = item.key();
+        let expected_times = item.expected_times();
+        let content = item.content();
+        match sent_items.remove(&key) {
+            // we keep the times of the last notification, for small changes to be able to add up
+            Some(previous)
+                if previous.content == content
+                    && !has_changed(&previous.expected_times, &expected_times, threshold) =>
+            {
+                new_sent_items.insert(key, previous);
+            }
+            _ => {
+                new_sent_items.insert(
+                    key,
+                    SentItem {
+                        expected_times,
+                        content,
+                    },
+                );
+                changed.push(item);
+            }
+        }
+    }
+    // the items not there anymore are forgotten
+    *sent_items = new_sent_items;
+    changed
+}
+
+struct Subscription {
+    subscriber_ref: String,
+    subscription_ref: String,
+    consumer_address: String,
+    content: SubscribedContent,
+    termination_time: chrono::DateTime<chrono::Utc>,
+    change_before_updates: chrono::Duration,
+    heartbeat_interval: Option<chrono::Duration>,
+    last_heartbeat: chrono::DateTime<chrono::Utc>,
+    /// items sent to the subscriber, used to only notify the changes
+    sent_items: HashMap<String, SentItem>,
+}
+
+impl Subscription {
+    fn filter_changes(&mut self, delivery: &mut ServiceDelivery) -> bool {
+        let threshold = self.change_before_updates;
+        let sent_items = &mut self.sent_items;
+        let mut has_changes = false;
+        for sm in &mut delivery.stop_monitoring_delivery {
+            sm.monitored_stop_visit = changed_items(
+                std::mem::take(&mut sm.monitored_stop_visit),
+                sent_items,
+                threshold,
+            );
+            has_changes |= !sm.monitored_stop_visit.is_empty();
+            sm.subscriber_ref = Some(self.subscriber_ref.clone());
+            sm.subscription_ref = Some(self.subscription_ref.clone());
+        }
+        for gm in &mut delivery.general_message_delivery {
+            gm.info_messages =
+                changed_items(std::mem::take(&mut gm.info_messages), sent_items, threshold);
+            has_changes |=
+                !gm.info_messages.is_empty() || !gm.info_messages_cancellation.is_empty();
+            gm.common.subscriber_ref = Some(self.subscriber_ref.clone());
+            gm.common.subscription_ref = Some(self.subscription_ref.clone());
+        }
+        for et in &mut delivery.estimated_timetable_delivery {
+            for frame in &mut et.estimated_journey_version_frame {
+                frame.estimated_vehicle_journey = changed_items(
+                    std::mem::take(&mut frame.estimated_vehicle_journey),
+                    sent_items,
+                    threshold,
+                );
+            }
+            et.estimated_journey_version_frame
+                .retain(|f| !f.estimated_vehicle_journey.is_empty());
+            has_changes |= !et.estimated_journey_version_frame.is_empty();
+            et.common.subscriber_ref = Some(self.subscriber_ref.clone());
+            et.common.subscription_ref = Some(self.subscription_ref.clone());
+        }
+        has_changes
+    }
+
+    /// Create the notification with the changes since the last notification,
+    /// None if nothing has changed
+    fn notification(
+        &mut self,
+        rt_dataset: &RealTimeDataset,
+    ) -> Result<Option<SiriResponse>, SiriError> {
+        let response = match &self.content {
+            SubscribedContent::StopMonitoring(request) => {
+                stop_monitoring(request.into(), rt_dataset)
+            }
+            SubscribedContent::GeneralMessage(request) => {
+                general_message(request.into(), rt_dataset)
+            }
+            SubscribedContent::EstimatedTimetable(request) => {
+                estimated_timetable(request, rt_dataset)
+            }
+        };
+        let mut response = match response {
+            Ok(r) => r,
+            // there is no realtime data for the moment, there will be notifications when some are available
+            Err(SiriError::NoInfoForTopic(_)) => return Ok(None),
+            Err(e) => return Err(e),
+        };
+        let has_changes = match &mut response.siri.service_delivery {
+            Some(delivery) => self.filter_changes(delivery),
+            None => false,
+        };
+        if !has_changes {
+            return Ok(None);
+        }
+        // the changes are computed on the ids of the dataset, so the profile is applied afterward
+        let dataset = get_siri_dataset(&rt_dataset.base_schedule_dataset)?;
+        let profile = dataset.feed_construction_info.dataset_info.siri_profile;
+        profile.apply(&mut response, dataset);
+        Ok(Some(response))
+    }
+}
+
+/// Actor that handles the SIRI subscriptions of a dataset
+///
+/// Each time a new `RealTimeDataset` is given (on the `NewRealTimeDataset` message)
+/// the changes are pushed to the consumer address of the subscribers
+pub struct SubscriptionActor {
+    /// Id of the producer in the siri responses and notifications
+    pub producer_ref: String,
+    pub realtime: Arc<RealTimeDataset>,
+    pub log: slog::Logger,
+    client: reqwest::Client,
+    /// Internal hosts on which the notifications can be pushed, the other internal addresses are rejected
+    allowed_hosts: Vec<String>,
+    // the subscriptions by subscriber and subscription id
+    subscriptions: BTreeMap<(String, String), Subscription>,
+}
+
+impl SubscriptionActor {
+    pub fn new(
+        producer_ref: &str,
+        realtime: Arc<RealTimeDataset>,
+        allowed_hosts: &[String],
+        log: slog::Logger,
+    ) -> Self {
+        SubscriptionActor {
+            producer_ref: producer_ref.to_owned(),
+            realtime,
+            log,
+            client: reqwest::Client::new(),
+            allowed_hosts: allowed_hosts.to_vec(),
+            subscriptions: BTreeMap::new(),
+        }
+    }
+
+    fn push(&self, address: &str, notification: &SiriResponse) {
+        let body = match serde_json::to_vec(notification) {
+            Ok(b) => b,
+            Err(e) => {
+                warn!(self.log, "impossible to serialize the notification: {}", e);
+                return;
+            }
+        };
+        let request = self
+            .client
+            .post(address)
+            .header(reqwest::header::CONTENT_TYPE, "application/json")
+            .body(body);
+        let log = self.log.clone();
+        let address = address.to_owned();
+        actix_rt::spawn(async move {
+            if let Err(e) = request
+                .send()
+                .await
+                .and_then(reqwest::Response::error_for_status)
+            {
+                warn!(
+                    log,
+                    "impossible to push the notification to {}: {}", address, e
+                );
+            }
+        });
+    }
+
+    fn subscribe(
+        &mut self,
+        request: &SubscriptionRequest,
+        params: &SubscriptionParams,
+        content: SubscribedContent,
+    ) -> ResponseStatus {
+        let now = chrono::Utc::now();
+        if let Err(e) = check_consumer_address(&request.consumer_address, &self.allowed_hosts) {
+            return ResponseStatus::from_error(Some(params.subscription_identifier.clone()), &e);
+        }
+        let key = (
+            request.requestor_ref.clone(),
+            params.subscription_identifier.clone(),
+        );
+        // a subscription replacing a previous one is not counted
+        if !self.subscriptions.contains_key(&key) {
+            let requestor_subscriptions = self
+                .subscriptions
+                .keys()
+                .filter(|(requestor_ref, _)| requestor_ref == &request.requestor_ref)
+                .count();
+            let error = if self.subscriptions.len() >= MAX_SUBSCRIPTIONS {
+                Some(format!(
+                    "the maximum number of subscriptions ({}) has been reached",
+                    MAX_SUBSCRIPTIONS
+                ))
+            } else if requestor_subscriptions >= MAX_SUBSCRIPTIONS_BY_REQUESTOR {
+                Some(format!(
+                    "the maximum number of subscriptions of {} ({}) has been reached",
+                    &request.requestor_ref, MAX_SUBSCRIPTIONS_BY_REQUESTOR
+                ))
+            } else {
+                None
+            };
+            if let Some(error) = error {
+                return ResponseStatus::from_error(
+                    Some(params.subscription_identifier.clone()),
+                    &SiriError::Other(error),
+                );
+            }
+        }
+        let termination_time = params.initial_termination_time.with_timezone(&chrono::Utc);
+        if termination_time <= now {
+            return ResponseStatus::from_error(
+                Some(params.subscription_identifier.clone()),
+                &SiriError::InvalidRequest(format!(
+                    "the subscription's termination time {} is in the past",
+                    params.initial_termination_time
+                )),
+            );
+        }
+        let mut subscription = Subscription {
+            subscriber_ref: request.requestor_ref.clone(),
+            subscription_ref: params.subscription_identifier.clone(),
+            consumer_address: request.consumer_address.clone(),
+            content,
+            termination_time,
+            change_before_updates: params
+                .change_before_updates
+                .as_ref()
+                .map(|d| **d)
+                .unwrap_or_else(chrono::Duration::zero),
+            heartbeat_interval: request
+                .subscription_context
+                .heartbeat_interval
+                .as_ref()
+                .map(|d| **d),
+            last_heartbeat: now,
+            sent_items: HashMap::new(),
+        };
+
+        // the first notification contains all the items,
+        // building it also checks that the subscription is valid
+        match subscription.notification(&self.realtime) {
+            Err(e) => ResponseStatus::from_error(Some(subscription.subscription_ref), &e),
+            Ok(notification) => {
+                if let Some(notification) = notification {
+                    self.push(&subscription.consumer_address, &notification);
+                }
+                info!(
+                    self.log,
+                    "new subscription {} for {}",
+                    &subscription.subscription_ref,
+                    &subscription.subscriber_ref
+                );
+                let subscription_ref = subscription.subscription_ref.clone();
+                // a subscription with the same id replaces the previous one
+                self.subscriptions.insert(key, subscription);
+                ResponseStatus {
+                    response_timestamp: now,
+                    subscription_ref: Some(subscription_ref),
+                    status: true,
+                    error_condition: None,
+                    valid_until: Some(termination_time),
+                }
+            }
+        }
+    }
+
+    fn remove_expired_subscriptions(&mut self) {
+        let now = chrono::Utc::now();
+        let expired: Vec<_> = self
+            .subscriptions
+            .iter()
+            .filter(|(_, s)| s.termination_time <= now)
+            .map(|(k, _)| k.clone())
+            .collect();
+        for key in expired {
+            if let Some(subscription) = self.subscriptions.remove(&key) {
+                info!(
+                    self.log,
+                    "subscription {} for {} has expired",
+                    &subscription.subscription_ref,
+                    &subscription.subscriber_ref
+                );
+                let notification = SiriResponse {
+                    siri: Siri {
+                        subscription_terminated_notification: Some(
+                            SubscriptionTerminatedNotification {
+                                response_timestamp: now,
+                                producer_ref: self.producer_ref.clone(),
+                                subscriber_ref: subscription.subscriber_ref,
+                                subscription_ref: subscription.subscription_ref,
+                                description: Some("the subscription has expired".to_owned()),
+                            },
+                        ),
+                        ..Default::default()
+                    },
+                };
+                self.push(&subscription.consumer_address, &notification);
+            }
+        }
+    }
+
+    fn send_heartbeats(&mut self) {
+        let now = chrono::Utc::now();
+        // only one heartbeat is sent by consumer address
+        let addresses: BTreeSet<String> = self
+            .subscriptions
+            .values_mut()
+            .filter(|s| {
+                s.heartbeat_interval
+                    .map_or(false, |interval| s.last_heartbeat + interval <= now)
+            })
+            .map(|s| {
+                s.last_heartbeat = now;
+                s.consumer_address.clone()
+            })
+            .collect();
+        let notification = SiriResponse {
+            siri: Siri {
+                heartbeat_notification: Some(HeartbeatNotification {
+                    request_timestamp: now,
+                    producer_ref: self.producer_ref.clone(),
+                    status: true,
+                }),
+                ..Default::default()
+            },
+        };
+        for address in addresses {
+            self.push(&address, &notification);
+        }
+    }
+}
+
+fn is_internal_ipv4(ip: &Ipv4Addr) -> bool {
+    ip.is_loopback()
+        || ip.is_private()
+        || ip.is_link_local()
+        || ip.is_unspecified()
+        || ip.is_broadcast()
+}
+
+fn is_internal_ip(ip: &IpAddr) -> bool {
+    match ip {
+        IpAddr::V4(ip) => is_internal_ipv4(ip),
+        IpAddr::V6(ip) => {
+            let first_segment = ip.segments()[0];
+            ip.is_loopback()
+                || ip.is_unspecified()
+                // unique local addresses (fc00::/7)
+                || (first_segment & 0xfe00) == 0xfc00
+                // link-local addresses (fe80::/10)
+                || (first_segment & 0xffc0) == 0xfe80
+                // ipv4 addresses given as ipv6 ones
+                || ip.to_ipv4().map_or(false, |ip| is_internal_ipv4(&ip))
+        }
+    }
+}
+
+/// The notifications can only be pushed to an http(s) address
+///
+/// For the subscriptions not to be used to reach the internal services,
+/// the loopback, private and link-local addresses are rejected, but on the `allowed_hosts`.
+/// Note: the domain names are resolved to check their addresses
+fn check_consumer_address(address: &str, allowed_hosts: &[String]) -> Result<(), SiriError> {
+    let url = url::Url::parse(address).map_err(|e| {
+        SiriError::InvalidRequest(format!("invalid consumer address '{}': {}", address, e))
+    })?;
+    let host = match (url.scheme(), url.host_str()) {
+        ("http", Some(host)) | ("https", Some(host)) => host,
+        _ => {
+            return Err(SiriError::InvalidRequest(format!(
+                "invalid consumer address '{}', only the http(s) addresses are handled",
+                address
+            )))
+        }
+    };
+    if allowed_hosts.iter().any(|h| h == host) {
+        return Ok(());
+    }
+    let ips: Vec<IpAddr> = match url.host() {
+        Some(url::Host::Ipv4(ip)) => vec![ip.into()],
+        Some(url::Host::Ipv6(ip)) => vec![ip.into()],
+        _ => url
+            .socket_addrs(|| None)
+            .map_err(|e| {
+                SiriError::InvalidRequest(format!(
+                    "impossible to resolve the consumer address '{}': {}",
+                    address, e
+                ))
+            })?
+            .into_iter()
+            .map(|a| a.ip())
+            .collect(),
+    };
+    if ips.iter().any(is_internal_ip) {
+        return Err(SiriError::InvalidRequest(format!(
+            "invalid consumer address '{}', the notifications cannot be pushed to an internal address",
+            address
+        )));
+    }
+    Ok(())
+}
+
+impl actix::Actor for SubscriptionActor {
+    type Context = actix::Context<Self>;
+
+    fn started(&mut self, ctx: &mut Self::Context) {
+        info!(self.log, "Subscription actor started");
+
+        ctx.run_interval(CHECK_INTERVAL, |act, _ctx| {
+            act.remove_expired_subscriptions();
+            act.send_heartbeats();
+        });
+    }
+}
+
+/// Message sent to a SubscriptionActor to subscribe to some siri deliveries
+pub struct Subscribe(pub SubscriptionRequest);
+
+impl actix::Message for Subscribe {
+    type Result = SubscriptionResponse;
+}
+
+impl actix::Handler<Subscribe> for SubscriptionActor {
+    type Result = actix::MessageResult<Subscribe>;
+
+    fn handle(&mut self, msg: Subscribe, _ctx: &mut actix::Context<Self>) -> Self::Result {
+        let request = msg.0;
+        let subscriptions = request
+            .stop_monitoring_subscription_request
+            .iter()
+            .map(|r| {
+                (
+                    &r.subscription,
+                    SubscribedContent::StopMonitoring(r.stop_monitoring_request.clone()),
+                )
+            })
+            .chain(
+                request
+                    .general_message_subscription_request
+                    .iter()
+                    .map(|r| {
+                        (
+                            &r.subscription,
+                            SubscribedContent::GeneralMessage(r.general_message_request.clone()),
+                        )
+                    }),
+            )
+            .chain(
+                request
+                    .estimated_timetable_subscription_request
+                    .iter()
+                    .map(|r| {
+                        (
+                            &r.subscription,
+                            SubscribedContent::EstimatedTimetable(
+                                r.estimated_timetable_request.clone(),
+                            ),
+                        )
+                    }),
+            )
+            .collect::<Vec<_>>();
+        let response_status = subscriptions
+            .into_iter()
+            .map(|(params, content)| self.subscribe(&request, params, content))
+            .collect();
+
+        actix::MessageResult(SubscriptionResponse {
+            response_timestamp: chrono::Utc::now(),
+            responder_ref: Some(self.producer_ref.clone()),
+            request_message_ref: request.message_identifier.clone(),
+            response_status,
+        })
+    }
+}
+
+/// Message sent to a SubscriptionActor to terminate some subscriptions
+pub struct TerminateSubscription(pub TerminateSubscriptionRequest);
+
+impl actix::Message for TerminateSubscription {
+    type Result = TerminateSubscriptionResponse;
+}
+
+impl actix::Handler<TerminateSubscription> for SubscriptionActor {
+    type Result = actix::MessageResult<TerminateSubscription>;
+
+    fn handle(
+        &mut self,
+        msg: TerminateSubscription,
+        _ctx: &mut actix::Context<Self>,
+    ) -> Self::Result {
+        let request = msg.0;
+        let subscription_refs: Vec<String> = if request.all {
+            self.subscriptions
+                .keys()
+                .filter(|(subscriber, _)| subscriber == &request.requestor_ref)
+                .map(|(_, subscription)| subscription.clone())
+                .collect()
+        } else {
+            request.subscription_ref.clone()
+        };
+        let termination_response_status = subscription_refs
+            .into_iter()
+            .map(|subscription_ref| {
+                let key = (request.requestor_ref.clone(), subscription_ref.clone());
+                match self.subscriptions.remove(&key) {
+                    Some(_) => {
+                        info!(
+                            self.log,
+                            "subscription {} for {} terminated",
+                            &subscription_ref,
+                            &request.requestor_ref
+                        );
+                        TerminationResponseStatus {
+                            subscription_ref: Some(subscription_ref),
+                            status: true,
+                            error_condition: None,
+                        }
+                    }
+                    None => TerminationResponseStatus::from_error(
+                        Some(subscription_ref.clone()),
+                        &SiriError::InvalidDataReferences(format!(
+                            "impossible to find subscription: '{}'",
+                            &subscription_ref
+                        )),
+                    ),
+                }
+            })
+            .collect();
+
+        actix::MessageResult(TerminateSubscriptionResponse {
+            response_timestamp: chrono::Utc::now(),
+            responder_ref: Some(self.producer_ref.clone()),
+            request_message_ref: request.message_identifier.clone(),
+            termination_response_status,
+        })
+    }
+}
+
+/// Message sent to a SubscriptionActor when a new RealTimeDataset is available
+/// the changes are then pushed to the subscribers
+pub struct NewRealTimeDataset(pub Arc<RealTimeDataset>);
+
+impl actix::Message for NewRealTimeDataset {
+    type Result = ();
+}
+
+impl actix::Handler<NewRealTimeDataset> for SubscriptionActor {
+    type Result = ();
+
+    fn handle(&mut self, msg: NewRealTimeDataset, _ctx: &mut actix::Context<Self>) -> Self::Result {
+        self.realtime = msg.0;
+        self.remove_expired_subscriptions();
+
+        let realtime = self.realtime.clone();
+        let log = &self.log;
+        let notifications: Vec<(String, SiriResponse)> = self
+            .subscriptions
+            .values_mut()
+            .filter_map(|s| match s.notification(&realtime) {
+                Ok(n) => n.map(|n| (s.consumer_address.clone(), n)),
+                Err(e) => {
+                    warn!(
+                        log,
+                        "impossible to create the notification for subscription {}: {}",
+                        &s.subscription_ref,
+                        e
+                    );
+                    None
+                }
+            })
+            .collect();
+        for (address, notification) in notifications {
+            self.push(&address, &notification);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn dt(s: &str) -> Option<NaiveDateTime> {
+        Some(NaiveDateTime::parse_from_str(s, "%Y-%m-%dT%H:%M:%S").unwrap())
+    }
+
+    struct Item(&'static str, Option<NaiveDateTime>);
+
+    impl NotifiedItem for Item {
+        fn key(&self) -> String {
+            self.0.to_owned()
+        }
+        fn expected_times(&self) -> Vec<Option<NaiveDateTime>> {
+            vec![self.1]
+        }
+    }
+
+    fn keys(items: &[Item]) -> Vec<&str> {
+        items.iter().map(|i| i.0).collect()
+    }
+
+    #[test]
+    fn only_changes_above_threshold_are_notified() {
+        let threshold = chrono::Duration::seconds(60);
+        let mut sent_items = HashMap::new();
+
+        // at first all the items are new
+        let changed = changed_items(
+            vec![
+                Item("a", dt("2018-12-15T10:00:00")),
+                Item("b", dt("2018-12-15T11:00:00")),
+            ],
+            &mut sent_items,
+            threshold,
+        );
+        assert_eq!(keys(&changed), vec!["a", "b"]);
+
+        // 'a' has a small delay, below the threshold, 'c' is new
+        let changed = changed_items(
+            vec![
+                Item("a", dt("2018-12-15T10:00:30")),
+                Item("b", dt("2018-12-15T11:00:00")),
+                Item("c", None),
+            ],
+            &mut sent_items,
+            threshold,
+        );
+        assert_eq!(keys(&changed), vec!["c"]);
+
+        // the small delays add up since the last notification
+        let changed = changed_items(
+            vec![
+                Item("a", dt("2018-12-15T10:01:00")),
+                Item("b", dt("2018-12-15T11:00:00")),
+                Item("c", dt("2018-12-15T12:00:00")),
+            ],
+            &mut sent_items,
+            threshold,
+        );
+        assert_eq!(keys(&changed), vec!["a", "c"]);
+
+        // 'b' has disappeared, if it comes back it's notified again
+        changed_items(
+            vec![Item("a", dt("2018-12-15T10:01:00"))],
+            &mut sent_items,
+            threshold,
+        );
+        let changed = changed_items(
+            vec![Item("b", dt("2018-12-15T11:00:00"))],
+            &mut sent_items,
+            threshold,
+        );
+        assert_eq!(keys(&changed), vec!["b"]);
+    }
+
+    struct Message(&'static str, &'static str);
+
+    impl NotifiedItem for Message {
+        fn key(&self) -> String {
+            self.0.to_owned()
+        }
+        fn expected_times(&self) -> Vec<Option<NaiveDateTime>> {
+            vec![]
+        }
+        fn content(&self) -> String {
+            self.1.to_owned()
+        }
+    }
+
+    #[test]
+    fn content_changes_are_notified() {
+        let mut sent_items = HashMap::new();
+        let threshold = chrono::Duration::zero();
+        changed_items(
+            vec![Message("a", "road closed"), Message("b", "elevator broken")],
+            &mut sent_items,
+            threshold,
+        );
+        let changed = changed_items(
+            vec![Message("a", "road closed"), Message("b", "elevator fixed")],
+            &mut sent_items,
+            threshold,
+        );
+        let keys: Vec<_> = changed.iter().map(|m| m.0).collect();
+        assert_eq!(keys, vec!["b"]);
+    }
+
+    #[test]
+    fn consumer_addresses() {
+        let check = |address| check_consumer_address(address, &[]);
+        assert!(check("http://93.184.216.34/notifications").is_ok());
+        assert!(check("https://[2606:2800:220:1::]/siri").is_ok());
+        assert!(check("file:///etc/passwd").is_err());
+        assert!(check("ftp://93.184.216.34").is_err());
+        assert!(check("not an address").is_err());
+
+        // the internal addresses are rejected
+        assert!(check("http://localhost:1234/notifications").is_err());
+        assert!(check("http://127.0.0.1:1234/notifications").is_err());
+        assert!(check("http://10.0.0.1/notifications").is_err());
+        assert!(check("http://192.168.1.1/notifications").is_err());
+        assert!(check("http://169.254.169.254/latest/meta-data").is_err());
+        assert!(check("http://0.0.0.0/notifications").is_err());
+        assert!(check("http://[::1]/notifications").is_err());
+        assert!(check("http://[fd00::1]/notifications").is_err());
+        assert!(check("http://[fe80::1]/notifications").is_err());
+        assert!(check("http://[::ffff:127.0.0.1]/notifications").is_err());
+
+        // but on the allowed hosts
+        let allowed_hosts = vec!["127.0.0.1".to_owned()];
+        assert!(check_consumer_address("http://127.0.0.1:1234/n", &allowed_hosts).is_ok());
+        assert!(check_consumer_address("http://10.0.0.1/n", &allowed_hosts).is_err());
+    }
+
+    #[test]
+    fn without_threshold_all_changes_are_notified() {
+        let mut sent_items = HashMap::new();
+        let threshold = chrono::Duration::zero();
+        changed_items(
+            vec![Item("a", dt("2018-12-15T10:00:00"))],
+            &mut sent_items,
+            threshold,
+        );
+        let changed = changed_items(
+            vec![Item("a", dt("2018-12-15T10:00:00"))],
+            &mut sent_items,
+            threshold,
+        );
+        assert!(changed.is_empty());
+        let changed = changed_items(
+            vec![Item("a", dt("2018-12-15T10:00:01"))],
+            &mut sent_items,
+            threshold,
+        );
+        assert_eq!(keys(&changed), vec!["a"]);
+    }
+}
@@ -1,7 +1,13 @@
-use crate::actors::DatasetActor;
-use crate::datasets::{Dataset, FeedConstructionInfo};
-use actix::AsyncContext;
-use slog::info;
+use crate::actors::{
+    ChangePeriod, DatasetActor, DatasetLoader, ForceReload, GetDataset, GetReloaderState,
+    LoadDataset, ReloaderState, SetPaused,
+};
+use crate::datasets::{base_schedule_version, Dataset, FeedConstructionInfo};
+use actix::fut::{ActorFuture, WrapFuture};
+use actix::prelude::ContextFutureSpawner;
+use actix::{Addr, AsyncContext};
+use anyhow::anyhow;
+use slog::{info, warn};
 use std::sync::Arc;
 
 /// Actor that once in a while reload the BaseSchedule data (GTFS)
@@ -13,37 +19,111 @@ pub struct BaseScheduleReloader {
     // NOte: for the moment it's a single Actor,
     // but if we have several instances of DatasetActor we could have a list of recipient here
     pub dataset_actor: actix::Addr<DatasetActor>,
+    // Address of the DatasetLoader, reading the base schedules of all the datasets
+    pub dataset_loader: Addr<DatasetLoader>,
     pub log: slog::Logger,
+    pub state: ReloaderState,
+    // true while a base schedule is loaded, the other reloadings are dropped in the meantime
+    pub loading: bool,
+}
+
+const RELOADING_INTERVAL: std::time::Duration = std::time::Duration::from_secs(60 * 60 * 24);
+
+/// Get the new base schedule dataset
+/// The base schedule is only read again if its version has changed (or if there is no current dataset),
+/// else only the timetable is built again on the current period
+async fn load_dataset(
+    feed_construction_info: FeedConstructionInfo,
+    current_dataset: Option<Arc<Result<Dataset, anyhow::Error>>>,
+    dataset_loader: Addr<DatasetLoader>,
+    log: slog::Logger,
+) -> Result<Dataset, anyhow::Error> {
+    let source_version = base_schedule_version(&feed_construction_info.dataset_info).await;
+    if let Some(current_dataset) = current_dataset {
+        if let Ok(dataset) = &*current_dataset {
+            if source_version.is_some() && source_version == dataset.source_version {
+                info!(
+                    log,
+                    "base schedule unchanged (version {:?}), it is not read again", &source_version
+                );
+                let period = dataset.current_period();
+                return dataset_loader
+                    .send(ChangePeriod {
+                        dataset: current_dataset.clone(),
+                        period,
+                    })
+                    .await
+                    .map_err(|e| anyhow!("impossible to build the timetable: {}", e))?;
+            }
+        }
+    }
+    dataset_loader
+        .send(LoadDataset {
+            dataset_info: feed_construction_info.dataset_info,
+            generation_period: feed_construction_info.generation_period,
+            source_version,
+            log,
+        })
+        .await
+        .map_err(|e| anyhow!("impossible to load the dataset: {}", e))?
 }
 
 impl BaseScheduleReloader {
-    fn update_data(&self, ctx: &mut actix::Context<Self>) {
-        slog_scope::scope(&self.log, || {
-            let new_dataset = Dataset::try_from_dataset_info(
-                self.feed_construction_info.dataset_info.clone(),
-                &crate::datasets::Period {
-                    begin: chrono::Local::today().naive_local(),
-                    horizon: self.feed_construction_info.generation_period.horizon,
-                },
+    /// Reload the base schedule, if `force` is false it is only read again if its version has changed
+    ///
+    /// Note: the dataset is loaded by the `DatasetLoader`, in its own threads,
+    /// the actor still handles its messages during the loading
+    fn update_data(&mut self, force: bool, ctx: &mut actix::Context<Self>) {
+        if self.loading {
+            info!(
+                self.log,
+                "the baseschedule data are already being loaded, this reloading is skipped"
             );
+            return;
+        }
+        self.loading = true;
+        let feed_construction_info = self.feed_construction_info.clone();
+        let dataset_actor = self.dataset_actor.clone();
+        let dataset_loader = self.dataset_loader.clone();
+        let log = self.log.clone();
+        async move {
+            let current_dataset = if force {
+                None
+            } else {
+                dataset_actor.send(GetDataset).await.ok()
+            };
+            load_dataset(feed_construction_info, current_dataset, dataset_loader, log).await
+        }
+        .into_actor(self)
+        .map(|new_dataset, act, ctx| {
+            act.loading = false;
+            act.send_dataset(new_dataset, ctx)
+        })
+        .spawn(ctx);
+    }
 
-            if let Err(e) = &new_dataset {
-                log::warn!("impossible to update dataset because of: {}", e);
-                log::warn!("rescheduling data loading in 5 mn");
+    fn send_dataset(
+        &mut self,
+        new_dataset: Result<Dataset, anyhow::Error>,
+        ctx: &mut actix::Context<Self>,
+    ) {
+        if let Err(e) = &new_dataset {
+            warn!(self.log, "impossible to update dataset because of: {}", e);
+            warn!(self.log, "rescheduling data loading in 5 mn");
 
-                // trace error in sentry
-                sentry::Hub::current().configure_scope(|scope| {
-                    scope.set_tag("dataset", &self.feed_construction_info.dataset_info.id);
-                });
-                sentry::integrations::anyhow::capture_anyhow(&e);
+            // trace error in sentry
+            sentry::Hub::current().configure_scope(|scope| {
+                scope.set_tag("dataset", &self.feed_construction_info.dataset_info.id);
+            });
+            sentry::integrations::anyhow::capture_anyhow(e);
 
-                ctx.run_later(std::time::Duration::from_secs(5 * 60), |act, ctx| {
-                    act.update_data(ctx)
-                });
-            }
-            self.dataset_actor
-                .do_send(UpdateBaseSchedule(Arc::new(new_dataset)));
-        });
+            ctx.run_later(std::time::Duration::from_secs(5 * 60), |act, ctx| {
+                act.update_data(false, ctx)
+            });
+        }
+        self.state.record(&new_dataset);
+        self.dataset_actor
+            .do_send(UpdateBaseSchedule(Arc::new(new_dataset)));
     }
 }
 
@@ -52,13 +132,48 @@ impl actix::Actor for BaseScheduleReloader {
 
     fn started(&mut self, ctx: &mut Self::Context) {
         info!(self.log, "Starting the base schedule updater actor");
-        ctx.run_interval(std::time::Duration::from_secs(60 * 60 * 24), |act, ctx| {
+        self.state.schedule(RELOADING_INTERVAL);
+        ctx.run_interval(RELOADING_INTERVAL, |act, ctx| {
+            act.state.schedule(RELOADING_INTERVAL);
+            if act.state.paused {
+                info!(
+                    act.log,
+                    "reloading paused, the baseschedule data are not reloaded"
+                );
+                return;
+            }
             info!(act.log, "reloading baseschedule data");
-            act.update_data(ctx);
+            act.update_data(false, ctx);
         });
     }
 }
 
+impl actix::Handler<GetReloaderState> for BaseScheduleReloader {
+    type Result = actix::MessageResult<GetReloaderState>;
+
+    fn handle(&mut self, _msg: GetReloaderState, _ctx: &mut Self::Context) -> Self::Result {
+        actix::MessageResult(self.state.clone())
+    }
+}
+
+impl actix::Handler<SetPaused> for BaseScheduleReloader {
+    type Result = ();
+
+    fn handle(&mut self, msg: SetPaused, _ctx: &mut Self::Context) -> Self::Result {
+        info!(self.log, "base schedule reloading paused: {}", msg.0);
+        self.state.paused = msg.0;
+    }
+}
+
+impl actix::Handler<ForceReload> for BaseScheduleReloader {
+    type Result = ();
+
+    fn handle(&mut self, _msg: ForceReload, ctx: &mut Self::Context) -> Self::Result {
+        info!(self.log, "forced reloading of the baseschedule data");
+        self.update_data(true, ctx);
+    }
+}
+
 /// Message send to a DatasetActor to update its baseschedule data
 struct UpdateBaseSchedule(Arc<Result<Dataset, anyhow::Error>>);
 
@@ -77,3 +192,69 @@ impl actix::Handler<UpdateBaseSchedule> for DatasetActor {
         self.gtfs = params.0;
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::load_dataset;
+    use crate::actors::{DatasetLoader, LoadsCounter};
+    use crate::datasets::{
+        base_schedule_version, Dataset, DatasetInfo, FeedConstructionInfo, GenerationPeriod, Period,
+    };
+    use std::sync::Arc;
+
+    // the base schedule is only read again when its version (modification time and size) has changed
+    #[actix_rt::test]
+    async fn base_schedule_read_again_when_changed() {
+        let dir = std::env::temp_dir().join(format!("transpo_rt_reload_{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("gtfs.zip");
+        std::fs::copy("fixtures/gtfs.zip", &path).unwrap();
+
+        let feed_construction_info = FeedConstructionInfo {
+            dataset_info: DatasetInfo::new_default(path.to_str().unwrap(), &[]),
+            generation_period: GenerationPeriod::Fixed(Period {
+                begin: chrono::NaiveDate::from_ymd(2018, 12, 15),
+                horizon: chrono::Duration::days(1),
+            }),
+        };
+        let version = base_schedule_version(&feed_construction_info.dataset_info).await;
+        assert!(version.is_some());
+        let current_dataset = Arc::new(Dataset::try_from_dataset_info(
+            feed_construction_info.dataset_info.clone(),
+            &feed_construction_info.generation_period,
+            version,
+        ));
+        let current_model = current_dataset.as_ref().as_ref().unwrap().ntm.clone();
+        let log = slog::Logger::root(slog::Discard, slog::o!());
+
+        // the file has not changed, only the timetable is built again, on the same model
+        let loads = Arc::new(LoadsCounter::default());
+        let dataset = load_dataset(
+            feed_construction_info.clone(),
+            Some(current_dataset.clone()),
+            DatasetLoader::start_threads(1, loads.clone()),
+            log.clone(),
+        )
+        .await
+        .unwrap();
+        assert!(Arc::ptr_eq(&dataset.ntm, &current_model));
+        assert_eq!(loads.peak(), 0);
+
+        // the file is replaced, it is read again
+        std::fs::copy("fixtures/two_timezones.zip", &path).unwrap();
+        let loads = Arc::new(LoadsCounter::default());
+        let dataset = load_dataset(
+            feed_construction_info,
+            Some(current_dataset),
+            DatasetLoader::start_threads(1, loads.clone()),
+            log,
+        )
+        .await
+        .unwrap();
+        assert!(!Arc::ptr_eq(&dataset.ntm, &current_model));
+        assert_eq!(loads.peak(), 1);
+        assert_ne!(dataset.source_version, None);
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
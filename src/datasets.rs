@@ -3,9 +3,10 @@ use chrono::{DateTime, NaiveDate, NaiveDateTime, Utc};
 use chrono_tz::Tz;
 use log::info;
 use openapi_schema::OpenapiSchema;
-use std::collections::HashMap;
+use std::collections::{BTreeMap, BTreeSet, HashMap};
 use std::sync::Arc;
 use transit_model::collection::Idx;
+use transit_model::objects::{StopPoint, VehicleJourney};
 
 use crate::transit_realtime;
 
@@ -20,6 +21,8 @@ pub enum ScheduleRelationship {
     Scheduled,
     Skipped,
     NoData,
+    /// the whole trip has been cancelled
+    Cancelled,
 }
 
 #[derive(Clone, Debug)]
@@ -47,6 +50,8 @@ pub struct Connection {
 }
 
 pub struct Timetable {
+    /// Period on which the connections have been generated
+    pub period: Period,
     pub connections: Vec<Connection>,
 }
 
@@ -60,15 +65,36 @@ pub struct UpdatedTimetable {
 #[derive(Clone)]
 pub struct FeedConstructionInfo {
     pub dataset_info: DatasetInfo,
-    pub generation_period: Period,
+    pub generation_period: GenerationPeriod,
 }
 
 pub struct Dataset {
-    pub ntm: transit_model::Model,
+    pub ntm: Arc<transit_model::Model>,
     pub timetable: Timetable,
+    /// timezone of the dataset, all the times of the timetable are in this timezone
     pub timezone: Tz,
+    /// timezones of the networks and of the stop points that have their own
+    pub timezones: Timezones,
     pub loaded_at: chrono::DateTime<chrono::Utc>,
+    /// version of the base schedules, to reload them only when they have changed
+    pub source_version: Option<String>,
     pub feed_construction_info: FeedConstructionInfo,
+    /// text search index of the stop points, built once when the dataset is loaded
+    pub stop_points_index: Arc<crate::search::StopPointsIndex>,
+    /// spatial index of the stop points, for the proximity search
+    pub stop_points_spatial_index: Arc<crate::spatial_index::StopPointsSpatialIndex>,
+    /// indexes in the timetable of the connections of each dated vehicle journey,
+    /// built with the timetable
+    pub connections_by_dated_vj: HashMap<DatedVehicleJourney, Vec<usize>>,
+}
+
+/// Alert that has been removed from the GTFS-RT, or marked as deleted,
+/// since the previous realtime update
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct CancelledAlert {
+    /// id of the GTFS-RT entity of the alert
+    pub id: String,
+    pub cancellation_time: chrono::DateTime<chrono::Utc>,
 }
 
 pub struct RealTimeDataset {
@@ -77,6 +103,9 @@ pub struct RealTimeDataset {
     pub gtfs_rt: Option<GtfsRT>,
     pub gtfs_rt_provider_urls: Vec<String>,
     pub updated_timetable: UpdatedTimetable,
+    pub cancelled_alerts: Vec<CancelledAlert>,
+    /// ids of the alerts of each gtfs-rt feed, the last time it has been fetched
+    pub alerts_by_url: BTreeMap<String, BTreeSet<String>>,
 }
 
 impl RealTimeDataset {
@@ -86,6 +115,8 @@ impl RealTimeDataset {
             gtfs_rt: None,
             gtfs_rt_provider_urls: urls.to_owned(),
             updated_timetable: UpdatedTimetable::default(),
+            cancelled_alerts: vec![],
+            alerts_by_url: BTreeMap::new(),
         }
     }
 }
@@ -96,20 +127,117 @@ pub struct Period {
     pub horizon: chrono::Duration,
 }
 
+impl Period {
+    /// First day after the period
+    pub fn end(&self) -> NaiveDate {
+        self.begin + self.horizon
+    }
+}
+
+/// How the period of the timetables of a dataset is computed
+#[derive(Debug, Clone)]
+pub enum GenerationPeriod {
+    /// The timetables are always generated on the same period
+    Fixed(Period),
+    /// The period begins the day before the current day in the dataset's timezone,
+    /// to keep the trips after midnight, and slides at midnight
+    FromYesterday { horizon: chrono::Duration },
+}
+
+impl GenerationPeriod {
+    /// Period of the timetables at a given time, for a dataset in the given timezone
+    pub fn period_at(&self, now: DateTime<Utc>, timezone: Tz) -> Period {
+        match self {
+            Self::Fixed(period) => period.clone(),
+            Self::FromYesterday { horizon } => Period {
+                begin: now.with_timezone(&timezone).date().naive_local().pred(),
+                horizon: *horizon,
+            },
+        }
+    }
+}
+
 #[derive(Deserialize, Clone, Default)]
 pub struct Datasets {
     pub datasets: Vec<DatasetInfo>,
 }
 
+/// Ids that cannot be given to a dataset, they are taken by other routes
+const RESERVED_IDS: &[&str] = &["admin"];
+
+impl Datasets {
+    /// Check the configuration of the datasets
+    pub fn check(&self) -> Result<(), anyhow::Error> {
+        match self
+            .datasets
+            .iter()
+            .find(|d| RESERVED_IDS.contains(&d.id.as_str()))
+        {
+            Some(dataset) => Err(anyhow!(
+                "the id '{}' is reserved, it cannot be given to a dataset",
+                dataset.id
+            )),
+            None => Ok(()),
+        }
+    }
+}
+
+/// Format of the base schedule of a dataset
+#[derive(
+    Serialize, Deserialize, Debug, Clone, Copy, OpenapiSchema, PartialEq, Eq, PartialOrd, Ord,
+)]
+#[serde(rename_all = "kebab-case")]
+pub enum BaseScheduleFormat {
+    /// zipped GTFS, local or remote
+    Gtfs,
+    /// zipped NeTEx files, local only
+    Netex,
+    /// Navitia Transit Feed Specification, the internal format of transit_model, local only
+    Ntfs,
+}
+
+impl Default for BaseScheduleFormat {
+    fn default() -> Self {
+        BaseScheduleFormat::Gtfs
+    }
+}
+
+/// A base schedule merged in a dataset
+#[derive(Serialize, Deserialize, Debug, Clone, OpenapiSchema, PartialEq, Eq, PartialOrd, Ord)]
+#[serde(rename_all = "kebab-case")]
+pub struct BaseScheduleSource {
+    /// Path or url of the base schedule
+    pub path: String,
+    /// Prefix of all the ids of the source (`<prefix>:<id>`), to avoid the conflicts between the sources
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub prefix: Option<String>,
+}
+
 #[derive(Serialize, Deserialize, Debug, Clone, OpenapiSchema, PartialEq, Eq, PartialOrd, Ord)]
 #[serde(rename_all = "kebab-case")]
 pub struct DatasetInfo {
     pub name: String,
     pub id: String,
+    /// Path or url of the base schedule
+    /// Note: the name is kept for compatibility, the base schedule can be in another format than GTFS
+    #[serde(default)]
     pub gtfs: String,
+    /// Other base schedules, merged with `gtfs` in the dataset
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub gtfs_sources: Vec<BaseScheduleSource>,
+    /// Format of the base schedule, GTFS if not set
+    /// Note: only a GTFS can be read from an url, a NeTEx or a NTFS must be a local path
+    #[serde(default)]
+    pub base_schedule_format: BaseScheduleFormat,
     pub gtfs_rt_urls: Vec<String>,
     #[serde(default)]
     pub extras: std::collections::BTreeMap<String, String>,
+    /// Id of the producer in the siri responses, the id of the dataset if not set
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub siri_producer_ref: Option<String>,
+    /// Profile of the siri responses, it can be overridden by the `profile` parameter of the requests
+    #[serde(default)]
+    pub siri_profile: crate::siri_lite::profile::SiriProfile,
 }
 
 impl DatasetInfo {
@@ -118,10 +246,41 @@ impl DatasetInfo {
             id: "default".into(),
             name: "default name".into(),
             gtfs: gtfs.to_owned(),
+            gtfs_sources: vec![],
+            base_schedule_format: BaseScheduleFormat::Gtfs,
             gtfs_rt_urls: gtfs_rt_urls.to_vec(),
             extras: std::collections::BTreeMap::default(),
+            siri_producer_ref: None,
+            siri_profile: Default::default(),
         }
     }
+
+    /// Id of the producer in the siri responses
+    pub fn producer_ref(&self) -> &str {
+        self.siri_producer_ref.as_deref().unwrap_or(&self.id)
+    }
+
+    /// All the base schedules of the dataset, `gtfs` (if set) first
+    pub fn base_schedule_sources(&self) -> Vec<BaseScheduleSource> {
+        let main_source = Some(&self.gtfs)
+            .filter(|path| !path.is_empty())
+            .map(|path| BaseScheduleSource {
+                path: path.clone(),
+                prefix: None,
+            });
+        main_source
+            .into_iter()
+            .chain(self.gtfs_sources.iter().cloned())
+            .collect()
+    }
+
+    /// Prefixes of the ids of the base schedules
+    pub fn id_prefixes(&self) -> Vec<String> {
+        self.gtfs_sources
+            .iter()
+            .filter_map(|s| s.prefix.clone())
+            .collect()
+    }
 }
 
 // create a dt from a Date and a StopTime's time
@@ -132,14 +291,31 @@ fn create_dt(date: NaiveDate, time: transit_model::objects::Time) -> NaiveDateTi
         + chrono::Duration::seconds(i64::from(time.total_seconds()))
 }
 
-fn create_timetable(ntm: &transit_model::Model, generation_period: &Period) -> Timetable {
+// convert a local time of a vehicle journey in the timezone of the dataset
+fn to_dataset_time(dt: NaiveDateTime, vj_timezone: Tz, timezone: Tz) -> NaiveDateTime {
+    use chrono::TimeZone;
+    if vj_timezone == timezone {
+        return dt;
+    }
+    vj_timezone
+        .from_local_datetime(&dt)
+        .earliest()
+        .map_or(dt, |vj_dt| vj_dt.with_timezone(&timezone).naive_local())
+}
+
+fn create_timetable(
+    ntm: &transit_model::Model,
+    timezones: &Timezones,
+    generation_period: &Period,
+) -> Timetable {
     info!("computing timetable for {:?}", &generation_period);
     let begin_dt = Utc::now();
     let mut timetable = Timetable {
+        period: generation_period.clone(),
         connections: vec![],
     };
     let begin = generation_period.begin;
-    let end = begin + generation_period.horizon;
+    let end = generation_period.end();
 
     for (vj_idx, vj) in ntm.vehicle_journeys.iter() {
         let service = skip_fail!(ntm.calendars.get(&vj.service_id).ok_or_else(|| anyhow!(
@@ -147,6 +323,9 @@ fn create_timetable(ntm: &transit_model::Model, generation_period: &Period) -> T
             &vj.service_id,
             &vj.id
         )));
+        // the times of the base schedule are in the timezone of the vj's network,
+        // the connections of all the vjs are in the timezone of the dataset to be comparable
+        let vj_timezone = timezones.of_vehicle_journey(vj_idx);
         for st in &vj.stop_times {
             for date in service
                 .dates
@@ -160,8 +339,16 @@ fn create_timetable(ntm: &transit_model::Model, generation_period: &Period) -> T
                         date: *date,
                     },
                     stop_point_idx: st.stop_point_idx,
-                    dep_time: create_dt(*date, st.departure_time),
-                    arr_time: create_dt(*date, st.arrival_time),
+                    dep_time: to_dataset_time(
+                        create_dt(*date, st.departure_time),
+                        vj_timezone,
+                        timezones.dataset,
+                    ),
+                    arr_time: to_dataset_time(
+                        create_dt(*date, st.arrival_time),
+                        vj_timezone,
+                        timezones.dataset,
+                    ),
                     sequence: st.sequence,
                 });
             }
@@ -178,6 +365,18 @@ fn create_timetable(ntm: &transit_model::Model, generation_period: &Period) -> T
     timetable
 }
 
+/// Indexes of the connections of each dated vehicle journey in the timetable
+fn index_connections(timetable: &Timetable) -> HashMap<DatedVehicleJourney, Vec<usize>> {
+    let mut connections_by_dated_vj: HashMap<DatedVehicleJourney, Vec<usize>> = HashMap::new();
+    for (idx, connection) in timetable.connections.iter().enumerate() {
+        connections_by_dated_vj
+            .entry(connection.dated_vj.clone())
+            .or_default()
+            .push(idx);
+    }
+    connections_by_dated_vj
+}
+
 impl GtfsRT {
     pub fn decode_feed_message(&self) -> Option<transit_realtime::FeedMessage> {
         use prost::Message;
@@ -191,40 +390,237 @@ pub trait HasTimezone {
     fn timezone(&self) -> Option<chrono_tz::Tz>;
 }
 
+fn parse_timezone(timezone: &str) -> Option<Tz> {
+    timezone
+        .parse()
+        .map_err(|e| log::warn!("impossible to parse timezone {} because: {}", timezone, e))
+        .ok()
+}
+
 impl HasTimezone for transit_model::Model {
     fn timezone(&self) -> Option<chrono_tz::Tz> {
         self.networks
             .values()
             .next()
-            .and_then(|n| n.timezone.as_ref())
-            .and_then(|t| {
-                t.parse()
-                    .map_err(|e| log::warn!("impossible to parse timezone {} because: {}", t, e))
-                    .ok()
+            .and_then(|n| n.timezone.as_deref())
+            .and_then(parse_timezone)
+    }
+}
+
+/// Timezones of a dataset
+///
+/// The networks (the agencies of a GTFS) can have a different timezone than the dataset,
+/// their base schedule is then in their own timezone.
+/// The stop points can also have their own timezone, in which their times are displayed
+#[derive(Debug, Clone)]
+pub struct Timezones {
+    /// timezone of the dataset, the one of its first network
+    pub dataset: Tz,
+    vehicle_journeys: HashMap<Idx<VehicleJourney>, Tz>,
+    stop_points: HashMap<Idx<StopPoint>, Tz>,
+}
+
+impl Timezones {
+    /// Timezones of a dataset where everything is in the same timezone
+    pub fn new(timezone: Tz) -> Self {
+        Self {
+            dataset: timezone,
+            vehicle_journeys: HashMap::default(),
+            stop_points: HashMap::default(),
+        }
+    }
+
+    pub fn from_model(model: &transit_model::Model, timezone: Tz) -> Self {
+        let networks: HashMap<&str, Tz> = model
+            .networks
+            .values()
+            .filter_map(|n| {
+                n.timezone
+                    .as_deref()
+                    .and_then(parse_timezone)
+                    .map(|tz| (n.id.as_str(), tz))
+            })
+            .collect();
+        let vehicle_journeys = model
+            .vehicle_journeys
+            .iter()
+            .filter_map(|(vj_idx, vj)| {
+                let route = model.routes.get(&vj.route_id)?;
+                let line = model.lines.get(&route.line_id)?;
+                networks
+                    .get(line.network_id.as_str())
+                    .filter(|tz| **tz != timezone)
+                    .map(|tz| (vj_idx, *tz))
             })
+            .collect();
+        let stop_points = model
+            .stop_points
+            .iter()
+            .filter_map(|(sp_idx, sp)| {
+                sp.timezone
+                    .as_deref()
+                    .and_then(parse_timezone)
+                    .map(|tz| (sp_idx, tz))
+            })
+            .collect();
+        Self {
+            dataset: timezone,
+            vehicle_journeys,
+            stop_points,
+        }
+    }
+
+    /// Timezone of the base schedule of a vehicle journey
+    pub fn of_vehicle_journey(&self, vj_idx: Idx<VehicleJourney>) -> Tz {
+        self.vehicle_journeys
+            .get(&vj_idx)
+            .copied()
+            .unwrap_or(self.dataset)
+    }
+
+    /// Timezone in which the times of a vehicle journey at a stop point are displayed
+    pub fn of_stop_point(&self, stop_point_idx: Idx<StopPoint>, vj_idx: Idx<VehicleJourney>) -> Tz {
+        self.stop_points
+            .get(&stop_point_idx)
+            .copied()
+            .unwrap_or_else(|| self.of_vehicle_journey(vj_idx))
+    }
+}
+
+fn read_base_schedule(
+    path: &str,
+    prefix: Option<String>,
+    format: BaseScheduleFormat,
+) -> Result<transit_model::Model, anyhow::Error> {
+    let is_remote = path.starts_with("http");
+    match format {
+        BaseScheduleFormat::Gtfs if is_remote => {
+            transit_model::gtfs::read_from_url(path, None::<&str>, prefix)
+        }
+        BaseScheduleFormat::Gtfs => transit_model::gtfs::read_from_zip(path, None::<&str>, prefix),
+        // only the GTFS reader of transit_model can download the data
+        BaseScheduleFormat::Netex | BaseScheduleFormat::Ntfs if is_remote => {
+            return Err(anyhow!(
+                "impossible to read {:?} {}, only the GTFS can be read from an url",
+                format,
+                path
+            ))
+        }
+        // the NTFS reader does not handle the prefixes
+        BaseScheduleFormat::Ntfs if prefix.is_some() => {
+            return Err(anyhow!("impossible to prefix the ids of the NTFS {}", path))
+        }
+        BaseScheduleFormat::Netex => transit_model::netex::read(path, None, prefix),
+        BaseScheduleFormat::Ntfs => transit_model::ntfs::read(path),
+    }
+    .map_err(|e| anyhow!("impossible to read {:?} {} because {}", format, path, e))
+}
+
+// version of a base schedule, the ETag (or the Last-Modified date) of a remote file
+// and the modification time and size of a local file
+async fn source_version(path: &str) -> Option<String> {
+    if path.starts_with("http") {
+        let response = reqwest::Client::new()
+            .head(path)
+            .send()
+            .await
+            .and_then(reqwest::Response::error_for_status)
+            .map_err(|e| log::warn!("impossible to get the version of {}: {}", path, e))
+            .ok()?;
+        let header = |name: reqwest::header::HeaderName| {
+            response
+                .headers()
+                .get(name)
+                .and_then(|value| value.to_str().ok())
+                .map(str::to_owned)
+        };
+        header(reqwest::header::ETAG).or_else(|| header(reqwest::header::LAST_MODIFIED))
+    } else {
+        let metadata = std::fs::metadata(path)
+            .map_err(|e| log::warn!("impossible to get the version of {}: {}", path, e))
+            .ok()?;
+        // a directory has no version, it is always read again
+        if metadata.is_dir() {
+            return None;
+        }
+        let modified = metadata
+            .modified()
+            .ok()?
+            .duration_since(std::time::UNIX_EPOCH)
+            .ok()?;
+        Some(format!(
+            "{}.{:09}-{}",
+            modified.as_secs(),
+            modified.subsec_nanos(),
+            metadata.len()
+        ))
     }
 }
 
+/// Version of the base schedules of a dataset
+///
+/// There is no version if one of the sources has none, the dataset is then always read again
+pub async fn base_schedule_version(dataset_info: &DatasetInfo) -> Option<String> {
+    let mut versions = vec![];
+    for source in dataset_info.base_schedule_sources() {
+        versions.push(source_version(&source.path).await?);
+    }
+    Some(versions.join(","))
+}
+
+/// Read all the base schedules of a dataset and merge them in one model
+fn read_base_schedules(dataset_info: &DatasetInfo) -> Result<transit_model::Model, anyhow::Error> {
+    let format = dataset_info.base_schedule_format;
+    let sources = dataset_info.base_schedule_sources();
+    let (first_source, other_sources) = sources
+        .split_first()
+        .ok_or_else(|| anyhow!("no base schedule for dataset {}", dataset_info.id))?;
+    let model = read_base_schedule(&first_source.path, first_source.prefix.clone(), format)?;
+    if other_sources.is_empty() {
+        return Ok(model);
+    }
+    let mut collections = model.into_collections();
+    for source in other_sources {
+        let other = read_base_schedule(&source.path, source.prefix.clone(), format)?;
+        collections
+            .try_merge(other.into_collections())
+            .map_err(|e| anyhow!("impossible to merge {}: {}", source.path, e))?;
+    }
+    transit_model::Model::new(collections)
+        .map_err(|e| anyhow!("impossible to build the merged model: {}", e))
+}
+
 impl Dataset {
     fn new(
         dataset_info: DatasetInfo,
         ntm: transit_model::Model,
-        generation_period: &Period,
+        generation_period: &GenerationPeriod,
+        source_version: Option<String>,
     ) -> Result<Self, anyhow::Error> {
         // To correctly handle GTFS-RT stream we need the dataset's timezone,
         // as all the time in the dataset are in local time and the GTFS-RT gives its time
         // as UTC.
-        // We consider that there can be at most one Company (gtfs's agency) in the dataset
-        // and we consider that the dataset's timezone is it's agency's timezone
+        // The dataset's timezone is the timezone of its first network (gtfs's agency),
+        // the times of the networks in other timezones are converted in this timezone
         let timezone = ntm.timezone().ok_or_else(|| {
             anyhow!("no timezone found, we will not be able to understand realtime information")
         })?;
+        let timezones = Timezones::from_model(&ntm, timezone);
+        let period = generation_period.period_at(Utc::now(), timezone);
+        let timetable = create_timetable(&ntm, &timezones, &period);
 
         Ok(Self {
-            timetable: create_timetable(&ntm, generation_period),
-            ntm,
+            connections_by_dated_vj: index_connections(&timetable),
+            timetable,
+            stop_points_index: Arc::new(crate::search::StopPointsIndex::from_model(&ntm)),
+            stop_points_spatial_index: Arc::new(
+                crate::spatial_index::StopPointsSpatialIndex::from_model(&ntm),
+            ),
+            ntm: Arc::new(ntm),
             timezone,
+            timezones,
             loaded_at: chrono::Utc::now(),
+            source_version,
             feed_construction_info: FeedConstructionInfo {
                 dataset_info,
                 generation_period: generation_period.clone(),
@@ -232,26 +628,52 @@ impl Dataset {
         })
     }
 
+    /// Read the base schedules of a dataset
+    ///
+    /// `source_version` is the version of the base schedules, computed before reading them
     pub fn try_from_dataset_info(
         dataset_info: DatasetInfo,
-        generation_period: &Period,
+        generation_period: &GenerationPeriod,
+        source_version: Option<String>,
     ) -> Result<Self, anyhow::Error> {
         log::info!("reading from path");
-        let gtfs = dataset_info.gtfs.as_str();
-        let nav_data = if gtfs.starts_with("http") {
-            transit_model::gtfs::read_from_url(gtfs, None::<&str>, None)
-        } else {
-            transit_model::gtfs::read_from_zip(gtfs, None::<&str>, None)
+        let nav_data = read_base_schedules(&dataset_info)?;
+        log::info!("base schedule read");
+        Self::new(dataset_info, nav_data, generation_period, source_version)
+    }
+
+    /// Current period of the timetable, as configured for the dataset
+    pub fn current_period(&self) -> Period {
+        self.feed_construction_info
+            .generation_period
+            .period_at(Utc::now(), self.timezone)
+    }
+
+    /// Copy of the dataset with a timetable on another period
+    ///
+    /// The base schedule and the stop points indexes are shared, they are not built again
+    pub fn with_period(&self, period: &Period) -> Self {
+        let timetable = create_timetable(&self.ntm, &self.timezones, period);
+        Self {
+            connections_by_dated_vj: index_connections(&timetable),
+            timetable,
+            stop_points_index: self.stop_points_index.clone(),
+            stop_points_spatial_index: self.stop_points_spatial_index.clone(),
+            ntm: self.ntm.clone(),
+            timezone: self.timezone,
+            timezones: self.timezones.clone(),
+            loaded_at: self.loaded_at,
+            source_version: self.source_version.clone(),
+            feed_construction_info: self.feed_construction_info.clone(),
         }
-        .map_err(|e| anyhow!("impossible to read GTFS {} because {}", gtfs, e))?;
-        log::info!("gtfs read");
-        Self::new(dataset_info, nav_data, &generation_period)
     }
 }
 
 #[cfg(test)]
 mod tests {
-    use crate::datasets::{Connection, DatedVehicleJourney, Period};
+    use crate::datasets::{
+        BaseScheduleFormat, Connection, DatasetInfo, DatedVehicleJourney, GenerationPeriod, Period,
+    };
     use transit_model_builder::ModelBuilder;
 
     #[test]
@@ -280,7 +702,8 @@ mod tests {
             begin: date,
             horizon: chrono::Duration::days(1),
         };
-        let timetable = super::create_timetable(&model, &period);
+        let timetable =
+            super::create_timetable(&model, &super::Timezones::new(chrono_tz::UTC), &period);
         assert_eq!(timetable.connections.len(), 5);
 
         assert_eq!(
@@ -353,4 +776,131 @@ mod tests {
             }
         );
     }
+
+    #[test]
+    fn timezones_of_two_networks() {
+        use chrono_tz::{America, Europe};
+        // the 2 networks of the dataset are in Paris and New York,
+        // the stop NEW_YORK_B of the New York network has the timezone of Paris
+        let model =
+            transit_model::gtfs::read_from_zip("fixtures/two_timezones.zip", None::<&str>, None)
+                .unwrap();
+        let timezones = super::Timezones::from_model(&model, Europe::Paris);
+        let vj = |id: &str| model.vehicle_journeys.get_idx(id).unwrap();
+        let stop_point = |id: &str| model.stop_points.get_idx(id).unwrap();
+
+        assert_eq!(timezones.dataset, Europe::Paris);
+        assert_eq!(timezones.of_vehicle_journey(vj("PARIS1")), Europe::Paris);
+        assert_eq!(
+            timezones.of_vehicle_journey(vj("NEW_YORK1")),
+            America::New_York
+        );
+        // the times at a stop point without timezone are in the timezone of the vehicle journey
+        assert_eq!(
+            timezones.of_stop_point(stop_point("PARIS_A"), vj("PARIS1")),
+            Europe::Paris
+        );
+        assert_eq!(
+            timezones.of_stop_point(stop_point("NEW_YORK_A"), vj("NEW_YORK1")),
+            America::New_York
+        );
+        assert_eq!(
+            timezones.of_stop_point(stop_point("NEW_YORK_B"), vj("NEW_YORK1")),
+            Europe::Paris
+        );
+    }
+
+    #[test]
+    fn reserved_dataset_ids() {
+        let datasets: super::Datasets = serde_yaml::from_str(
+            "
+datasets:
+- id: admin
+  name: admin dataset
+  gtfs: fixtures/gtfs.zip
+  gtfs-rt-urls: []
+",
+        )
+        .unwrap();
+        assert_eq!(
+            datasets.check().unwrap_err().to_string(),
+            "the id 'admin' is reserved, it cannot be given to a dataset"
+        );
+
+        let datasets = super::Datasets {
+            datasets: vec![DatasetInfo::new_default("fixtures/gtfs.zip", &[])],
+        };
+        assert!(datasets.check().is_ok());
+    }
+
+    #[test]
+    fn base_schedule_format() {
+        let dataset: DatasetInfo = serde_yaml::from_str(
+            "
+id: default
+name: default name
+gtfs: fixtures/gtfs.zip
+gtfs-rt-urls: []
+",
+        )
+        .unwrap();
+        assert_eq!(dataset.base_schedule_format, BaseScheduleFormat::Gtfs);
+
+        let dataset: DatasetInfo = serde_yaml::from_str(
+            "
+id: netex
+name: netex dataset
+gtfs: fixtures/netex.zip
+base-schedule-format: netex
+gtfs-rt-urls: []
+",
+        )
+        .unwrap();
+        assert_eq!(dataset.base_schedule_format, BaseScheduleFormat::Netex);
+
+        // only the GTFS can be downloaded
+        let error =
+            super::read_base_schedule("http://localhost/ntfs.zip", None, BaseScheduleFormat::Ntfs)
+                .unwrap_err();
+        assert!(error
+            .to_string()
+            .contains("only the GTFS can be read from an url"));
+    }
+
+    #[test]
+    fn period_from_yesterday() {
+        use chrono::TimeZone;
+        let generation_period = GenerationPeriod::FromYesterday {
+            horizon: chrono::Duration::days(3),
+        };
+        let date = chrono::NaiveDate::from_ymd;
+
+        // 2018-12-14 23:00 in Los Angeles
+        let now = chrono::Utc.ymd(2018, 12, 15).and_hms(7, 0, 0);
+        let period = generation_period.period_at(now, chrono_tz::America::Los_Angeles);
+        assert_eq!(period.begin, date(2018, 12, 13));
+        assert_eq!(period.end(), date(2018, 12, 16));
+        // but already 2018-12-15 in Paris
+        let period = generation_period.period_at(now, chrono_tz::Europe::Paris);
+        assert_eq!(period.begin, date(2018, 12, 14));
+
+        // the period slides at midnight in Los Angeles
+        let now = chrono::Utc.ymd(2018, 12, 15).and_hms(8, 0, 0);
+        let period = generation_period.period_at(now, chrono_tz::America::Los_Angeles);
+        assert_eq!(period.begin, date(2018, 12, 14));
+    }
+
+    #[test]
+    fn vj_time_in_dataset_timezone() {
+        let dt = chrono::NaiveDate::from_ymd(2019, 2, 6).and_hms(10, 0, 0);
+        // a vj in Paris is one hour ahead of a dataset in London
+        assert_eq!(
+            super::to_dataset_time(dt, chrono_tz::Europe::Paris, chrono_tz::Europe::London),
+            chrono::NaiveDate::from_ymd(2019, 2, 6).and_hms(9, 0, 0)
+        );
+        assert_eq!(
+            super::to_dataset_time(dt, chrono_tz::Europe::London, chrono_tz::Europe::London),
+            dt
+        );
+    }
 }
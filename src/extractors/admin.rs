@@ -0,0 +1,72 @@
+use actix_web::{dev::Payload, http::header, web::Data, FromRequest, HttpRequest};
+use futures::future::{ready, Ready};
+
+/// Token needed to access the admin routes
+#[derive(Clone)]
+pub struct AdminToken(pub String);
+
+/// This extractor checks that the request is authenticated for the admin routes,
+/// with a `Authorization: Bearer <token>` header.
+/// The request is rejected with a 401 otherwise.
+///
+/// ```
+/// use transpo_rt::extractors::Admin;
+/// pub async fn an_admin_route(_admin: Admin) -> actix_web::Result<()> {
+///    Ok(())
+///}
+/// ```
+pub struct Admin;
+
+// comparison whose duration does not depend on the position of the first difference
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0, |acc, (x, y)| acc | (x ^ y)) == 0
+}
+
+fn check_token(req: &HttpRequest) -> Result<Admin, actix_web::Error> {
+    let token = req.app_data::<Data<AdminToken>>().ok_or_else(|| {
+        actix_web::error::ErrorInternalServerError("no admin token configured".to_string())
+    })?;
+    // an empty token would accept any `Authorization: Bearer ` header
+    if token.0.is_empty() {
+        return Err(actix_web::error::ErrorUnauthorized(
+            "the admin api is disabled".to_string(),
+        ));
+    }
+    let given_token = req
+        .headers()
+        .get(header::AUTHORIZATION)
+        .and_then(|h| h.to_str().ok())
+        .and_then(|h| h.strip_prefix("Bearer "))
+        .ok_or_else(|| actix_web::error::ErrorUnauthorized("missing admin token".to_string()))?;
+
+    if constant_time_eq(given_token.as_bytes(), token.0.as_bytes()) {
+        Ok(Admin)
+    } else {
+        Err(actix_web::error::ErrorUnauthorized(
+            "invalid admin token".to_string(),
+        ))
+    }
+}
+
+impl FromRequest for Admin {
+    type Config = ();
+    type Error = actix_web::Error;
+    type Future = Ready<Result<Admin, actix_web::Error>>;
+
+    fn from_request(req: &HttpRequest, _payload: &mut Payload) -> Self::Future {
+        ready(check_token(req))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::constant_time_eq;
+
+    #[test]
+    fn compare_tokens() {
+        assert!(constant_time_eq(b"secret", b"secret"));
+        assert!(!constant_time_eq(b"secret", b"secreT"));
+        assert!(!constant_time_eq(b"secret", b"secret2"));
+        assert!(!constant_time_eq(b"", b"secret"));
+    }
+}
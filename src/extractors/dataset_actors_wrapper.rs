@@ -0,0 +1,118 @@
+use crate::actors::{DatasetLookup, DatasetStatus, DatasetsRegistry, GetDatasetActors};
+use crate::datasets::DatasetInfo;
+use crate::routes::{Delivery, SiriErrorResponse, SiriFormat};
+use crate::server::DatasetActors;
+use crate::siri_lite::shared::SiriError;
+use actix::Addr;
+use actix_web::http::{header, HeaderValue, StatusCode};
+use actix_web::{dev::Payload, web::Data, FromRequest, HttpRequest, HttpResponse, ResponseError};
+use futures::future::{err, FutureExt, LocalBoxFuture};
+
+/// Delay (in seconds) given to the clients to retry a request on a dataset being loaded
+const RETRY_AFTER: u32 = 30;
+
+/// This wrapper gives the configuration and the actors of the dataset of a request.
+/// The dataset is found at request time with the `{id}` of the route, in the `DatasetsRegistry`,
+/// a 404 is returned if there is no dataset with this id,
+/// and a 503 (with a `Retry-After` header) if the dataset is still loading.
+/// On the siri routes (registered with their `Delivery` as app data)
+/// those errors are given as siri errors.
+///
+/// ```
+/// use transpo_rt::extractors::DatasetActorsWrapper;
+/// pub async fn a_route(dataset: DatasetActorsWrapper) -> actix_web::Result<()> {
+///    let subscription_actor = &dataset.actors.subscriptions;
+///    Ok(())
+///}
+/// ```
+pub struct DatasetActorsWrapper {
+    pub dataset_info: DatasetInfo,
+    pub actors: DatasetActors,
+}
+
+/// Response given while a dataset is loading
+#[derive(Serialize, Debug)]
+struct LoadingDataset {
+    id: String,
+    status: DatasetStatus,
+    message: String,
+}
+
+fn loading_error(
+    dataset_info: &DatasetInfo,
+    siri: Option<(Delivery, SiriFormat)>,
+) -> actix_web::Error {
+    let message = format!("dataset '{}' is loading", &dataset_info.id);
+    let mut response = match siri {
+        Some((delivery, format)) => SiriErrorResponse::new(
+            delivery,
+            format,
+            SiriError::ServiceNotAvailable(message.clone()),
+        )
+        .error_response(),
+        None => HttpResponse::Ok().json(LoadingDataset {
+            id: dataset_info.id.clone(),
+            status: DatasetStatus::loading,
+            message: message.clone(),
+        }),
+    };
+    *response.status_mut() = StatusCode::SERVICE_UNAVAILABLE;
+    response
+        .headers_mut()
+        .insert(header::RETRY_AFTER, HeaderValue::from(RETRY_AFTER));
+    actix_web::error::InternalError::from_response(message, response).into()
+}
+
+fn not_found_error(id: &str, siri: Option<(Delivery, SiriFormat)>) -> actix_web::Error {
+    let message = format!("impossible to find dataset '{}'", id);
+    match siri {
+        Some((delivery, format)) => {
+            SiriErrorResponse::new(delivery, format, SiriError::InvalidDataReferences(message))
+                .into()
+        }
+        None => actix_web::error::ErrorNotFound(message),
+    }
+}
+
+pub(crate) fn mailbox_error(e: actix::MailboxError) -> actix_web::Error {
+    log::error!("error while querying actor for data: {:?}", e);
+    actix_web::error::ErrorInternalServerError("impossible to get data".to_string())
+}
+
+impl FromRequest for DatasetActorsWrapper {
+    type Config = ();
+    type Error = actix_web::Error;
+    type Future = LocalBoxFuture<'static, Result<DatasetActorsWrapper, actix_web::Error>>;
+
+    fn from_request(req: &HttpRequest, _payload: &mut Payload) -> Self::Future {
+        let registry = match req.app_data::<Data<Addr<DatasetsRegistry>>>() {
+            Some(r) => r.get_ref().clone(),
+            None => {
+                return err(actix_web::error::ErrorInternalServerError(
+                    "impossible to get the datasets".to_string(),
+                ))
+                .boxed_local()
+            }
+        };
+        let id = req.match_info().get("id").unwrap_or_default().to_owned();
+        let siri = req
+            .app_data::<Delivery>()
+            .map(|delivery| (*delivery, SiriFormat::negotiate(req)));
+
+        async move {
+            match registry
+                .send(GetDatasetActors(id.clone()))
+                .await
+                .map_err(mailbox_error)?
+            {
+                DatasetLookup::Ready(dataset_info, actors) => Ok(DatasetActorsWrapper {
+                    dataset_info,
+                    actors,
+                }),
+                DatasetLookup::Loading(dataset_info) => Err(loading_error(&dataset_info, siri)),
+                DatasetLookup::NotFound => Err(not_found_error(&id, siri)),
+            }
+        }
+        .boxed_local()
+    }
+}
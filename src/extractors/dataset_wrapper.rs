@@ -1,8 +1,9 @@
-use crate::actors::{DatasetActor, GetDataset, GetRealtimeDataset};
+use super::dataset_actors_wrapper::{mailbox_error, DatasetActorsWrapper};
+use crate::actors::{GetDataset, GetRealtimeDataset};
 use crate::datasets::{Dataset, RealTimeDataset};
-use actix::Addr;
-use actix_web::{dev::Payload, web::Data, FromRequest, HttpRequest};
-use futures::future::{err, FutureExt, LocalBoxFuture};
+use crate::siri_lite::shared::SiriError;
+use actix_web::{dev::Payload, FromRequest, HttpRequest};
+use futures::future::{FutureExt, LocalBoxFuture};
 use std::sync::Arc;
 
 /// This wrapper provides a convenient way to get a `Dataset` from an actix route.
@@ -38,6 +39,11 @@ impl DatasetWrapper {
     pub fn get_dataset(&self) -> Result<&Dataset, actix_web::Error> {
         get_dataset(&self.dataset)
     }
+
+    /// Same as `get_dataset`, but the error is a siri error, to be used in the siri routes
+    pub fn get_siri_dataset(&self) -> Result<&Dataset, SiriError> {
+        get_siri_dataset(&self.dataset)
+    }
 }
 
 impl std::ops::Deref for RealTimeDatasetWrapper {
@@ -51,15 +57,29 @@ impl RealTimeDatasetWrapper {
     pub fn get_base_schedule_dataset(&self) -> Result<&Dataset, actix_web::Error> {
         get_dataset(&self.realtime_dataset.base_schedule_dataset)
     }
+
+    /// Same as `get_base_schedule_dataset`, but the error is a siri error, to be used in the siri routes
+    pub fn get_siri_base_schedule_dataset(&self) -> Result<&Dataset, SiriError> {
+        get_siri_dataset(&self.realtime_dataset.base_schedule_dataset)
+    }
+}
+
+fn unavailable_dataset_msg(e: &anyhow::Error) -> String {
+    format!("theoretical dataset temporarily unavailable: {}", e)
 }
 
 fn get_dataset(d: &Arc<Result<Dataset, anyhow::Error>>) -> Result<&Dataset, actix_web::Error> {
-    d.as_ref().as_ref().map_err(|e| {
-        actix_web::error::ErrorBadGateway(format!(
-            "theoretical dataset temporarily unavailable: {}",
-            e
-        ))
-    })
+    d.as_ref()
+        .as_ref()
+        .map_err(|e| actix_web::error::ErrorBadGateway(unavailable_dataset_msg(e)))
+}
+
+pub(crate) fn get_siri_dataset(
+    d: &Arc<Result<Dataset, anyhow::Error>>,
+) -> Result<&Dataset, SiriError> {
+    d.as_ref()
+        .as_ref()
+        .map_err(|e| SiriError::ServiceNotAvailable(unavailable_dataset_msg(e)))
 }
 
 impl FromRequest for DatasetWrapper {
@@ -68,26 +88,19 @@ impl FromRequest for DatasetWrapper {
     type Future = LocalBoxFuture<'static, Result<DatasetWrapper, actix_web::Error>>;
 
     fn from_request(req: &HttpRequest, _payload: &mut Payload) -> Self::Future {
-        let dataset_actor = match req.app_data::<Data<Addr<DatasetActor>>>() {
-            Some(d) => d,
-            None => {
-                return err(actix_web::error::ErrorInternalServerError(
-                    "impossible to get data".to_string(),
-                ))
-                .boxed_local()
-            }
-        };
-
-        dataset_actor
-            .send(GetDataset)
-            .map(|res| {
-                res.map_err(|e| {
-                    log::error!("error while querying actor for data: {:?}", e);
-                    actix_web::error::ErrorInternalServerError("impossible to get data".to_string())
-                })
-                .map(|d| DatasetWrapper { dataset: d })
-            })
-            .boxed_local()
+        let dataset_actors = DatasetActorsWrapper::extract(req);
+
+        async move {
+            let dataset = dataset_actors
+                .await?
+                .actors
+                .dataset
+                .send(GetDataset)
+                .await
+                .map_err(mailbox_error)?;
+            Ok(DatasetWrapper { dataset })
+        }
+        .boxed_local()
     }
 }
 
@@ -97,27 +110,18 @@ impl FromRequest for RealTimeDatasetWrapper {
     type Future = LocalBoxFuture<'static, Result<RealTimeDatasetWrapper, actix_web::Error>>;
 
     fn from_request(req: &HttpRequest, _payload: &mut Payload) -> Self::Future {
-        let dataset_actor = match req.app_data::<Data<Addr<DatasetActor>>>() {
-            Some(d) => d,
-            None => {
-                return err(actix_web::error::ErrorInternalServerError(
-                    "impossible to get real time data".to_string(),
-                ))
-                .boxed_local()
-            }
-        };
-
-        dataset_actor
-            .send(GetRealtimeDataset)
-            .map(|res| {
-                res.map_err(|e| {
-                    log::error!("error while querying actor for data: {:?}", e);
-                    actix_web::error::ErrorInternalServerError("impossible to get data".to_string())
-                })
-                .map(|d| RealTimeDatasetWrapper {
-                    realtime_dataset: d,
-                })
-            })
-            .boxed_local()
+        let dataset_actors = DatasetActorsWrapper::extract(req);
+
+        async move {
+            let realtime_dataset = dataset_actors
+                .await?
+                .actors
+                .dataset
+                .send(GetRealtimeDataset)
+                .await
+                .map_err(mailbox_error)?;
+            Ok(RealTimeDatasetWrapper { realtime_dataset })
+        }
+        .boxed_local()
     }
 }
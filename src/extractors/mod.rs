@@ -1,3 +1,8 @@
+mod admin;
+mod dataset_actors_wrapper;
 mod dataset_wrapper;
 
+pub use admin::{Admin, AdminToken};
+pub use dataset_actors_wrapper::DatasetActorsWrapper;
+pub(crate) use dataset_wrapper::get_siri_dataset;
 pub use dataset_wrapper::{DatasetWrapper, RealTimeDatasetWrapper};
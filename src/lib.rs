@@ -15,8 +15,10 @@ pub mod extractors;
 pub mod middlewares;
 pub(crate) mod model_update;
 pub(crate) mod routes;
+pub mod search;
 pub mod server;
 pub mod siri_lite;
+pub mod spatial_index;
 
 #[cfg(test)]
 mod tests;
@@ -1,5 +1,7 @@
+use actix::Addr;
 use anyhow::{anyhow, Context};
 use structopt::StructOpt;
+use transpo_rt::actors::{DatasetsRegistry, UpdateDatasets};
 use transpo_rt::datasets::{DatasetInfo, Datasets};
 use transpo_rt::middlewares;
 
@@ -9,7 +11,7 @@ struct Params {
     #[structopt(
         short = "c",
         long = "config-file",
-        help = "path or url to configuration yaml file",
+        help = "path or url to configuration yaml file. Note: the base schedules of the datasets can be read from an url only for the GTFS, the NeTEx and NTFS must be local paths.",
         env = "TRANSPO_RT_CONFIG_FILE"
     )]
     config_file: Option<String>,
@@ -45,6 +47,26 @@ struct Params {
     bind: String,
     #[structopt(long = "sentry", help = "sentry dsn", env = "TRANSPO_RT_SENTRY")]
     sentry: Option<String>,
+    #[structopt(
+        long = "admin-token",
+        help = "Token needed to access the admin api. Note: if no token (or an empty one) is given, the admin api is not available",
+        env = "TRANSPO_RT_ADMIN_TOKEN"
+    )]
+    admin_token: Option<String>,
+    #[structopt(
+        long = "max-concurrent-loads",
+        help = "Maximum number of datasets whose base schedule is read at the same time, to limit the memory usage",
+        env = "TRANSPO_RT_MAX_CONCURRENT_LOADS",
+        default_value = "2"
+    )]
+    max_concurrent_loads: usize,
+    #[structopt(
+        long = "subscription-allowed-hosts",
+        help = "Comma separated hosts on which the notifications of the subscriptions can be pushed even if they are internal (loopback, private or link-local addresses, that are rejected otherwise)",
+        env = "TRANSPO_RT_SUBSCRIPTION_ALLOWED_HOSTS",
+        use_delimiter = true
+    )]
+    subscription_allowed_hosts: Vec<String>,
 }
 
 /// Load datasets from the configuration
@@ -64,7 +86,10 @@ fn get_datasets(params: &Params) -> Result<Datasets, anyhow::Error> {
             )
         };
 
-        Ok(yaml.with_context(|| "impossible to parse config file".to_string())?)
+        let datasets: Datasets =
+            yaml.with_context(|| "impossible to parse config file".to_string())?;
+        datasets.check()?;
+        Ok(datasets)
     } else if let (Some(gtfs), Some(url)) = (&params.gtfs, &params.url) {
         Ok(Datasets {
             datasets: vec![DatasetInfo::new_default(gtfs, &[url.clone()])],
@@ -76,6 +101,29 @@ fn get_datasets(params: &Params) -> Result<Datasets, anyhow::Error> {
     }
 }
 
+/// Read the configuration again each time a SIGHUP is received,
+/// for the datasets to be added, removed or changed without restarting
+fn reload_datasets_on_sighup(params: Params, datasets_registry: Addr<DatasetsRegistry>) {
+    use actix_rt::signal::unix::{signal, SignalKind};
+    actix_rt::spawn(async move {
+        let mut hangups = match signal(SignalKind::hangup()) {
+            Ok(s) => s,
+            Err(e) => {
+                log::error!("impossible to listen to SIGHUP: {}", e);
+                return;
+            }
+        };
+        while hangups.recv().await.is_some() {
+            log::info!("SIGHUP received, reading the configuration again");
+            let params = params.clone();
+            match actix_web::web::block(move || get_datasets(&params)).await {
+                Ok(datasets) => datasets_registry.do_send(UpdateDatasets(datasets)),
+                Err(e) => log::error!("impossible to reload the configuration: {}", e),
+            }
+        }
+    });
+}
+
 #[actix_web::main]
 async fn main() -> std::io::Result<()> {
     let log_guard = transpo_rt::utils::init_logger();
@@ -88,16 +136,29 @@ async fn main() -> std::io::Result<()> {
         std::env::set_var("RUST_BACKTRACE", "1");
     }
     let bind = format!("{}:{}", &params.bind, &params.port);
-    let today = chrono::Local::today(); //TODO use the timezone's dataset ?
-    let period = transpo_rt::datasets::Period {
-        begin: today.naive_local(),
-        horizon: chrono::Duration::days(2),
+    // the timetables cover yesterday, today and tomorrow in the timezone of each dataset
+    let period = transpo_rt::datasets::GenerationPeriod::FromYesterday {
+        horizon: chrono::Duration::days(3),
     };
     let datasets_infos = get_datasets(&params).unwrap();
     // we create all the actors
-    // this is an async function as we need to wait for all data (and realtime data too) to be read
-    // we wait for this to be finished before spawning the webserver
-    let actors = transpo_rt::server::create_all_actors(datasets_infos, &period).await;
+    // the datasets are loaded in the background, the webserver is spawned right away
+    // and answers a 503 on the routes of a dataset until it is loaded
+    let actors = transpo_rt::server::create_all_actors(
+        datasets_infos,
+        &period,
+        params.max_concurrent_loads,
+        params.subscription_allowed_hosts.clone(),
+    );
+    let admin_token = params.admin_token.clone().filter(|token| {
+        if token.trim().is_empty() {
+            log::warn!("the admin token is empty, the admin api is disabled");
+        }
+        !token.trim().is_empty()
+    });
+    if params.config_file.is_some() {
+        reload_datasets_on_sighup(params.clone(), actors.clone());
+    }
 
     actix_web::HttpServer::new(move || {
         actix_web::App::new()
@@ -110,6 +171,11 @@ async fn main() -> std::io::Result<()> {
             )
             .wrap_fn(middlewares::sentry::sentry_middleware)
             .wrap(actix_web::middleware::Logger::default())
+            .configure(|cfg| {
+                if let Some(token) = &admin_token {
+                    transpo_rt::server::init_admin_routes(cfg, &actors, token)
+                }
+            })
             .configure(|cfg| transpo_rt::server::init_routes(cfg, &actors))
             .default_service(actix_web::web::get().to(|req: actix_web::HttpRequest| {
                 actix_web::HttpResponse::NotFound()
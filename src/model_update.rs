@@ -1,11 +1,11 @@
-use crate::datasets::DatedVehicleJourney;
+use crate::datasets::{DatedVehicleJourney, Timezones};
 use crate::transit_realtime;
 use anyhow::anyhow;
 use anyhow::Error;
 use chrono::{DateTime, NaiveDateTime, Utc};
 use log::{debug, trace, warn};
 use std::collections::HashMap;
-use transit_model::collection::Idx;
+use transit_model::collection::{CollectionWithId, Id, Idx};
 use transit_model::objects::StopPoint;
 
 #[derive(Debug, PartialEq, Eq)]
@@ -13,11 +13,15 @@ pub struct StopTimeUpdate {
     pub stop_point_idx: Option<Idx<StopPoint>>,
     pub updated_departure: Option<NaiveDateTime>,
     pub updated_arrival: Option<NaiveDateTime>,
+    /// the vehicle does not stop at this stop
+    pub skipped: bool,
 }
 
 pub struct TripUpdate {
     pub stop_time_update_by_sequence: HashMap<u32, StopTimeUpdate>,
     pub update_dt: chrono::DateTime<chrono::Utc>,
+    /// the whole trip has been cancelled
+    pub cancelled: bool,
 }
 
 #[derive(Default)]
@@ -37,6 +41,32 @@ fn get_date_time(
         .map(|local_dt| local_dt.naive_local())
 }
 
+/// Find an object of the model from its id in the GTFS-RT
+///
+/// When the dataset merges several sources, the ids of the model are prefixed
+/// by the prefix of their source (`<prefix>:<id>`), whereas the GTFS-RT can give
+/// the ids of the sources, so we also look for the prefixed ids
+fn find_idx<T: Id<T>>(
+    collection: &CollectionWithId<T>,
+    id: &str,
+    prefixes: &[String],
+) -> Option<Idx<T>> {
+    if let Some(idx) = collection.get_idx(id) {
+        return Some(idx);
+    }
+    let mut candidates = prefixes
+        .iter()
+        .filter_map(|prefix| collection.get_idx(&format!("{}:{}", prefix, id)));
+    match (candidates.next(), candidates.next()) {
+        (Some(idx), None) => Some(idx),
+        (Some(_), Some(_)) => {
+            warn!("the id {} is in several sources, we cannot choose one", id);
+            None
+        }
+        (None, _) => None,
+    }
+}
+
 // Create the list of StopTimeUpdates from a gtfs-RT TripUpdate
 //
 // Note: we do not read the delay, we only read the updated time and compute the delay base on the scheduled time
@@ -44,6 +74,7 @@ fn get_date_time(
 fn create_stop_time_updates(
     trip_update: &transit_realtime::TripUpdate,
     model: &transit_model::Model,
+    prefixes: &[String],
     timezone: chrono_tz::Tz,
 ) -> Result<HashMap<u32, StopTimeUpdate>, Error> {
     let mut res = HashMap::default();
@@ -55,7 +86,7 @@ fn create_stop_time_updates(
 
         let stop_idx = match stop_id
             .as_ref()
-            .map(|stop_id| model.stop_points.get_idx(&stop_id))
+            .map(|stop_id| find_idx(&model.stop_points, &stop_id, prefixes))
         {
             Some(None) => {
                 warn!(
@@ -80,6 +111,8 @@ fn create_stop_time_updates(
                 stop_point_idx: stop_idx,
                 updated_departure,
                 updated_arrival,
+                skipped: stop_time_update.schedule_relationship()
+                    == transit_realtime::trip_update::stop_time_update::ScheduleRelationship::Skipped,
             },
         );
     }
@@ -99,17 +132,24 @@ fn default_date(timezone: chrono_tz::Tz) -> chrono::NaiveDate {
         .naive_local()
 }
 
+fn parse_start_date(
+    trip: &transit_realtime::TripDescriptor,
+) -> Result<Option<chrono::NaiveDate>, anyhow::Error> {
+    trip.start_date
+        .as_ref()
+        .map(|s| {
+            chrono::NaiveDate::parse_from_str(s, "%Y%m%d")
+                .map_err(|e| anyhow!("Impossible to parse date: {}", e))
+        })
+        .transpose()
+}
+
+// the date of a trip, today in its timezone if not given
 fn get_date(
     trip: &transit_realtime::TripDescriptor,
     timezone: chrono_tz::Tz,
 ) -> Result<chrono::NaiveDate, anyhow::Error> {
-    trip.start_date.as_ref().map_or_else(
-        || Ok(default_date(timezone)),
-        |s| {
-            chrono::NaiveDate::parse_from_str(s, "%Y%m%d")
-                .map_err(|e| anyhow!("Impossible to parse date: {}", e))
-        },
-    )
+    Ok(parse_start_date(trip)?.unwrap_or_else(|| default_date(timezone)))
 }
 
 // TODO move this in transit_model ?
@@ -122,26 +162,31 @@ fn make_navitia_route_id(gtfs_route_id: &str, direction_id: u32) -> Result<Strin
 }
 
 // TODO move this in transit_model ?
+// The start date and time of the trip are in the timezone of the vehicle journey,
+// like its base schedule
 fn find_corresponging_vjs(
     model: &transit_model::Model,
+    prefixes: &[String],
     gtfs_route_id: &str,
     direction_id: u32,
-    start_date: chrono::NaiveDate,
+    trip: &transit_realtime::TripDescriptor,
     start_time: transit_model::objects::Time,
+    timezones: &Timezones,
 ) -> Result<Vec<Idx<transit_model::objects::VehicleJourney>>, anyhow::Error> {
     let route_id = make_navitia_route_id(gtfs_route_id, direction_id)?;
+    let start_date = parse_start_date(trip)?;
 
-    let route_idx = model
-        .routes
-        .get_idx(&route_id)
+    let route_idx = find_idx(&model.routes, &route_id, prefixes)
         .ok_or_else(|| anyhow!("impossible to find route {}", route_id))?;
 
     Ok(model
         .get_corresponding_from_idx(route_idx)
         .into_iter()
         .map(|vj_idx| (&model.vehicle_journeys[vj_idx], vj_idx))
-        .filter(|(vj, _)| {
+        .filter(|(vj, vj_idx)| {
             // we want all the vjs that are valid this day
+            let start_date =
+                start_date.unwrap_or_else(|| default_date(timezones.of_vehicle_journey(*vj_idx)));
             model
                 .calendars
                 .get(&vj.service_id)
@@ -161,11 +206,12 @@ fn find_corresponging_vjs(
 
 fn get_dated_vj(
     model: &transit_model::Model,
+    prefixes: &[String],
     trip: &transit_realtime::TripDescriptor,
     entity_id: &str,
-    timezone: chrono_tz::Tz,
+    timezones: &Timezones,
 ) -> Result<DatedVehicleJourney, anyhow::Error> {
-    let vj_idx = model.vehicle_journeys.get_idx(trip.trip_id());
+    let vj_idx = find_idx(&model.vehicle_journeys, trip.trip_id(), prefixes);
 
     let vj_idx = if let Some(vj_idx) = vj_idx {
         vj_idx
@@ -175,9 +221,16 @@ fn get_dated_vj(
             (&trip.route_id, trip.direction_id, &trip.start_time)
         {
             use std::str::FromStr;
-            let date = get_date(&trip, timezone)?;
             let time = transit_model::objects::Time::from_str(start_time)?;
-            let vjs = find_corresponging_vjs(model, &route_id, direction_id, date, time)?;
+            let vjs = find_corresponging_vjs(
+                model,
+                prefixes,
+                route_id,
+                direction_id,
+                trip,
+                time,
+                timezones,
+            )?;
 
             match vjs.len() {
                 1 => Ok(vjs[0]),
@@ -200,17 +253,21 @@ fn get_dated_vj(
         }?
     };
 
-    let date = get_date(trip, timezone)?;
+    // the date of the trip is in the timezone of its network
+    let date = get_date(trip, timezones.of_vehicle_journey(vj_idx))?;
 
     Ok(DatedVehicleJourney { vj_idx, date })
 }
 
 /// read a gtfs-rt FeedMessage to create a ModelUpdate,
 /// a temporary structure used to
+///
+/// `prefixes` are the prefixes of the ids of the merged sources of the model
 pub fn get_model_update(
     model: &transit_model::Model,
+    prefixes: &[String],
     gtfs_rts: &[transit_realtime::FeedMessage],
-    timezone: chrono_tz::Tz,
+    timezones: &Timezones,
 ) -> Result<ModelUpdate, Error> {
     debug!("applying a trip update");
     let mut model_update = ModelUpdate::default();
@@ -219,12 +276,17 @@ pub fn get_model_update(
         for entity in &gtfs_rt.entity {
             let entity_id = &entity.id;
             if let Some(tu) = &entity.trip_update {
-                let dated_vj = skip_fail!(get_dated_vj(&model, &tu.trip, entity_id, timezone));
+                let dated_vj = skip_fail!(get_dated_vj(
+                    &model, prefixes, &tu.trip, entity_id, timezones
+                ));
                 model_update.trips.insert(
                     dated_vj,
                     TripUpdate {
                         stop_time_update_by_sequence: create_stop_time_updates(
-                            tu, model, timezone,
+                            tu,
+                            model,
+                            prefixes,
+                            timezones.dataset,
                         )?,
                         update_dt: chrono::DateTime::<chrono::Utc>::from_utc(
                             chrono::NaiveDateTime::from_timestamp(
@@ -233,6 +295,8 @@ pub fn get_model_update(
                             ),
                             chrono::Utc,
                         ),
+                        cancelled: tu.trip.schedule_relationship()
+                            == transit_realtime::trip_descriptor::ScheduleRelationship::Canceled,
                     },
                 );
             } else {
@@ -251,6 +315,7 @@ pub fn get_model_update(
 
 #[cfg(test)]
 mod test {
+    use crate::datasets::Timezones;
     use crate::transit_realtime as tr;
 
     fn make_fake_model() -> transit_model::Model {
@@ -292,7 +357,13 @@ mod test {
 
         let model = make_fake_model();
 
-        let dated_vj = super::get_dated_vj(&model, &trip_descriptor, "entity_id", chrono_tz::UTC);
+        let dated_vj = super::get_dated_vj(
+            &model,
+            &[],
+            &trip_descriptor,
+            "entity_id",
+            &Timezones::new(chrono_tz::UTC),
+        );
 
         // we should be able to find the vj since the id is valid
         let vj_idx = dated_vj.unwrap().vj_idx;
@@ -307,7 +378,13 @@ mod test {
             ..Default::default()
         };
         let model = make_fake_model();
-        let dated_vj = super::get_dated_vj(&model, &trip_descriptor, "entity_id", chrono_tz::UTC);
+        let dated_vj = super::get_dated_vj(
+            &model,
+            &[],
+            &trip_descriptor,
+            "entity_id",
+            &Timezones::new(chrono_tz::UTC),
+        );
         // we shouldn't be able to find a vj
         assert_eq!(
             &format!("{}", dated_vj.unwrap_err()),
@@ -323,7 +400,13 @@ mod test {
             ..Default::default()
         };
         let model = make_fake_model();
-        let dated_vj = super::get_dated_vj(&model, &trip_descriptor, "entity_id", chrono_tz::UTC);
+        let dated_vj = super::get_dated_vj(
+            &model,
+            &[],
+            &trip_descriptor,
+            "entity_id",
+            &Timezones::new(chrono_tz::UTC),
+        );
         // we shouldn't be able to find a vj
         assert_eq!(&format!("{}", dated_vj.unwrap_err()),
         "impossible to find trip id_that_does_not_exist for entity entity_id and no route_id was provided");
@@ -344,7 +427,13 @@ mod test {
             ..Default::default()
         };
         let model = make_fake_model();
-        let dated_vj = super::get_dated_vj(&model, &trip_descriptor, "entity_id", chrono_tz::UTC);
+        let dated_vj = super::get_dated_vj(
+            &model,
+            &[],
+            &trip_descriptor,
+            "entity_id",
+            &Timezones::new(chrono_tz::UTC),
+        );
 
         // we should be able to find the vj since the id is valid
         let vj_idx = dated_vj.unwrap().vj_idx;
@@ -366,7 +455,13 @@ mod test {
             ..Default::default()
         };
         let model = make_fake_model();
-        let dated_vj = super::get_dated_vj(&model, &trip_descriptor, "entity_id", chrono_tz::UTC);
+        let dated_vj = super::get_dated_vj(
+            &model,
+            &[],
+            &trip_descriptor,
+            "entity_id",
+            &Timezones::new(chrono_tz::UTC),
+        );
         assert_eq!(
             &format!("{}", dated_vj.unwrap_err()),
             "for entity entity_id, impossible to find a matching trip"
@@ -412,11 +507,92 @@ mod test {
                     .st("C", "12:00:00", "12:01:00");
             })
             .build();
-        let dated_vj = super::get_dated_vj(&model, &trip_descriptor, "entity_id", chrono_tz::UTC);
+        let dated_vj = super::get_dated_vj(
+            &model,
+            &[],
+            &trip_descriptor,
+            "entity_id",
+            &Timezones::new(chrono_tz::UTC),
+        );
         // vj1 and vj2 are eligible, there is an error
         assert_eq!(
             &format!("{}", dated_vj.unwrap_err()),
             "for entity entity_id, there is no trip id, and 2 matching trips, we can\'t choose one"
         );
     }
+
+    #[test]
+    fn corresponding_vj_with_prefixed_ids() {
+        // the model merges 2 sources, their ids are prefixed
+        let model = transit_model_builder::ModelBuilder::default()
+            .calendar("c", |c| {
+                c.dates.insert(chrono::NaiveDate::from_ymd(2019, 2, 6));
+            })
+            .vj("op1:vj1", |vj_builder| {
+                vj_builder
+                    .calendar("c")
+                    .st("op1:A", "10:00:00", "10:01:00")
+                    .st("op1:B", "11:00:00", "11:01:00");
+            })
+            .vj("op2:vj1", |vj_builder| {
+                vj_builder
+                    .calendar("c")
+                    .st("op2:A", "10:00:00", "10:01:00")
+                    .st("op2:B", "11:00:00", "11:01:00");
+            })
+            .vj("op2:vj2", |vj_builder| {
+                vj_builder
+                    .calendar("c")
+                    .st("op2:B", "11:30:00", "11:31:00")
+                    .st("op2:C", "15:00:00", "15:01:00");
+            })
+            .build();
+        let prefixes = vec!["op1".to_owned(), "op2".to_owned()];
+        let find_vj = |trip_id: &str| {
+            let trip_descriptor = tr::TripDescriptor {
+                trip_id: Some(trip_id.to_owned()),
+                start_date: Some("20190206".to_owned()),
+                ..Default::default()
+            };
+            super::get_dated_vj(
+                &model,
+                &prefixes,
+                &trip_descriptor,
+                "entity_id",
+                &Timezones::new(chrono_tz::UTC),
+            )
+            .map(|dated_vj| model.vehicle_journeys[dated_vj.vj_idx].id.clone())
+        };
+
+        // the GTFS-RT can give the ids of the sources
+        assert_eq!(find_vj("vj2").unwrap(), "op2:vj2");
+        // or the prefixed ids
+        assert_eq!(find_vj("op1:vj1").unwrap(), "op1:vj1");
+        // but we cannot choose between 2 sources with the same id
+        assert!(find_vj("vj1").is_err());
+        // the stops are also found with the ids of the sources
+        assert!(super::find_idx(&model.stop_points, "C", &prefixes).is_some());
+        assert!(super::find_idx(&model.stop_points, "A", &prefixes).is_none());
+    }
+
+    #[test]
+    fn corresponding_vj_in_another_timezone() {
+        // the 2 networks of the dataset are in Paris and New York
+        let model =
+            transit_model::gtfs::read_from_zip("fixtures/two_timezones.zip", None::<&str>, None)
+                .unwrap();
+        let timezones = Timezones::from_model(&model, chrono_tz::Europe::Paris);
+        // the start time is the one of the base schedule, in the timezone of the vehicle journey
+        let trip_descriptor = tr::TripDescriptor {
+            route_id: Some("NEW_YORK".to_owned()),
+            direction_id: Some(0),
+            start_date: Some("20181215".to_owned()),
+            start_time: Some("08:00:00".to_owned()),
+            ..Default::default()
+        };
+        let dated_vj =
+            super::get_dated_vj(&model, &[], &trip_descriptor, "entity_id", &timezones).unwrap();
+        assert_eq!(model.vehicle_journeys[dated_vj.vj_idx].id, "NEW_YORK1");
+        assert_eq!(dated_vj.date, chrono::NaiveDate::from_ymd(2018, 12, 15));
+    }
 }
@@ -0,0 +1,106 @@
+use crate::actors::{
+    DatasetState, ForceReload, GetDatasetState, GetReloaderState, ReloaderState, SetPaused,
+};
+use crate::extractors::{Admin, DatasetActorsWrapper};
+use actix_web::{web, HttpResponse};
+
+/// State of a dataset and of its reloaders
+#[derive(Serialize, Debug)]
+pub struct AdminState {
+    dataset: DatasetState,
+    base_schedule_reloader: ReloaderState,
+    realtime_reloader: ReloaderState,
+}
+
+fn mailbox_error(e: actix::MailboxError) -> actix_web::Error {
+    log::error!("error while querying actor: {:?}", e);
+    actix_web::error::ErrorInternalServerError("impossible to query the actors".to_string())
+}
+
+pub async fn admin_state_query(
+    _admin: Admin,
+    dataset: DatasetActorsWrapper,
+) -> actix_web::Result<web::Json<AdminState>> {
+    let actors = &dataset.actors;
+    Ok(web::Json(AdminState {
+        dataset: actors
+            .dataset
+            .send(GetDatasetState)
+            .await
+            .map_err(mailbox_error)?,
+        base_schedule_reloader: actors
+            .base_schedule_reloader
+            .send(GetReloaderState)
+            .await
+            .map_err(mailbox_error)?,
+        realtime_reloader: actors
+            .realtime_reloader
+            .send(GetReloaderState)
+            .await
+            .map_err(mailbox_error)?,
+    }))
+}
+
+/// Reload the base schedule, even if its version has not changed
+/// The reloading is done in the background, its result is given by the admin state
+pub async fn admin_reload_query(
+    _admin: Admin,
+    dataset: DatasetActorsWrapper,
+) -> actix_web::Result<HttpResponse> {
+    dataset
+        .actors
+        .base_schedule_reloader
+        .send(ForceReload)
+        .await
+        .map_err(mailbox_error)?;
+    Ok(HttpResponse::Accepted().finish())
+}
+
+/// Fetch the gtfs-rt feeds right now
+pub async fn admin_realtime_refresh_query(
+    _admin: Admin,
+    dataset: DatasetActorsWrapper,
+) -> actix_web::Result<HttpResponse> {
+    dataset
+        .actors
+        .realtime_reloader
+        .send(ForceReload)
+        .await
+        .map_err(mailbox_error)?;
+    Ok(HttpResponse::Accepted().finish())
+}
+
+async fn set_paused(
+    paused: bool,
+    dataset: &DatasetActorsWrapper,
+) -> actix_web::Result<HttpResponse> {
+    dataset
+        .actors
+        .base_schedule_reloader
+        .send(SetPaused(paused))
+        .await
+        .map_err(mailbox_error)?;
+    dataset
+        .actors
+        .realtime_reloader
+        .send(SetPaused(paused))
+        .await
+        .map_err(mailbox_error)?;
+    Ok(HttpResponse::NoContent().finish())
+}
+
+/// Pause the periodic reloading of the base schedule and of the realtime data
+pub async fn admin_pause_query(
+    _admin: Admin,
+    dataset: DatasetActorsWrapper,
+) -> actix_web::Result<HttpResponse> {
+    set_paused(true, &dataset).await
+}
+
+/// Resume the periodic reloading of the base schedule and of the realtime data
+pub async fn admin_resume_query(
+    _admin: Admin,
+    dataset: DatasetActorsWrapper,
+) -> actix_web::Result<HttpResponse> {
+    set_paused(false, &dataset).await
+}
@@ -1,5 +1,6 @@
-use crate::datasets::Datasets;
+use crate::actors::{DatasetsRegistry, GetDatasets};
 use crate::routes::{ExposedDataset, Link, Links};
+use actix::Addr;
 use actix_web::{get, web, HttpRequest};
 use maplit::btreemap;
 use openapi_schema::OpenapiSchema;
@@ -18,15 +19,22 @@ fn raw_url(req: &HttpRequest, u: &str) -> String {
     format!("{}://{}{}", conn.scheme(), conn.host(), u)
 }
 
-/// Api to list all the hosted datasets
+/// Api to list all the hosted datasets, with their readiness
 #[get("/")]
-async fn entry_point(req: HttpRequest, datasets: web::Data<Datasets>) -> web::Json<ApiEntryPoint> {
-    web::Json(ApiEntryPoint {
+async fn entry_point(
+    req: HttpRequest,
+    datasets_registry: web::Data<Addr<DatasetsRegistry>>,
+) -> actix_web::Result<web::Json<ApiEntryPoint>> {
+    let datasets = datasets_registry.send(GetDatasets).await.map_err(|e| {
+        log::error!("error while querying the datasets registry: {:?}", e);
+        actix_web::error::ErrorInternalServerError("impossible to get the datasets".to_string())
+    })?;
+    Ok(web::Json(ApiEntryPoint {
         datasets: datasets
-            .datasets
             .iter()
-            .map(|d| {
+            .map(|(d, status)| {
                 ExposedDataset::from(d)
+                    .with_status(*status)
                     .add_link("self", &raw_url(&req, &format!("/{id}/", id = &d.id)))
             })
             .collect(),
@@ -38,5 +46,5 @@ async fn entry_point(req: HttpRequest, datasets: web::Data<Datasets>) -> web::Js
             }
         }
         .into(),
-    })
+    }))
 }
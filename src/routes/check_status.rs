@@ -0,0 +1,46 @@
+use super::message_identifier::MessageIdentifier;
+use super::siri_format::SiriFormat;
+use crate::datasets::{DatasetInfo, RealTimeDataset};
+use crate::extractors::{get_siri_dataset, DatasetActorsWrapper, RealTimeDatasetWrapper};
+use crate::siri_lite::check_status::CheckStatusResponse;
+use crate::siri_lite::{Siri, SiriResponse};
+use actix_web::HttpResponse;
+
+pub(crate) fn check_status(
+    dataset_info: &DatasetInfo,
+    rt_dataset: &RealTimeDataset,
+) -> SiriResponse {
+    let base_schedule = get_siri_dataset(&rt_dataset.base_schedule_dataset);
+    let error = base_schedule.as_ref().err();
+    SiriResponse {
+        siri: Siri {
+            check_status_response: Some(CheckStatusResponse {
+                response_timestamp: chrono::Utc::now(),
+                producer_ref: dataset_info.producer_ref().to_owned(),
+                request_message_ref: None,
+                status: error.is_none(),
+                data_ready: base_schedule.is_ok(),
+                real_time_data_ready: rt_dataset.gtfs_rt.is_some(),
+                error_condition: error.map(|e| e.error_condition()),
+                service_started_time: base_schedule.ok().map(|d| d.loaded_at),
+                real_time_data_updated_time: rt_dataset.gtfs_rt.as_ref().map(|rt| rt.datetime),
+            }),
+            ..Default::default()
+        },
+    }
+}
+
+/// Siri's `CheckStatus` service, to know if the producer is up and when its data changed
+///
+/// Note: contrary to the other siri routes, an unavailable dataset is not an error,
+/// it is given as a `Status` at false in the response
+pub async fn check_status_query(
+    dataset: DatasetActorsWrapper,
+    rt_dataset_wrapper: RealTimeDatasetWrapper,
+    format: SiriFormat,
+    message_identifier: MessageIdentifier,
+) -> HttpResponse {
+    let mut response = check_status(&dataset.dataset_info, &rt_dataset_wrapper);
+    response.set_request_message_ref(&message_identifier.0);
+    format.response(&response)
+}
@@ -0,0 +1,157 @@
+use crate::datasets::{
+    Connection, Dataset, DatedVehicleJourney, RealTimeDataset, UpdatedTimetable,
+};
+use crate::extractors::get_siri_dataset;
+use crate::siri_lite::profile::local_id;
+use crate::siri_lite::shared::{CommonDelivery, SiriError};
+use crate::siri_lite::subscription::EstimatedTimetableRequest;
+use crate::siri_lite::{
+    self, estimated_timetable as model, service_delivery::ServiceDelivery, Siri, SiriResponse,
+};
+use std::collections::{HashMap, HashSet};
+
+fn create_estimated_call(
+    data: &Dataset,
+    idx: usize,
+    connection: &Connection,
+    updated_timetable: &UpdatedTimetable,
+) -> model::EstimatedCall {
+    let stop = &data.ntm.stop_points[connection.stop_point_idx];
+    let updated_connection = updated_timetable.realtime_connections.get(&idx);
+    model::EstimatedCall {
+        stop_point_ref: stop.id.clone(),
+        order: connection.sequence as u16,
+        stop_point_name: stop.name.clone(),
+        aimed_arrival_time: Some(siri_lite::DateTime::at_stop(
+            data,
+            connection,
+            connection.arr_time,
+        )),
+        expected_arrival_time: updated_connection
+            .and_then(|c| c.arr_time)
+            .map(|dt| siri_lite::DateTime::at_stop(data, connection, dt)),
+        aimed_departure_time: Some(siri_lite::DateTime::at_stop(
+            data,
+            connection,
+            connection.dep_time,
+        )),
+        expected_departure_time: updated_connection
+            .and_then(|c| c.dep_time)
+            .map(|dt| siri_lite::DateTime::at_stop(data, connection, dt)),
+    }
+}
+
+fn create_estimated_vehicle_journey(
+    data: &Dataset,
+    dated_vj: &DatedVehicleJourney,
+    connections: &[(usize, &Connection)],
+    updated_timetable: &UpdatedTimetable,
+) -> model::EstimatedVehicleJourney {
+    let vj = &data.ntm.vehicle_journeys[dated_vj.vj_idx];
+    // we consider that the siri's operator in transmodel's company
+    let operator_ref = data
+        .ntm
+        .get_corresponding_from_idx(dated_vj.vj_idx)
+        .into_iter()
+        .next()
+        .map(|idx| data.ntm.companies[idx].id.clone());
+    let recorded_at_time = connections
+        .iter()
+        .filter_map(|(idx, _)| updated_timetable.realtime_connections.get(idx))
+        .map(|c| c.update_time)
+        .max()
+        .unwrap_or_else(|| data.loaded_at);
+
+    model::EstimatedVehicleJourney {
+        line_ref: get_line_ref(dated_vj, data).unwrap_or("").to_owned(),
+        dated_vehicle_journey_ref: format!("{}:{}", &vj.id, dated_vj.date),
+        operator_ref,
+        recorded_at_time,
+        estimated_calls: connections
+            .iter()
+            .map(|(idx, c)| create_estimated_call(data, *idx, c, updated_timetable))
+            .collect(),
+    }
+}
+
+pub(crate) fn get_line_ref<'a>(
+    dated_vj: &DatedVehicleJourney,
+    data: &'a Dataset,
+) -> Option<&'a str> {
+    let vj = &data.ntm.vehicle_journeys[dated_vj.vj_idx];
+    data.ntm
+        .routes
+        .get(&vj.route_id)
+        .map(|r| r.line_id.as_str())
+}
+
+/// Create the estimated timetable of all the vehicle journeys that have realtime updates
+pub(crate) fn estimated_timetable(
+    request: &EstimatedTimetableRequest,
+    rt_dataset: &RealTimeDataset,
+) -> Result<SiriResponse, SiriError> {
+    let data = get_siri_dataset(&rt_dataset.base_schedule_dataset)?;
+    let updated_timetable = &rt_dataset.updated_timetable;
+
+    if let Some(unknown_line) = request
+        .line_ref
+        .iter()
+        .find(|l| data.ntm.lines.get(local_id(l)).is_none())
+    {
+        return Err(SiriError::InvalidDataReferences(format!(
+            "impossible to find line: '{}'",
+            unknown_line
+        )));
+    }
+
+    let updated_vjs: HashSet<&DatedVehicleJourney> = updated_timetable
+        .realtime_connections
+        .keys()
+        .map(|idx| &data.timetable.connections[*idx].dated_vj)
+        .filter(|dated_vj| {
+            request.line_ref.is_empty()
+                || get_line_ref(dated_vj, data)
+                    .map_or(false, |l| request.line_ref.iter().any(|r| local_id(r) == l))
+        })
+        .collect();
+
+    // we group all the connections of the updated vehicle journeys
+    let mut connections_by_vj: HashMap<&DatedVehicleJourney, Vec<(usize, &Connection)>> =
+        HashMap::new();
+    for (idx, connection) in data.timetable.connections.iter().enumerate() {
+        if updated_vjs.contains(&connection.dated_vj) {
+            connections_by_vj
+                .entry(&connection.dated_vj)
+                .or_insert_with(Vec::new)
+                .push((idx, connection));
+        }
+    }
+
+    let mut journeys: Vec<_> = connections_by_vj
+        .into_iter()
+        .map(|(dated_vj, mut connections)| {
+            connections.sort_by_key(|(_, c)| c.sequence);
+            create_estimated_vehicle_journey(data, dated_vj, &connections, updated_timetable)
+        })
+        .collect();
+    journeys.sort_by(|a, b| {
+        a.dated_vehicle_journey_ref
+            .cmp(&b.dated_vehicle_journey_ref)
+    });
+
+    Ok(SiriResponse {
+        siri: Siri {
+            service_delivery: Some(ServiceDelivery {
+                estimated_timetable_delivery: vec![model::EstimatedTimetableDelivery {
+                    common: CommonDelivery::default(),
+                    estimated_journey_version_frame: vec![model::EstimatedJourneyVersionFrame {
+                        recorded_at_time: chrono::Utc::now(),
+                        estimated_vehicle_journey: journeys,
+                    }],
+                }],
+                ..ServiceDelivery::new(data.feed_construction_info.dataset_info.producer_ref())
+            }),
+            ..Default::default()
+        },
+    })
+}
@@ -1,3 +1,4 @@
+use crate::actors::DatasetStatus;
 use crate::datasets::DatasetInfo;
 use crate::routes::{Link, Links};
 use openapi_schema::OpenapiSchema;
@@ -8,6 +9,9 @@ pub struct ExposedDataset {
     pub id: String,
     pub gtfs: String,
     pub extras: std::collections::BTreeMap<String, String>,
+    /// Readiness of the dataset, only given in the list of the datasets
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub status: Option<DatasetStatus>,
     // we do not expose the gtfs-rt sources since the information can contains api key
     // so we add links to the gtfs-rt routes instead
     #[serde(flatten)]
@@ -21,12 +25,18 @@ impl From<&DatasetInfo> for ExposedDataset {
             id: d.id.clone(),
             gtfs: d.gtfs.clone(),
             extras: d.extras.clone(),
+            status: None,
             links: Links::default(),
         }
     }
 }
 
 impl ExposedDataset {
+    pub fn with_status(mut self, status: DatasetStatus) -> Self {
+        self.status = Some(status);
+        self
+    }
+
     pub fn add_link(mut self, key: &str, href: &str) -> Self {
         self.links.links.insert(
             key.to_owned(),
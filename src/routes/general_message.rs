@@ -1,29 +1,183 @@
+use super::message_identifier::MessageIdentifier;
 use super::open_api::make_param;
-use crate::extractors::RealTimeDatasetWrapper;
+use super::siri_error::{Delivery, SiriErrorResponse};
+use super::siri_format::SiriFormat;
+use super::siri_profile::RequestedProfile;
+use crate::datasets::RealTimeDataset;
+use crate::extractors::{get_siri_dataset, RealTimeDatasetWrapper};
+use crate::siri_lite::subscription::GeneralMessageRequest;
 use crate::siri_lite::{
-    general_message as gm, service_delivery::ServiceDelivery, shared::CommonDelivery, Siri,
-    SiriResponse,
+    general_message as gm,
+    profile::local_id,
+    service_delivery::ServiceDelivery,
+    shared::{CommonDelivery, SiriError},
+    Siri, SiriResponse,
 };
 use crate::transit_realtime;
 use crate::utils;
-use actix_web::{web, Result};
+use actix_web::{web, HttpResponse};
+use std::collections::BTreeSet;
+use transit_model::objects::{Line, StopPoint};
+use transit_model::Model;
 
-#[derive(Deserialize, Debug)]
+#[derive(Deserialize, Debug, Default)]
 #[serde(rename_all = "PascalCase")]
 pub struct Params {
     /// start_time is the datetime from which we want the next departures
     /// The default is the current time of the query
     request_timestamp: Option<crate::siri_lite::DateTime>,
+    /// Id of the line, to get only its messages
+    line_ref: Option<String>,
+    /// Id of the stop point, to get only its messages (and the ones on its stop area)
+    stop_point_ref: Option<String>,
+    /// Channel of the messages (`Perturbation` or `Information`)
+    info_channel_ref: Option<String>,
+    /// Language of the messages
+    language: Option<String>,
 }
 
 impl Params {
     // TODO: generate this via derive macro
     pub fn openapi_description(spec: &mut openapi::v3_0::Spec) -> Vec<openapi::v3_0::Parameter> {
-        vec![make_param::<crate::siri_lite::DateTime>(
-            spec,
-            "RequestTimestamp",
-            false,
-        )]
+        vec![
+            make_param::<crate::siri_lite::DateTime>(spec, "RequestTimestamp", false),
+            make_param::<String>(spec, "LineRef", false),
+            make_param::<String>(spec, "StopPointRef", false),
+            make_param::<String>(spec, "InfoChannelRef", false),
+            make_param::<String>(spec, "Language", false),
+            MessageIdentifier::openapi_param(spec),
+            RequestedProfile::openapi_param(spec),
+        ]
+    }
+}
+
+impl From<&GeneralMessageRequest> for Params {
+    fn from(request: &GeneralMessageRequest) -> Self {
+        Params {
+            // the subscriptions are always on the current messages
+            request_timestamp: None,
+            line_ref: request.line_ref.clone(),
+            stop_point_ref: request.stop_point_ref.clone(),
+            info_channel_ref: request.info_channel_ref.clone(),
+            language: request.language.clone(),
+        }
+    }
+}
+
+/// The request's filters, with their objects found in the model
+struct Filters<'a> {
+    line: Option<&'a Line>,
+    stop_point: Option<&'a StopPoint>,
+    info_channel_ref: Option<String>,
+    language: Option<String>,
+}
+
+impl<'a> Filters<'a> {
+    fn new(request: Params, model: &'a Model) -> Result<Self, SiriError> {
+        let line = request
+            .line_ref
+            .map(|id| {
+                model.lines.get(local_id(&id)).ok_or_else(|| {
+                    SiriError::InvalidDataReferences(format!("impossible to find line: '{}'", id))
+                })
+            })
+            .transpose()?;
+        let stop_point = request
+            .stop_point_ref
+            .map(|id| {
+                model.stop_points.get(local_id(&id)).ok_or_else(|| {
+                    SiriError::InvalidDataReferences(format!("impossible to find stop: '{}'", id))
+                })
+            })
+            .transpose()?;
+        Ok(Self {
+            line,
+            stop_point,
+            info_channel_ref: request.info_channel_ref,
+            language: request.language,
+        })
+    }
+
+    fn matches(&self, impacted: &ImpactedObjects, info_channel: &str) -> bool {
+        self.line.map_or(true, |l| {
+            impacted.lines.contains(&l.id) || impacted.networks.contains(&l.network_id)
+        }) && self.stop_point.map_or(true, |sp| {
+            impacted.stops.contains(&sp.id) || impacted.stops.contains(&sp.stop_area_id)
+        }) && self
+            .info_channel_ref
+            .as_ref()
+            .map_or(true, |c| c == info_channel)
+    }
+}
+
+/// Objects impacted by an alert
+///
+/// The ids that cannot be found in the model are kept as is
+#[derive(Default)]
+pub(crate) struct ImpactedObjects {
+    /// networks impacted as a whole
+    pub networks: BTreeSet<String>,
+    pub lines: BTreeSet<String>,
+    /// stop points or stop areas
+    pub stops: BTreeSet<String>,
+    pub vehicle_journeys: BTreeSet<String>,
+}
+
+// the gtfs routes are grouped in lines in the model
+pub(crate) fn get_line_id(route_id: &str, model: &Model) -> String {
+    model
+        .routes
+        .get(route_id)
+        .map_or_else(|| route_id.to_owned(), |r| r.line_id.clone())
+}
+
+pub(crate) fn get_impacted_objects(
+    alert: &transit_realtime::Alert,
+    model: &Model,
+) -> ImpactedObjects {
+    let mut impacted = ImpactedObjects::default();
+    for informed_entity in &alert.informed_entity {
+        let trip = informed_entity.trip.as_ref();
+        let line_id = informed_entity
+            .route_id
+            .as_ref()
+            .or_else(|| trip.and_then(|t| t.route_id.as_ref()))
+            .map(|r| get_line_id(r, model))
+            .or_else(|| {
+                trip.and_then(|t| t.trip_id.as_ref())
+                    .and_then(|id| model.vehicle_journeys.get(id))
+                    .map(|vj| get_line_id(&vj.route_id, model))
+            });
+        if let Some(s) = &informed_entity.stop_id {
+            impacted.stops.insert(s.clone());
+        }
+        if let Some(vj) = trip.and_then(|t| t.trip_id.as_ref()) {
+            impacted.vehicle_journeys.insert(vj.clone());
+        }
+        match (&line_id, &informed_entity.agency_id) {
+            (Some(l), _) => {
+                impacted.lines.insert(l.clone());
+            }
+            // only an agency is given, all its network is impacted
+            (None, Some(a)) if informed_entity.stop_id.is_none() => {
+                impacted.networks.insert(a.clone());
+            }
+            _ => {}
+        }
+    }
+    impacted
+}
+
+fn get_info_channel(alert: &transit_realtime::Alert) -> &'static str {
+    use transit_realtime::alert::Effect;
+    match alert.effect() {
+        Effect::NoService
+        | Effect::ReducedService
+        | Effect::SignificantDelays
+        | Effect::Detour
+        | Effect::ModifiedService
+        | Effect::StopMoved => "Perturbation",
+        Effect::AdditionalService | Effect::OtherEffect | Effect::UnknownEffect => "Information",
     }
 }
 
@@ -36,30 +190,35 @@ fn get_max_validity(
         .iter()
         .filter_map(|p| utils::read_pbf_dt(p.end, timezone))
         .max()
-        .map(crate::siri_lite::DateTime)
+        .map(|dt| crate::siri_lite::DateTime::in_timezone(dt, timezone))
 }
 
-fn display_alert(
+pub(crate) fn display_alert(
     alert: &transit_realtime::Alert,
     requested_dt: chrono::NaiveDateTime,
     timezone: chrono_tz::Tz,
 ) -> bool {
-    alert.active_period.iter().any(|p| {
-        utils::read_pbf_dt(p.start, timezone).map_or(true, |s| s <= requested_dt)
-            && utils::read_pbf_dt(p.end, timezone).map_or(true, |e| requested_dt <= e)
-    })
+    // an alert without active period is always active
+    alert.active_period.is_empty()
+        || alert.active_period.iter().any(|p| {
+            utils::read_pbf_dt(p.start, timezone).map_or(true, |s| s <= requested_dt)
+                && utils::read_pbf_dt(p.end, timezone).map_or(true, |e| requested_dt <= e)
+        })
 }
 
 // we create one message by lang
+// if a language is requested, the translations without language are kept too
 fn get_msgs(
     ts: &Option<transit_realtime::TranslatedString>,
     msg_type: gm::MessageType,
+    language: &Option<String>,
 ) -> Vec<gm::Message> {
     ts.as_ref()
         .map(|translated_string| {
             translated_string
                 .translation
                 .iter()
+                .filter(|s| language.is_none() || s.language.is_none() || s.language == *language)
                 .map(|s| gm::Message {
                     message_type: Some(msg_type.clone()),
                     message_text: gm::NaturalLangString {
@@ -72,29 +231,29 @@ fn get_msgs(
         .unwrap_or_else(Vec::new)
 }
 
-fn read_content(alert: &transit_realtime::Alert) -> gm::GeneralMessageStructure {
-    // use btreeset because there can be lots of dupplicates
-    let mut line_ref = std::collections::BTreeSet::new();
-    let mut sp_ref = std::collections::BTreeSet::new();
+fn read_content(
+    alert: &transit_realtime::Alert,
+    impacted: ImpactedObjects,
+    language: &Option<String>,
+) -> gm::GeneralMessageStructure {
     let destination_ref = vec![]; // TODO, implement the destination
-    for informed_entity in &alert.informed_entity {
-        if let Some(s) = &informed_entity.stop_id {
-            sp_ref.insert(s.clone());
-        }
-        if let Some(l) = &informed_entity.route_id {
-            line_ref.insert(l.clone());
-        }
-    }
 
     gm::GeneralMessageStructure {
-        line_ref: line_ref.into_iter().collect(),
-        stop_point_ref: sp_ref.into_iter().collect(),
+        line_ref: impacted.lines.into_iter().collect(),
+        stop_point_ref: impacted.stops.into_iter().collect(),
         destination_ref,
         // not sure about this, but we split the header/description as 2 different messages
         // a short and a long one
-        message: get_msgs(&alert.header_text, gm::MessageType::shortMessage)
+        message: get_msgs(&alert.header_text, gm::MessageType::shortMessage, language)
             .into_iter()
-            .chain(get_msgs(&alert.description_text, gm::MessageType::longMessage).into_iter())
+            .chain(
+                get_msgs(
+                    &alert.description_text,
+                    gm::MessageType::longMessage,
+                    language,
+                )
+                .into_iter(),
+            )
             .collect(),
     }
 }
@@ -103,55 +262,104 @@ fn read_info_messages(
     feed: &transit_realtime::FeedMessage,
     requested_dt: chrono::NaiveDateTime,
     timezone: chrono_tz::Tz,
+    model: &Model,
+    filters: &Filters,
 ) -> Vec<gm::InfoMessage> {
     feed.entity
         .iter()
-        .filter_map(|e| e.alert.as_ref())
-        .filter(|a| display_alert(a, requested_dt, timezone))
-        .map(|a| gm::InfoMessage {
-            content: read_content(a),
-            valid_until_time: get_max_validity(a, timezone),
-            ..Default::default()
+        .filter(|e| e.is_deleted != Some(true))
+        .filter_map(|e| e.alert.as_ref().map(|a| (e, a)))
+        .filter(|(_, a)| display_alert(a, requested_dt, timezone))
+        .filter_map(|(e, a)| {
+            let impacted = get_impacted_objects(a, model);
+            let info_channel = get_info_channel(a);
+            if !filters.matches(&impacted, info_channel) {
+                return None;
+            }
+            let content = read_content(a, impacted, &filters.language);
+            if content.message.is_empty() {
+                // nothing in the requested language
+                return None;
+            }
+            Some(gm::InfoMessage {
+                // the entity's id is used for the message to be stable between the gtfs-rt updates
+                item_identifier: Some(e.id.clone()),
+                info_message_identifier: Some(e.id.clone()),
+                info_channel_ref: Some(info_channel.to_owned()),
+                content,
+                valid_until_time: get_max_validity(a, timezone),
+                ..Default::default()
+            })
         })
         .collect()
 }
 
-fn general_message(
-    request: Params,
-    rt_dataset_wrapper: RealTimeDatasetWrapper,
-) -> Result<SiriResponse> {
-    let timezone = rt_dataset_wrapper.get_base_schedule_dataset()?.timezone;
+// Note: we do not know what was impacted by the cancelled alerts, so the cancellations are not filtered
+fn read_cancellations(
+    rt_dataset: &RealTimeDataset,
+    timezone: chrono_tz::Tz,
+) -> Vec<gm::InfoMessageCancellation> {
+    rt_dataset
+        .cancelled_alerts
+        .iter()
+        .map(|c| gm::InfoMessageCancellation {
+            recorded_at_time: crate::siri_lite::DateTime::in_timezone(
+                c.cancellation_time.with_timezone(&timezone).naive_local(),
+                timezone,
+            ),
+            item_identifier: Some(c.id.clone()),
+            info_message_identifier: Some(c.id.clone()),
+        })
+        .collect()
+}
 
-    let requested_dt = request
-        .request_timestamp
-        .map(|d| d.0)
-        .unwrap_or_else(|| chrono::Utc::now().with_timezone(&timezone).naive_local());
-    // Note: we decode the gtfs at the query. if needed we can cache this, to parse it once
+// Note: we decode the gtfs at the query. if needed we can cache this, to parse it once
+pub(crate) fn read_feed(
+    rt_dataset: &RealTimeDataset,
+) -> Result<transit_realtime::FeedMessage, SiriError> {
     use prost::Message;
-    let feed = rt_dataset_wrapper
+    rt_dataset
         .gtfs_rt
         .as_ref()
-        .ok_or_else(|| actix_web::error::ErrorNotFound("no realtime data available"))
+        .ok_or_else(|| SiriError::NoInfoForTopic("no realtime data available".to_owned()))
         .map(|rt| rt.data.clone())
         .and_then(|d| {
             transit_realtime::FeedMessage::decode(d.as_slice()).map_err(|e| {
-                actix_web::error::ErrorInternalServerError(format!(
-                    "impossible to decode protobuf message: {}",
-                    e
-                ))
+                SiriError::Other(format!("impossible to decode protobuf message: {}", e))
             })
-        })?;
+        })
+}
+
+pub(crate) fn general_message(
+    request: Params,
+    rt_dataset: &RealTimeDataset,
+) -> Result<SiriResponse, SiriError> {
+    let dataset = get_siri_dataset(&rt_dataset.base_schedule_dataset)?;
+    let timezone = dataset.timezone;
+
+    let requested_dt = request
+        .request_timestamp
+        .as_ref()
+        .map(|d| d.naive_in(timezone))
+        .unwrap_or_else(|| chrono::Utc::now().with_timezone(&timezone).naive_local());
+    let filters = Filters::new(request, &dataset.ntm)?;
+    let feed = read_feed(rt_dataset)?;
 
     Ok(SiriResponse {
         siri: Siri {
             service_delivery: Some(ServiceDelivery {
-                producer_ref: None, // TODO take the id of the dataset ?
                 general_message_delivery: vec![gm::GeneralMessageDelivery {
                     common: CommonDelivery::default(),
-                    info_messages: read_info_messages(&feed, requested_dt, timezone),
-                    info_messages_cancellation: vec![],
+                    info_messages: read_info_messages(
+                        &feed,
+                        requested_dt,
+                        timezone,
+                        &dataset.ntm,
+                        &filters,
+                    ),
+                    info_messages_cancellation: read_cancellations(rt_dataset, timezone),
                 }],
-                ..Default::default()
+                ..ServiceDelivery::new(dataset.feed_construction_info.dataset_info.producer_ref())
             }),
             ..Default::default()
         },
@@ -161,6 +369,17 @@ fn general_message(
 pub async fn general_message_query(
     web::Query(query): web::Query<Params>,
     realtime_dataset_wrapper: RealTimeDatasetWrapper,
-) -> actix_web::Result<web::Json<SiriResponse>> {
-    Ok(web::Json(general_message(query, realtime_dataset_wrapper)?))
+    format: SiriFormat,
+    message_identifier: MessageIdentifier,
+    profile: RequestedProfile,
+) -> Result<HttpResponse, SiriErrorResponse> {
+    general_message(query, &realtime_dataset_wrapper)
+        .map(|mut r| {
+            if let Ok(data) = realtime_dataset_wrapper.get_siri_base_schedule_dataset() {
+                profile.apply(&mut r, data);
+            }
+            r.set_request_message_ref(&message_identifier.0);
+            format.response(&r)
+        })
+        .map_err(|e| SiriErrorResponse::new(Delivery::GeneralMessage, format, e))
 }
@@ -0,0 +1,85 @@
+use super::message_identifier::MessageIdentifier;
+use super::open_api::make_param;
+use super::siri_error::{Delivery, SiriErrorResponse};
+use super::siri_format::SiriFormat;
+use super::siri_profile::RequestedProfile;
+use crate::extractors::DatasetWrapper;
+use crate::siri_lite::lines_delivery::{AnnotatedLineRef, LinesDelivery};
+use crate::siri_lite::shared::CommonDelivery;
+use crate::siri_lite::{Siri, SiriResponse};
+use actix_web::{web, HttpResponse};
+
+fn default_limit() -> usize {
+    20
+}
+
+#[derive(Deserialize, Clone)]
+pub struct Params {
+    q: Option<String>,
+    #[serde(default = "default_limit")]
+    limit: usize,
+    #[serde(default)]
+    offset: usize,
+}
+
+impl Params {
+    pub fn openapi_description(spec: &mut openapi::v3_0::Spec) -> Vec<openapi::v3_0::Parameter> {
+        vec![
+            make_param::<String>(spec, "q", false),
+            make_param::<usize>(spec, "limit", false),
+            make_param::<usize>(spec, "offset", false),
+            MessageIdentifier::openapi_param(spec),
+            RequestedProfile::openapi_param(spec),
+        ]
+    }
+}
+
+fn line_matches(line: &transit_model::objects::Line, q: &str) -> bool {
+    line.name.to_lowercase().contains(q)
+        || line
+            .code
+            .as_ref()
+            .map_or(false, |c| c.to_lowercase().contains(q))
+}
+
+pub fn filter(data: &crate::datasets::Dataset, request: Params) -> SiriResponse {
+    let model = &data.ntm;
+
+    let q = request.q.unwrap_or_default().to_lowercase();
+
+    let filtered = model
+        .lines
+        .iter()
+        .filter(|(_, l)| line_matches(l, &q))
+        .skip(request.offset)
+        .take(request.limit)
+        .map(|(idx, _)| AnnotatedLineRef::from(idx, &model))
+        .collect();
+
+    SiriResponse {
+        siri: Siri {
+            lines_delivery: Some(LinesDelivery {
+                common: CommonDelivery::default(),
+                annotated_line_ref: filtered,
+            }),
+            ..Default::default()
+        },
+    }
+}
+
+pub async fn lines_discovery_query(
+    web::Query(query): web::Query<Params>,
+    dataset_wrapper: DatasetWrapper,
+    format: SiriFormat,
+    message_identifier: MessageIdentifier,
+    profile: RequestedProfile,
+) -> Result<HttpResponse, SiriErrorResponse> {
+    let dataset = dataset_wrapper
+        .get_siri_dataset()
+        .map_err(|e| SiriErrorResponse::new(Delivery::LinesDiscovery, format, e))?;
+
+    let mut response = filter(&dataset, query);
+    profile.apply(&mut response, dataset);
+    response.set_request_message_ref(&message_identifier.0);
+    Ok(format.response(&response))
+}
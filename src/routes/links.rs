@@ -34,15 +34,13 @@ impl Link {
             ..Default::default()
         }
     }
+    /// Link to a route of the `/{id}` scope of a dataset
     pub fn from_scoped_url(req: &actix_web::HttpRequest, name: &str, scope: &str) -> Self {
-        let route_name = format!("{}/{}", scope, name);
         Self {
             href: req
-                .url_for(&route_name, &[""])
+                .url_for(name, &[scope])
                 .map(|u| u.into_string())
-                .unwrap_or_else(|_| {
-                    panic!("route {} has not been registered with a name", route_name)
-                }),
+                .unwrap_or_else(|_| panic!("route {} has not been registered with a name", name)),
             ..Default::default()
         }
     }
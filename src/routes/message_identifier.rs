@@ -0,0 +1,35 @@
+use super::open_api::make_param;
+use actix_web::{dev::Payload, web, FromRequest, HttpRequest};
+use futures::future::{ok, Ready};
+
+#[derive(Deserialize, Default)]
+struct Params {
+    #[serde(rename = "MessageIdentifier")]
+    message_identifier: Option<String>,
+}
+
+/// Identifier of a siri-lite request, given in the `MessageIdentifier` query parameter
+///
+/// It is echoed in the `RequestMessageRef` of the response
+#[derive(Debug, Clone, Default)]
+pub struct MessageIdentifier(pub Option<String>);
+
+impl MessageIdentifier {
+    pub fn openapi_param(spec: &mut openapi::v3_0::Spec) -> openapi::v3_0::Parameter {
+        make_param::<String>(spec, "MessageIdentifier", false)
+    }
+}
+
+impl FromRequest for MessageIdentifier {
+    type Config = ();
+    type Error = actix_web::Error;
+    type Future = Ready<Result<MessageIdentifier, actix_web::Error>>;
+
+    fn from_request(req: &HttpRequest, _payload: &mut Payload) -> Self::Future {
+        // the other query parameters are checked by the route, so we ignore any error here
+        let params = web::Query::<Params>::from_query(req.query_string())
+            .map(|q| q.into_inner())
+            .unwrap_or_default();
+        ok(MessageIdentifier(params.message_identifier))
+    }
+}
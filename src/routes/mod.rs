@@ -1,27 +1,65 @@
+mod admin;
 mod api_entry_point;
+mod check_status;
+mod estimated_timetable;
 mod exposed_dataset;
 mod general_message;
 mod gtfs_rt;
+mod lines_discovery;
 mod links;
+mod message_identifier;
 pub(crate) mod open_api;
+mod production_timetable;
 mod siri;
+mod siri_error;
+mod siri_format;
+mod siri_profile;
+mod siri_soap;
+mod situation_exchange;
 mod status;
 mod stop_monitoring;
+mod stop_timetable;
 mod stoppoints_discovery;
+mod subscription;
+mod trip;
 
+pub use self::admin::{
+    admin_pause_query, admin_realtime_refresh_query, admin_reload_query, admin_resume_query,
+    admin_state_query,
+};
 pub use self::api_entry_point::entry_point;
+pub use self::check_status::check_status_query;
 pub use self::exposed_dataset::ExposedDataset;
 pub use self::general_message::general_message_query;
 pub use self::gtfs_rt::{gtfs_rt_json, gtfs_rt_protobuf};
+pub use self::lines_discovery::lines_discovery_query;
 pub use self::links::{Link, Links};
 pub use self::open_api::documentation;
+pub use self::production_timetable::production_timetable_query;
 pub use self::siri::siri_endpoint;
+pub use self::siri_error::{siri_json_config, siri_query_config, Delivery, SiriErrorResponse};
+pub use self::siri_format::SiriFormat;
+pub use self::siri_soap::siri_soap_query;
+pub use self::situation_exchange::situation_exchange_query;
 pub use self::status::status_query;
 pub use self::stop_monitoring::stop_monitoring_query;
+pub use self::stop_timetable::stop_timetable_query;
 pub use self::stoppoints_discovery::stoppoints_discovery_query;
+pub use self::subscription::{subscribe_query, terminate_subscription_query};
+pub use self::trip::trip_query;
 
 // export the params/responses for the openapi module
 pub(crate) use self::general_message::Params as GeneralMessageParams;
+pub(crate) use self::lines_discovery::Params as LinesDiscoveryParams;
+pub(crate) use self::production_timetable::Params as ProductionTimetableParams;
+pub(crate) use self::situation_exchange::Params as SituationExchangeParams;
 pub(crate) use self::status::Status;
 pub(crate) use self::stop_monitoring::Params as StopMonitoringParams;
+pub(crate) use self::stop_timetable::Params as StopTimetableParams;
 pub(crate) use self::stoppoints_discovery::Params as StopPointsDiscoveryParams;
+pub(crate) use self::trip::{Params as TripParams, Trip};
+
+// export the siri logic for the subscriptions
+pub(crate) use self::estimated_timetable::estimated_timetable;
+pub(crate) use self::general_message::general_message;
+pub(crate) use self::stop_monitoring::stop_monitoring;
@@ -154,6 +154,45 @@ fn add_path_item_with_undefined_response(
     );
 }
 
+/// Add the error responses of a siri route
+/// the errors are returned as a `SiriResponse` with a `Status=false` and an `ErrorCondition`
+fn add_siri_error_responses(spec: &mut oa::Spec, route: &str, errors: &[(u16, &str)]) {
+    let operation = spec
+        .paths
+        .get_mut(route)
+        .and_then(|p| p.get.as_mut())
+        .unwrap_or_else(|| panic!("route {} has not been added", route));
+    for (code, description) in errors {
+        operation.responses.insert(
+            code.to_string(),
+            oa::Response {
+                description: Some((*description).to_owned()),
+                content: Some(btreemap! {
+                    "application/json".to_owned() => oa::MediaType {
+                        schema: Some(oa::ObjectOrReference::Ref {
+                            ref_path: "#/components/schemas/SiriResponse".to_owned(),
+                        }),
+                        ..Default::default()
+                    }
+                }),
+                ..Default::default()
+            },
+        );
+    }
+}
+
+/// The siri routes can also give SIRI 2.0 XML responses
+/// (with the `.xml` variant of the route or with the `Accept` header)
+fn add_siri_xml_responses(spec: &mut oa::Spec, route: &str) {
+    if let Some(operation) = spec.paths.get_mut(route).and_then(|p| p.get.as_mut()) {
+        for response in operation.responses.values_mut() {
+            if let Some(content) = response.content.as_mut() {
+                content.insert("application/xml".to_owned(), oa::MediaType::default());
+            }
+        }
+    }
+}
+
 fn create_schema() -> oa::Spec {
     let mut spec = oa::Spec {
         openapi: "3.0.0".to_owned(),
@@ -171,15 +210,145 @@ fn create_schema() -> oa::Spec {
     );
 
     add_route!(spec, "/{dataset}" => super::Status, description = "status of a dataset", params = vec![]);
+    add_route!(spec, "/{dataset}/trips/{vehicle_journey_id}" => super::Trip,
+                description = "all the calls of a vehicle journey on a date, with their realtime updates",
+                params = super::TripParams::openapi_description(&mut spec));
+    add_route!(spec, "/{dataset}/siri/2.0/check-status.json" => crate::siri_lite::SiriResponse,
+                description = "siri-lite check status, to know if the dataset is available and when its data changed",
+                params = vec![super::message_identifier::MessageIdentifier::openapi_param(&mut spec)]);
     add_route!(spec, "/{dataset}/siri/2.0/stop-monitoring.json" => crate::siri_lite::SiriResponse,
                 description = "siri-lite stop monitoring",
                 params = super::StopMonitoringParams::openapi_description(&mut spec));
     add_route!(spec, "/{dataset}/siri/2.0/stoppoints-discovery.json" => crate::siri_lite::SiriResponse,
                 description = "siri-lite stop discovery",
                 params = super::StopPointsDiscoveryParams::openapi_description(&mut spec));
+    add_route!(spec, "/{dataset}/siri/2.0/lines-discovery.json" => crate::siri_lite::SiriResponse,
+                description = "siri-lite lines discovery",
+                params = super::LinesDiscoveryParams::openapi_description(&mut spec));
     add_route!(spec, "/{dataset}/siri/2.0/general-message.json" => crate::siri_lite::SiriResponse,
                 description = "siri-lite general message",
                 params = super::GeneralMessageParams::openapi_description(&mut spec));
+    add_route!(spec, "/{dataset}/siri/2.0/situation-exchange.json" => crate::siri_lite::SiriResponse,
+                description = "siri-lite situation exchange",
+                params = super::SituationExchangeParams::openapi_description(&mut spec));
+    add_route!(spec, "/{dataset}/siri/2.0/production-timetable.json" => crate::siri_lite::SiriResponse,
+                description = "siri-lite production timetable, the scheduled vehicle journeys by line",
+                params = super::ProductionTimetableParams::openapi_description(&mut spec));
+    add_route!(spec, "/{dataset}/siri/2.0/stop-timetable.json" => crate::siri_lite::SiriResponse,
+                description = "siri-lite stop timetable, the scheduled visits at a stop",
+                params = super::StopTimetableParams::openapi_description(&mut spec));
+
+    add_siri_error_responses(
+        &mut spec,
+        "/{dataset}/siri/2.0/stop-monitoring.json",
+        &[
+            (400, "OtherError: the parameters are not valid"),
+            (
+                404,
+                "InvalidDataReferencesError: the MonitoringRef is not a known stop",
+            ),
+            (
+                502,
+                "ServiceNotAvailableError: the dataset is temporarily unavailable",
+            ),
+        ],
+    );
+    add_siri_error_responses(
+        &mut spec,
+        "/{dataset}/siri/2.0/stoppoints-discovery.json",
+        &[
+            (400, "OtherError: the parameters are not valid"),
+            (
+                502,
+                "ServiceNotAvailableError: the dataset is temporarily unavailable",
+            ),
+        ],
+    );
+    add_siri_error_responses(
+        &mut spec,
+        "/{dataset}/siri/2.0/lines-discovery.json",
+        &[
+            (400, "OtherError: the parameters are not valid"),
+            (
+                502,
+                "ServiceNotAvailableError: the dataset is temporarily unavailable",
+            ),
+        ],
+    );
+    add_siri_error_responses(
+        &mut spec,
+        "/{dataset}/siri/2.0/general-message.json",
+        &[
+            (400, "OtherError: the parameters are not valid"),
+            (
+                404,
+                "InvalidDataReferencesError: the LineRef or StopPointRef is not known, NoInfoForTopicError: no realtime data available",
+            ),
+            (
+                502,
+                "ServiceNotAvailableError: the dataset is temporarily unavailable",
+            ),
+        ],
+    );
+    add_siri_error_responses(
+        &mut spec,
+        "/{dataset}/siri/2.0/situation-exchange.json",
+        &[
+            (400, "OtherError: the parameters are not valid"),
+            (404, "NoInfoForTopicError: no realtime data available"),
+            (
+                502,
+                "ServiceNotAvailableError: the dataset is temporarily unavailable",
+            ),
+        ],
+    );
+    add_siri_error_responses(
+        &mut spec,
+        "/{dataset}/siri/2.0/production-timetable.json",
+        &[
+            (
+                400,
+                "OtherError: the parameters are not valid, BeyondDataHorizon: the period is outside the loaded data",
+            ),
+            (
+                404,
+                "InvalidDataReferencesError: the LineRef is not a known line",
+            ),
+            (
+                502,
+                "ServiceNotAvailableError: the dataset is temporarily unavailable",
+            ),
+        ],
+    );
+    add_siri_error_responses(
+        &mut spec,
+        "/{dataset}/siri/2.0/stop-timetable.json",
+        &[
+            (
+                400,
+                "OtherError: the parameters are not valid, BeyondDataHorizon: the window is outside the loaded data",
+            ),
+            (
+                404,
+                "InvalidDataReferencesError: the MonitoringRef is not a known stop",
+            ),
+            (
+                502,
+                "ServiceNotAvailableError: the dataset is temporarily unavailable",
+            ),
+        ],
+    );
+    for route in &[
+        "/{dataset}/siri/2.0/stop-monitoring.json",
+        "/{dataset}/siri/2.0/stoppoints-discovery.json",
+        "/{dataset}/siri/2.0/lines-discovery.json",
+        "/{dataset}/siri/2.0/general-message.json",
+        "/{dataset}/siri/2.0/situation-exchange.json",
+        "/{dataset}/siri/2.0/production-timetable.json",
+        "/{dataset}/siri/2.0/stop-timetable.json",
+    ] {
+        add_siri_xml_responses(&mut spec, route);
+    }
 
     // for gtfs-rt we don't really want to define the response, it's too complex
     add_path_item_with_undefined_response(
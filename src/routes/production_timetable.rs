@@ -0,0 +1,218 @@
+use super::estimated_timetable::get_line_ref;
+use super::message_identifier::MessageIdentifier;
+use super::open_api::make_param;
+use super::siri_error::{Delivery, SiriErrorResponse};
+use super::siri_format::SiriFormat;
+use super::siri_profile::RequestedProfile;
+use crate::datasets::{Connection, Dataset, DatedVehicleJourney, RealTimeDataset};
+use crate::extractors::{get_siri_dataset, RealTimeDatasetWrapper};
+use crate::siri_lite::profile::local_id;
+use crate::siri_lite::shared::{CommonDelivery, SiriError};
+use crate::siri_lite::{
+    production_timetable as model, service_delivery::ServiceDelivery, DateTime, Siri, SiriResponse,
+};
+use actix_web::{web, HttpResponse};
+use chrono::NaiveDateTime;
+use std::collections::{BTreeMap, HashMap, HashSet};
+
+#[derive(Deserialize, Debug, Default)]
+#[serde(rename_all = "PascalCase")]
+pub struct Params {
+    /// Filter the vehicle journeys of the given line's id
+    line_ref: Option<String>,
+    /// Beginning of the requested period
+    /// The default is the current time of the query
+    start_time: Option<DateTime>,
+    /// End of the requested period
+    /// The default is the end of the data horizon
+    end_time: Option<DateTime>,
+}
+
+impl Params {
+    pub fn openapi_description(spec: &mut openapi::v3_0::Spec) -> Vec<openapi::v3_0::Parameter> {
+        vec![
+            make_param::<String>(spec, "LineRef", false),
+            make_param::<DateTime>(spec, "StartTime", false),
+            make_param::<DateTime>(spec, "EndTime", false),
+            MessageIdentifier::openapi_param(spec),
+            RequestedProfile::openapi_param(spec),
+        ]
+    }
+}
+
+/// Period requested by the client, checked against the horizon of the base schedule
+///
+/// The connections are only computed for the days of the generation period, so there is
+/// no data to give outside of it
+pub(crate) fn requested_period(
+    data: &Dataset,
+    start_time: &Option<DateTime>,
+    end_time: &Option<DateTime>,
+) -> Result<(NaiveDateTime, NaiveDateTime), SiriError> {
+    let generation_period = &data.timetable.period;
+    let horizon_begin = generation_period.begin.and_hms(0, 0, 0);
+    let horizon_end = generation_period.end().and_hms(0, 0, 0);
+    let start = start_time
+        .as_ref()
+        .map(|d| d.naive_in(data.timezone))
+        .unwrap_or_else(|| {
+            chrono::Utc::now()
+                .with_timezone(&data.timezone)
+                .naive_local()
+        });
+    let end = end_time
+        .as_ref()
+        .map_or(horizon_end, |d| d.naive_in(data.timezone));
+
+    if start < horizon_begin || start > horizon_end || end > horizon_end {
+        return Err(SiriError::BeyondDataHorizon(format!(
+            "the period [{}, {}] is outside the data horizon [{}, {}]",
+            start, end, horizon_begin, horizon_end
+        )));
+    }
+    if end < start {
+        return Err(SiriError::InvalidRequest(format!(
+            "the end of the period ({}) is before its start ({})",
+            end, start
+        )));
+    }
+    Ok((start, end))
+}
+
+fn create_dated_call(data: &Dataset, connection: &Connection) -> model::DatedCall {
+    let stop = &data.ntm.stop_points[connection.stop_point_idx];
+    model::DatedCall {
+        stop_point_ref: stop.id.clone(),
+        order: connection.sequence as u16,
+        stop_point_name: stop.name.clone(),
+        aimed_arrival_time: DateTime::at_stop(data, connection, connection.arr_time),
+        aimed_departure_time: DateTime::at_stop(data, connection, connection.dep_time),
+    }
+}
+
+fn create_dated_vehicle_journey(
+    data: &Dataset,
+    dated_vj: &DatedVehicleJourney,
+    connections: &[&Connection],
+) -> model::DatedVehicleJourney {
+    let vj = &data.ntm.vehicle_journeys[dated_vj.vj_idx];
+    // we consider that the siri's operator in transmodel's company
+    let operator_ref = data
+        .ntm
+        .get_corresponding_from_idx(dated_vj.vj_idx)
+        .into_iter()
+        .next()
+        .map(|idx| data.ntm.companies[idx].id.clone());
+
+    model::DatedVehicleJourney {
+        dated_vehicle_journey_code: format!("{}:{}", &vj.id, dated_vj.date),
+        operator_ref,
+        dated_calls: connections
+            .iter()
+            .map(|c| create_dated_call(data, c))
+            .collect(),
+    }
+}
+
+/// Create the timetable of all the vehicle journeys running in the requested period, by line
+pub(crate) fn production_timetable(
+    request: Params,
+    rt_dataset: &RealTimeDataset,
+) -> Result<SiriResponse, SiriError> {
+    let data = get_siri_dataset(&rt_dataset.base_schedule_dataset)?;
+    let (start, end) = requested_period(data, &request.start_time, &request.end_time)?;
+
+    let requested_line_ref = request.line_ref.as_deref().map(local_id);
+    if let Some(unknown_line) = requested_line_ref.filter(|l| data.ntm.lines.get(l).is_none()) {
+        return Err(SiriError::InvalidDataReferences(format!(
+            "impossible to find line: '{}'",
+            unknown_line
+        )));
+    }
+
+    // a vehicle journey is in the period if it stops at least once during the period
+    let dated_vjs: HashSet<&DatedVehicleJourney> = data
+        .timetable
+        .connections
+        .iter()
+        .filter(|c| c.dep_time >= start && c.dep_time <= end)
+        .map(|c| &c.dated_vj)
+        .filter(|dated_vj| {
+            requested_line_ref.is_none() || requested_line_ref == get_line_ref(dated_vj, data)
+        })
+        .collect();
+
+    // we group all the connections of those vehicle journeys
+    let mut connections_by_vj: HashMap<&DatedVehicleJourney, Vec<&Connection>> = HashMap::new();
+    for connection in &data.timetable.connections {
+        if dated_vjs.contains(&connection.dated_vj) {
+            connections_by_vj
+                .entry(&connection.dated_vj)
+                .or_insert_with(Vec::new)
+                .push(connection);
+        }
+    }
+
+    // and the vehicle journeys by line, sorted by their first departure
+    let mut vjs_by_line: BTreeMap<&str, Vec<(NaiveDateTime, model::DatedVehicleJourney)>> =
+        BTreeMap::new();
+    for (dated_vj, mut connections) in connections_by_vj {
+        connections.sort_by_key(|c| c.sequence);
+        let first_departure = connections.first().map(|c| c.dep_time).unwrap_or(start);
+        vjs_by_line
+            .entry(get_line_ref(dated_vj, data).unwrap_or(""))
+            .or_insert_with(Vec::new)
+            .push((
+                first_departure,
+                create_dated_vehicle_journey(data, dated_vj, &connections),
+            ));
+    }
+
+    let frames = vjs_by_line
+        .into_iter()
+        .map(|(line_ref, mut vjs)| {
+            vjs.sort_by(|(a_dt, a), (b_dt, b)| {
+                a_dt.cmp(b_dt).then_with(|| {
+                    a.dated_vehicle_journey_code
+                        .cmp(&b.dated_vehicle_journey_code)
+                })
+            });
+            model::DatedTimetableVersionFrame {
+                recorded_at_time: data.loaded_at,
+                line_ref: line_ref.to_owned(),
+                dated_vehicle_journey: vjs.into_iter().map(|(_, vj)| vj).collect(),
+            }
+        })
+        .collect();
+
+    Ok(SiriResponse {
+        siri: Siri {
+            service_delivery: Some(ServiceDelivery {
+                production_timetable_delivery: vec![model::ProductionTimetableDelivery {
+                    common: CommonDelivery::default(),
+                    dated_timetable_version_frame: frames,
+                }],
+                ..ServiceDelivery::new(data.feed_construction_info.dataset_info.producer_ref())
+            }),
+            ..Default::default()
+        },
+    })
+}
+
+pub async fn production_timetable_query(
+    web::Query(query): web::Query<Params>,
+    rt_dataset_wrapper: RealTimeDatasetWrapper,
+    format: SiriFormat,
+    message_identifier: MessageIdentifier,
+    profile: RequestedProfile,
+) -> Result<HttpResponse, SiriErrorResponse> {
+    production_timetable(query, &rt_dataset_wrapper)
+        .map(|mut r| {
+            if let Ok(data) = rt_dataset_wrapper.get_siri_base_schedule_dataset() {
+                profile.apply(&mut r, data);
+            }
+            r.set_request_message_ref(&message_identifier.0);
+            format.response(&r)
+        })
+        .map_err(|e| SiriErrorResponse::new(Delivery::ProductionTimetable, format, e))
+}
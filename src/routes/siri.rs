@@ -12,9 +12,14 @@ pub async fn siri_endpoint(
     let dataset_id = &dataset.feed_construction_info.dataset_info.id;
     Ok(web::Json(
         btreemap! {
+            "check-status" => Link::from_scoped_url(&req, "check_status_query", dataset_id),
             "stop-monitoring" => Link::from_scoped_url(&req, "stop_monitoring_query", dataset_id),
             "stoppoints-discovery" => Link::from_scoped_url(&req, "stoppoints_discovery_query", dataset_id),
+            "lines-discovery" => Link::from_scoped_url(&req, "lines_discovery_query", dataset_id),
             "general-message" => Link::from_scoped_url(&req, "general_message_query", dataset_id),
+            "situation-exchange" => Link::from_scoped_url(&req, "situation_exchange_query", dataset_id),
+            "production-timetable" => Link::from_scoped_url(&req, "production_timetable_query", dataset_id),
+            "stop-timetable" => Link::from_scoped_url(&req, "stop_timetable_query", dataset_id),
         }
         .into(),
     ))
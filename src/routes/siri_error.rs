@@ -0,0 +1,196 @@
+use super::siri_format::SiriFormat;
+use crate::siri_lite::general_message::GeneralMessageDelivery;
+use crate::siri_lite::lines_delivery::LinesDelivery;
+use crate::siri_lite::production_timetable::ProductionTimetableDelivery;
+use crate::siri_lite::service_delivery::{ServiceDelivery, StopMonitoringDelivery};
+use crate::siri_lite::shared::{CommonDelivery, SiriError};
+use crate::siri_lite::situation_exchange::SituationExchangeDelivery;
+use crate::siri_lite::stop_points_delivery::StopPointsDelivery;
+use crate::siri_lite::stop_timetable::StopTimetableDelivery;
+use crate::siri_lite::subscription::{
+    ResponseStatus, SubscriptionResponse, TerminateSubscriptionResponse, TerminationResponseStatus,
+};
+use crate::siri_lite::{Siri, SiriResponse};
+use actix_web::{web, HttpResponse, ResponseError};
+
+/// The different siri deliveries
+///
+/// used to return an error in the delivery the client expects
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Delivery {
+    StopMonitoring,
+    GeneralMessage,
+    SituationExchange,
+    ProductionTimetable,
+    StopTimetable,
+    StopPointsDiscovery,
+    LinesDiscovery,
+    Subscription,
+    TerminateSubscription,
+}
+
+/// Error of a siri route
+///
+/// Contrary to the other routes, the errors are not returned as plain text, but
+/// as a siri response, with a `Status=false` and an `ErrorCondition` in the delivery
+#[derive(Debug)]
+pub struct SiriErrorResponse {
+    pub delivery: Delivery,
+    pub format: SiriFormat,
+    pub error: SiriError,
+}
+
+impl SiriErrorResponse {
+    pub fn new(delivery: Delivery, format: SiriFormat, error: SiriError) -> Self {
+        Self {
+            delivery,
+            format,
+            error,
+        }
+    }
+
+    pub fn siri_response(&self) -> SiriResponse {
+        let siri = match self.delivery {
+            Delivery::StopPointsDiscovery => Siri {
+                stop_points_delivery: Some(StopPointsDelivery {
+                    common: CommonDelivery::from_error(&self.error),
+                    annotated_stop_point: vec![],
+                }),
+                ..Default::default()
+            },
+            Delivery::LinesDiscovery => Siri {
+                lines_delivery: Some(LinesDelivery {
+                    common: CommonDelivery::from_error(&self.error),
+                    annotated_line_ref: vec![],
+                }),
+                ..Default::default()
+            },
+            Delivery::StopMonitoring => Siri {
+                service_delivery: Some(ServiceDelivery {
+                    common: CommonDelivery::from_error(&self.error),
+                    stop_monitoring_delivery: vec![StopMonitoringDelivery {
+                        version: "2.0".to_owned(),
+                        response_time_stamp: chrono::Utc::now().to_rfc3339(),
+                        request_message_ref: None,
+                        subscriber_ref: None,
+                        subscription_ref: None,
+                        status: false,
+                        error_condition: Some(self.error.error_condition()),
+                        monitored_stop_visit: vec![],
+                    }],
+                    ..Default::default()
+                }),
+                ..Default::default()
+            },
+            Delivery::GeneralMessage => Siri {
+                service_delivery: Some(ServiceDelivery {
+                    common: CommonDelivery::from_error(&self.error),
+                    general_message_delivery: vec![GeneralMessageDelivery {
+                        common: CommonDelivery::from_error(&self.error),
+                        ..Default::default()
+                    }],
+                    ..Default::default()
+                }),
+                ..Default::default()
+            },
+            Delivery::SituationExchange => Siri {
+                service_delivery: Some(ServiceDelivery {
+                    common: CommonDelivery::from_error(&self.error),
+                    situation_exchange_delivery: vec![SituationExchangeDelivery {
+                        common: CommonDelivery::from_error(&self.error),
+                        situations: vec![],
+                    }],
+                    ..Default::default()
+                }),
+                ..Default::default()
+            },
+            Delivery::ProductionTimetable => Siri {
+                service_delivery: Some(ServiceDelivery {
+                    common: CommonDelivery::from_error(&self.error),
+                    production_timetable_delivery: vec![ProductionTimetableDelivery {
+                        common: CommonDelivery::from_error(&self.error),
+                        dated_timetable_version_frame: vec![],
+                    }],
+                    ..Default::default()
+                }),
+                ..Default::default()
+            },
+            Delivery::StopTimetable => Siri {
+                service_delivery: Some(ServiceDelivery {
+                    common: CommonDelivery::from_error(&self.error),
+                    stop_timetable_delivery: vec![StopTimetableDelivery {
+                        common: CommonDelivery::from_error(&self.error),
+                        timetabled_stop_visit: vec![],
+                    }],
+                    ..Default::default()
+                }),
+                ..Default::default()
+            },
+            Delivery::Subscription => Siri {
+                subscription_response: Some(SubscriptionResponse {
+                    response_timestamp: chrono::Utc::now(),
+                    responder_ref: None,
+                    request_message_ref: None,
+                    response_status: vec![ResponseStatus::from_error(None, &self.error)],
+                }),
+                ..Default::default()
+            },
+            Delivery::TerminateSubscription => Siri {
+                terminate_subscription_response: Some(TerminateSubscriptionResponse {
+                    response_timestamp: chrono::Utc::now(),
+                    responder_ref: None,
+                    request_message_ref: None,
+                    termination_response_status: vec![TerminationResponseStatus::from_error(
+                        None,
+                        &self.error,
+                    )],
+                }),
+                ..Default::default()
+            },
+        };
+        SiriResponse { siri }
+    }
+}
+
+impl std::fmt::Display for SiriErrorResponse {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.error)
+    }
+}
+
+impl ResponseError for SiriErrorResponse {
+    fn status_code(&self) -> actix_web::http::StatusCode {
+        self.error.status_code()
+    }
+
+    fn error_response(&self) -> HttpResponse {
+        self.format
+            .response_with_status(self.status_code(), &self.siri_response())
+    }
+}
+
+/// Query configuration for the siri routes,
+/// the invalid parameters are reported in the siri delivery
+pub fn siri_query_config(delivery: Delivery) -> web::QueryConfig {
+    web::QueryConfig::default().error_handler(move |err, req| {
+        SiriErrorResponse::new(
+            delivery,
+            SiriFormat::negotiate(req),
+            SiriError::InvalidRequest(format!("invalid parameters: {}", err)),
+        )
+        .into()
+    })
+}
+
+/// Json configuration for the siri routes with a request body,
+/// the invalid bodies are reported in the siri response
+pub fn siri_json_config(delivery: Delivery) -> web::JsonConfig {
+    web::JsonConfig::default().error_handler(move |err, _req| {
+        SiriErrorResponse::new(
+            delivery,
+            SiriFormat::Json,
+            SiriError::InvalidRequest(format!("invalid request: {}", err)),
+        )
+        .into()
+    })
+}
@@ -0,0 +1,64 @@
+use crate::siri_lite::{xml, SiriResponse};
+use actix_web::http::{header, StatusCode};
+use actix_web::{dev::Payload, FromRequest, HttpRequest, HttpResponse};
+use futures::future::{ok, Ready};
+
+/// Format of the siri responses
+///
+/// By default the responses are in siri-lite json,
+/// but the SIRI 2.0 XML can be requested with the `.xml` variant of the routes
+/// or with the `Accept` header
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SiriFormat {
+    Json,
+    Xml,
+}
+
+fn is_xml_media_type(media_type: &str) -> bool {
+    let media_type = media_type.split(';').next().unwrap_or("").trim();
+    media_type == "application/xml" || media_type == "text/xml"
+}
+
+impl SiriFormat {
+    pub fn negotiate(req: &HttpRequest) -> Self {
+        if req.path().trim_end_matches('/').ends_with(".xml") {
+            return SiriFormat::Xml;
+        }
+        // we only consider the preferred media type of the client
+        let prefers_xml = req
+            .headers()
+            .get(header::ACCEPT)
+            .and_then(|h| h.to_str().ok())
+            .and_then(|accept| accept.split(',').next())
+            .map(is_xml_media_type)
+            .unwrap_or(false);
+        if prefers_xml {
+            SiriFormat::Xml
+        } else {
+            SiriFormat::Json
+        }
+    }
+
+    pub fn response(self, siri: &SiriResponse) -> HttpResponse {
+        self.response_with_status(StatusCode::OK, siri)
+    }
+
+    pub fn response_with_status(self, status: StatusCode, siri: &SiriResponse) -> HttpResponse {
+        match self {
+            SiriFormat::Json => HttpResponse::build(status).json(siri),
+            SiriFormat::Xml => HttpResponse::build(status)
+                .content_type("application/xml")
+                .body(xml::to_xml(siri)),
+        }
+    }
+}
+
+impl FromRequest for SiriFormat {
+    type Config = ();
+    type Error = actix_web::Error;
+    type Future = Ready<Result<SiriFormat, actix_web::Error>>;
+
+    fn from_request(req: &HttpRequest, _payload: &mut Payload) -> Self::Future {
+        ok(SiriFormat::negotiate(req))
+    }
+}
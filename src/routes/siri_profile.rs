@@ -0,0 +1,62 @@
+use super::open_api::make_param;
+use super::siri_error::{Delivery, SiriErrorResponse};
+use super::siri_format::SiriFormat;
+use crate::datasets::Dataset;
+use crate::siri_lite::profile::SiriProfile;
+use crate::siri_lite::shared::SiriError;
+use crate::siri_lite::SiriResponse;
+use actix_web::{dev::Payload, error, web, FromRequest, HttpRequest};
+use futures::future::{ready, Ready};
+
+#[derive(Deserialize, Default)]
+struct Params {
+    profile: Option<SiriProfile>,
+}
+
+/// Siri profile asked in the `profile` query parameter of a siri-lite request
+///
+/// If not given, the profile of the dataset is used
+#[derive(Debug, Clone, Copy, Default)]
+pub struct RequestedProfile(pub Option<SiriProfile>);
+
+impl RequestedProfile {
+    pub fn openapi_param(spec: &mut openapi::v3_0::Spec) -> openapi::v3_0::Parameter {
+        make_param::<SiriProfile>(spec, "profile", false)
+    }
+
+    /// Adapt the response to the requested profile
+    pub fn apply(self, response: &mut SiriResponse, dataset: &Dataset) {
+        self.0
+            .unwrap_or(dataset.feed_construction_info.dataset_info.siri_profile)
+            .apply(response, dataset)
+    }
+}
+
+impl FromRequest for RequestedProfile {
+    type Config = ();
+    type Error = actix_web::Error;
+    type Future = Ready<Result<RequestedProfile, actix_web::Error>>;
+
+    fn from_request(req: &HttpRequest, _payload: &mut Payload) -> Self::Future {
+        // the other query parameters are checked by the route,
+        // an invalid query is only an error if it concerns the profile
+        let profile = match web::Query::<Params>::from_query(req.query_string()) {
+            Ok(params) => Ok(RequestedProfile(params.into_inner().profile)),
+            Err(_) if !req.query_string().contains("profile=") => Ok(RequestedProfile(None)),
+            Err(e) => {
+                let message = format!("invalid profile: {}", e);
+                // on the siri routes the error is given in the siri delivery
+                Err(match req.app_data::<Delivery>() {
+                    Some(delivery) => SiriErrorResponse::new(
+                        *delivery,
+                        SiriFormat::negotiate(req),
+                        SiriError::InvalidRequest(message),
+                    )
+                    .into(),
+                    None => error::ErrorBadRequest(message),
+                })
+            }
+        };
+        ready(profile)
+    }
+}
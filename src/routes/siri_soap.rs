@@ -0,0 +1,299 @@
+use super::siri_error::{Delivery, SiriErrorResponse};
+use super::siri_format::SiriFormat;
+use super::{
+    general_message, lines_discovery, production_timetable, situation_exchange, stop_monitoring,
+    stop_timetable, stoppoints_discovery,
+};
+use crate::extractors::RealTimeDatasetWrapper;
+use crate::siri_lite::shared::SiriError;
+use crate::siri_lite::xml::{self, ToXml, XmlWriter, SIRI_NAMESPACE};
+use crate::siri_lite::SiriResponse;
+use actix_web::HttpResponse;
+
+const SOAP_NAMESPACE: &str = "http://schemas.xmlsoap.org/soap/envelope/";
+const SIRI_WSDL_NAMESPACE: &str = "http://wsdl.siri.org.uk";
+
+/// SIRI SOAP request
+///
+/// The parameters of the request are read as a flat list of (name, value)
+/// with the same names as the siri-lite query parameters,
+/// to be able to reuse the siri-lite logic
+#[derive(Debug)]
+struct SoapRequest {
+    delivery: Delivery,
+    /// id of the request, echoed back in the response as its `RequestMessageRef`
+    message_identifier: Option<String>,
+    params: Vec<(String, String)>,
+}
+
+impl SoapRequest {
+    fn operation(&self) -> &'static str {
+        operation(self.delivery)
+    }
+}
+
+fn operation(delivery: Delivery) -> &'static str {
+    match delivery {
+        Delivery::StopMonitoring => "GetStopMonitoring",
+        Delivery::GeneralMessage => "GetGeneralMessage",
+        Delivery::SituationExchange => "GetSituationExchange",
+        Delivery::ProductionTimetable => "GetProductionTimetable",
+        Delivery::StopTimetable => "GetStopTimetable",
+        Delivery::StopPointsDiscovery => "StopPointsDiscovery",
+        Delivery::LinesDiscovery => "LinesDiscovery",
+        Delivery::Subscription => "Subscribe",
+        Delivery::TerminateSubscription => "DeleteSubscription",
+    }
+}
+
+fn find_child<'a, 'input>(
+    node: roxmltree::Node<'a, 'input>,
+    name: &str,
+) -> Option<roxmltree::Node<'a, 'input>> {
+    node.children()
+        .find(|n| n.is_element() && n.tag_name().name() == name)
+}
+
+fn child_text(node: Option<roxmltree::Node<'_, '_>>, name: &str) -> Option<String> {
+    node.and_then(|n| find_child(n, name))
+        .and_then(|n| n.text())
+        .map(|t| t.trim().to_owned())
+}
+
+// flatten all the leaf elements of the request as (name, value)
+// the name of nested elements are joined with a '.'
+// (like `BoundingBox.UpperLeft.Longitude`)
+fn read_params(node: roxmltree::Node<'_, '_>, prefix: &str, params: &mut Vec<(String, String)>) {
+    for child in node.children().filter(|n| n.is_element()) {
+        let name = if prefix.is_empty() {
+            child.tag_name().name().to_owned()
+        } else {
+            format!("{}.{}", prefix, child.tag_name().name())
+        };
+        if child.children().any(|n| n.is_element()) {
+            read_params(child, &name, params);
+        } else if let Some(text) = child.text() {
+            params.push((name, text.trim().to_owned()));
+        }
+    }
+}
+
+fn parse_request(body: &str) -> Result<SoapRequest, String> {
+    let doc =
+        roxmltree::Document::parse(body).map_err(|e| format!("invalid xml request: {}", e))?;
+    let envelope = doc.root_element();
+    if envelope.tag_name().name() != "Envelope" {
+        return Err("the request is not a SOAP envelope".to_owned());
+    }
+    let operation_node = find_child(envelope, "Body")
+        .and_then(|b| b.children().find(|n| n.is_element()))
+        .ok_or_else(|| "no request found in the SOAP body".to_owned())?;
+
+    let delivery = match operation_node.tag_name().name() {
+        "GetStopMonitoring" => Delivery::StopMonitoring,
+        "GetGeneralMessage" => Delivery::GeneralMessage,
+        "GetSituationExchange" => Delivery::SituationExchange,
+        "GetProductionTimetable" => Delivery::ProductionTimetable,
+        "GetStopTimetable" => Delivery::StopTimetable,
+        "StopPointsDiscovery" => Delivery::StopPointsDiscovery,
+        "LinesDiscovery" => Delivery::LinesDiscovery,
+        o => return Err(format!("unsupported SIRI operation {}", o)),
+    };
+
+    let service_request_info = find_child(operation_node, "ServiceRequestInfo");
+    let request = find_child(operation_node, "Request")
+        .ok_or_else(|| "no Request in the SIRI request".to_owned())?;
+
+    let mut params = vec![];
+    read_params(request, "", &mut params);
+    if delivery == Delivery::StopPointsDiscovery {
+        // in siri-lite the bounding box parameters are named after the structure
+        for (name, _) in &mut params {
+            if name.starts_with("BoundingBox.") {
+                *name = name.replacen("BoundingBox.", "BoundingBoxStructure.", 1);
+            }
+        }
+    }
+    // in siri-lite the bounds of the period are given directly
+    for (name, _) in &mut params {
+        for period in &["ValidityPeriod.", "DepartureWindow."] {
+            if let Some(bound) = name.strip_prefix(period) {
+                *name = bound.to_owned();
+            }
+        }
+    }
+
+    Ok(SoapRequest {
+        delivery,
+        message_identifier: child_text(Some(request), "MessageIdentifier")
+            .or_else(|| child_text(service_request_info, "MessageIdentifier")),
+        params,
+    })
+}
+
+fn read_query_params<T: serde::de::DeserializeOwned>(
+    params: &[(String, String)],
+) -> Result<T, SiriError> {
+    let query = url::form_urlencoded::Serializer::new(String::new())
+        .extend_pairs(params)
+        .finish();
+    actix_web::web::Query::<T>::from_query(&query)
+        .map(|q| q.into_inner())
+        .map_err(|e| SiriError::InvalidRequest(format!("invalid parameters: {}", e)))
+}
+
+fn dispatch(
+    request: &SoapRequest,
+    rt_dataset_wrapper: RealTimeDatasetWrapper,
+) -> Result<SiriResponse, SiriError> {
+    let mut response = match request.delivery {
+        Delivery::StopMonitoring => {
+            let params = read_query_params(&request.params)?;
+            stop_monitoring::stop_monitoring(params, &rt_dataset_wrapper)
+        }
+        Delivery::GeneralMessage => {
+            let params = read_query_params(&request.params)?;
+            general_message::general_message(params, &rt_dataset_wrapper)
+        }
+        Delivery::SituationExchange => {
+            let params = read_query_params(&request.params)?;
+            situation_exchange::situation_exchange(params, &rt_dataset_wrapper)
+        }
+        Delivery::ProductionTimetable => {
+            let params = read_query_params(&request.params)?;
+            production_timetable::production_timetable(params, &rt_dataset_wrapper)
+        }
+        Delivery::StopTimetable => {
+            let params = read_query_params(&request.params)?;
+            stop_timetable::stop_timetable(params, &rt_dataset_wrapper)
+        }
+        Delivery::StopPointsDiscovery => {
+            let params = read_query_params(&request.params)?;
+            let dataset = rt_dataset_wrapper.get_siri_base_schedule_dataset()?;
+            Ok(stoppoints_discovery::filter(dataset, params))
+        }
+        Delivery::LinesDiscovery => {
+            let params = read_query_params(&request.params)?;
+            let dataset = rt_dataset_wrapper.get_siri_base_schedule_dataset()?;
+            Ok(lines_discovery::filter(dataset, params))
+        }
+        Delivery::Subscription | Delivery::TerminateSubscription => {
+            Err(SiriError::CapabilityNotSupported(format!(
+                "{} is not supported in SOAP",
+                request.operation()
+            )))
+        }
+    }?;
+    // the SOAP requests have no parameter for the profile, the one of the dataset is used
+    let dataset = rt_dataset_wrapper.get_siri_base_schedule_dataset()?;
+    let profile = dataset.feed_construction_info.dataset_info.siri_profile;
+    profile.apply(&mut response, dataset);
+    Ok(response)
+}
+
+fn soap_envelope<F: FnOnce(&mut XmlWriter)>(content: F) -> String {
+    let mut w = XmlWriter::default();
+    w.element_with_attributes("S:Envelope", &[("xmlns:S", SOAP_NAMESPACE)], |w| {
+        w.element("S:Body", content)
+    });
+    format!(
+        "<?xml version=\"1.0\" encoding=\"UTF-8\"?>{}",
+        w.into_string()
+    )
+}
+
+fn soap_response(request: &SoapRequest, response: &SiriResponse) -> String {
+    let response_name = format!("sw:{}Response", request.operation());
+    soap_envelope(|w| {
+        w.element_with_attributes(
+            &response_name,
+            &[
+                ("xmlns:sw", SIRI_WSDL_NAMESPACE),
+                ("xmlns:siri", SIRI_NAMESPACE),
+            ],
+            |w| {
+                if let Some(delivery) = &response.siri.stop_points_delivery {
+                    w.element_with_attributes(
+                        "Answer",
+                        &[("version", delivery.common.version.as_str())],
+                        |w| {
+                            w.prefixed("siri", |w| {
+                                xml::write_stop_points_delivery_content(delivery, w)
+                            })
+                        },
+                    );
+                }
+                if let Some(delivery) = &response.siri.lines_delivery {
+                    w.element_with_attributes(
+                        "Answer",
+                        &[("version", delivery.common.version.as_str())],
+                        |w| w.prefixed("siri", |w| xml::write_lines_delivery_content(delivery, w)),
+                    );
+                }
+                if let Some(delivery) = &response.siri.service_delivery {
+                    w.element("ServiceDeliveryInfo", |w| {
+                        w.prefixed("siri", |w| {
+                            w.text("ResponseTimestamp", &delivery.common.response_time_stamp);
+                            w.opt_text("ProducerRef", &delivery.producer_ref);
+                            w.opt_text("Address", &delivery.address);
+                            w.opt_text(
+                                "ResponseMessageIdentifier",
+                                &delivery.response_message_identifier,
+                            );
+                            w.opt_text("RequestMessageRef", &delivery.common.request_message_ref);
+                        })
+                    });
+                    w.element("Answer", |w| {
+                        w.prefixed("siri", |w| {
+                            delivery.stop_monitoring_delivery.write_xml(w);
+                            delivery.general_message_delivery.write_xml(w);
+                            delivery.situation_exchange_delivery.write_xml(w);
+                            delivery.production_timetable_delivery.write_xml(w);
+                            delivery.stop_timetable_delivery.write_xml(w);
+                        })
+                    });
+                }
+                w.element("AnswerExtension", |_| {});
+            },
+        );
+    })
+}
+
+fn soap_fault(message: &str) -> String {
+    soap_envelope(|w| {
+        w.element("S:Fault", |w| {
+            w.text("faultcode", "S:Client");
+            w.text("faultstring", message);
+        })
+    })
+}
+
+/// SIRI SOAP interface
+///
+/// The SIRI requests (`GetStopMonitoring`, `GetGeneralMessage`, `GetSituationExchange`,
+/// `GetProductionTimetable`, `GetStopTimetable`, `StopPointsDiscovery` and `LinesDiscovery`)
+/// are dispatched to the same logic as the siri-lite routes.
+/// The siri errors are given in the `Answer` of the SOAP response,
+/// and only the invalid SOAP requests are given as SOAP faults.
+pub async fn siri_soap_query(
+    body: String,
+    rt_dataset_wrapper: RealTimeDatasetWrapper,
+) -> HttpResponse {
+    let request = match parse_request(&body) {
+        Ok(r) => r,
+        Err(e) => {
+            return HttpResponse::InternalServerError()
+                .content_type("text/xml; charset=utf-8")
+                .body(soap_fault(&e))
+        }
+    };
+
+    let mut response = dispatch(&request, rt_dataset_wrapper).unwrap_or_else(|e| {
+        SiriErrorResponse::new(request.delivery, SiriFormat::Xml, e).siri_response()
+    });
+    response.set_request_message_ref(&request.message_identifier);
+
+    HttpResponse::Ok()
+        .content_type("text/xml; charset=utf-8")
+        .body(soap_response(&request, &response))
+}
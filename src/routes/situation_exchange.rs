@@ -0,0 +1,303 @@
+use super::general_message::{
+    display_alert, get_impacted_objects, get_line_id, read_feed, ImpactedObjects,
+};
+use super::message_identifier::MessageIdentifier;
+use super::open_api::make_param;
+use super::siri_error::{Delivery, SiriErrorResponse};
+use super::siri_format::SiriFormat;
+use super::siri_profile::RequestedProfile;
+use crate::datasets::RealTimeDataset;
+use crate::extractors::{get_siri_dataset, RealTimeDatasetWrapper};
+use crate::siri_lite::{
+    general_message::NaturalLangString,
+    service_delivery::ServiceDelivery,
+    shared::{CommonDelivery, SiriError},
+    situation_exchange as sx, DateTime, Siri, SiriResponse,
+};
+use crate::transit_realtime;
+use crate::utils;
+use actix_web::{web, HttpResponse};
+use chrono::NaiveDateTime;
+use std::collections::BTreeMap;
+use transit_model::Model;
+
+#[derive(Deserialize, Debug, Default)]
+#[serde(rename_all = "PascalCase")]
+pub struct Params {
+    /// datetime at which the situations are active
+    /// The default is the current time of the query
+    request_timestamp: Option<DateTime>,
+}
+
+impl Params {
+    pub fn openapi_description(spec: &mut openapi::v3_0::Spec) -> Vec<openapi::v3_0::Parameter> {
+        vec![
+            make_param::<DateTime>(spec, "RequestTimestamp", false),
+            MessageIdentifier::openapi_param(spec),
+            RequestedProfile::openapi_param(spec),
+        ]
+    }
+}
+
+/// Siri's reason of a situation
+enum Reason {
+    Miscellaneous(&'static str),
+    Personnel(&'static str),
+    Equipment(&'static str),
+    Environment(&'static str),
+}
+
+fn get_reason(alert: &transit_realtime::Alert) -> Reason {
+    use transit_realtime::alert::Cause;
+    match alert.cause() {
+        Cause::UnknownCause => Reason::Miscellaneous("unknown"),
+        Cause::OtherCause => Reason::Miscellaneous("undefinedProblem"),
+        Cause::TechnicalProblem => Reason::Equipment("technicalProblem"),
+        Cause::Strike => Reason::Personnel("industrialAction"),
+        Cause::Demonstration => Reason::Miscellaneous("demonstration"),
+        Cause::Accident => Reason::Miscellaneous("accident"),
+        Cause::Holiday => Reason::Miscellaneous("specialEvent"),
+        Cause::Weather => Reason::Environment("badWeather"),
+        Cause::Maintenance => Reason::Equipment("maintenanceWork"),
+        Cause::Construction => Reason::Equipment("constructionWork"),
+        Cause::PoliceActivity => Reason::Miscellaneous("policeActivity"),
+        Cause::MedicalEmergency => Reason::Miscellaneous("illVehicleOccupants"),
+    }
+}
+
+fn get_severity(alert: &transit_realtime::Alert) -> &'static str {
+    use transit_realtime::alert::Effect;
+    match alert.effect() {
+        Effect::NoService => "severe",
+        Effect::ReducedService | Effect::SignificantDelays | Effect::Detour | Effect::StopMoved => {
+            "normal"
+        }
+        Effect::ModifiedService | Effect::AdditionalService => "slight",
+        Effect::OtherEffect => "undefined",
+        Effect::UnknownEffect => "unknown",
+    }
+}
+
+fn get_texts(ts: &Option<transit_realtime::TranslatedString>) -> Vec<NaturalLangString> {
+    ts.iter()
+        .flat_map(|t| t.translation.iter())
+        .map(|t| NaturalLangString {
+            value: t.text.clone(),
+            lang: t.language.clone(),
+        })
+        .collect()
+}
+
+// a period without start is considered to be already active at the creation of the situation
+fn get_validity_periods(
+    alert: &transit_realtime::Alert,
+    creation_time: NaiveDateTime,
+    timezone: chrono_tz::Tz,
+) -> Vec<(NaiveDateTime, Option<NaiveDateTime>)> {
+    if alert.active_period.is_empty() {
+        // no active period means that the alert is always active
+        return vec![(creation_time, None)];
+    }
+    alert
+        .active_period
+        .iter()
+        .map(|p| {
+            (
+                utils::read_pbf_dt(p.start, timezone).unwrap_or(creation_time),
+                utils::read_pbf_dt(p.end, timezone),
+            )
+        })
+        .collect()
+}
+
+fn make_range(
+    period: (NaiveDateTime, Option<NaiveDateTime>),
+    timezone: chrono_tz::Tz,
+) -> sx::HalfOpenTimestampRange {
+    sx::HalfOpenTimestampRange {
+        start_time: DateTime::in_timezone(period.0, timezone),
+        end_time: period.1.map(|end| DateTime::in_timezone(end, timezone)),
+    }
+}
+
+fn get_affects(impacted: ImpactedObjects, model: &Model) -> sx::Affects {
+    // the lines are grouped by network
+    let mut networks: BTreeMap<Option<String>, sx::AffectedNetwork> = impacted
+        .networks
+        .into_iter()
+        .map(|n| {
+            (
+                Some(n.clone()),
+                sx::AffectedNetwork {
+                    network_ref: Some(n),
+                    all_lines: true,
+                    affected_line: vec![],
+                },
+            )
+        })
+        .collect();
+    for line_ref in impacted.lines {
+        let network_ref = model.lines.get(&line_ref).map(|l| l.network_id.clone());
+        let network = networks
+            .entry(network_ref.clone())
+            .or_insert_with(|| sx::AffectedNetwork {
+                network_ref,
+                all_lines: false,
+                affected_line: vec![],
+            });
+        if !network.all_lines {
+            network.affected_line.push(sx::AffectedLine { line_ref });
+        }
+    }
+
+    let mut affects = sx::Affects {
+        affected_network: networks.into_values().collect(),
+        ..Default::default()
+    };
+    for stop in impacted.stops {
+        if let Some(stop_area) = model.stop_areas.get(&stop) {
+            affects.affected_stop_place.push(sx::AffectedStopPlace {
+                place_name: Some(stop_area.name.clone()),
+                stop_place_ref: stop,
+            });
+        } else {
+            affects.affected_stop_point.push(sx::AffectedStopPoint {
+                stop_point_name: model.stop_points.get(&stop).map(|sp| sp.name.clone()),
+                stop_point_ref: stop,
+            });
+        }
+    }
+    affects.affected_vehicle_journey = impacted
+        .vehicle_journeys
+        .into_iter()
+        .map(|vj| sx::AffectedVehicleJourney {
+            line_ref: model
+                .vehicle_journeys
+                .get(&vj)
+                .map(|vj| get_line_id(&vj.route_id, model)),
+            vehicle_journey_ref: vj,
+        })
+        .collect();
+    affects
+}
+
+fn make_situation(
+    id: &str,
+    alert: &transit_realtime::Alert,
+    creation_time: NaiveDateTime,
+    participant_ref: &str,
+    timezone: chrono_tz::Tz,
+    model: &Model,
+) -> sx::PtSituationElement {
+    let periods = get_validity_periods(alert, creation_time, timezone);
+    let publication_window = (
+        periods.iter().map(|p| p.0).min().unwrap_or(creation_time),
+        // the window is open if one of the periods is open
+        periods
+            .iter()
+            .map(|p| p.1)
+            .collect::<Option<Vec<_>>>()
+            .and_then(|ends| ends.into_iter().max()),
+    );
+    let mut situation = sx::PtSituationElement {
+        creation_time: DateTime::in_timezone(creation_time, timezone),
+        participant_ref: participant_ref.to_owned(),
+        situation_number: id.to_owned(),
+        source: sx::SituationSource {
+            source_type: "feed".to_owned(),
+        },
+        progress: "open".to_owned(),
+        validity_period: periods
+            .into_iter()
+            .map(|p| make_range(p, timezone))
+            .collect(),
+        publication_window: make_range(publication_window, timezone),
+        miscellaneous_reason: None,
+        personnel_reason: None,
+        equipment_reason: None,
+        environment_reason: None,
+        severity: get_severity(alert).to_owned(),
+        summary: get_texts(&alert.header_text),
+        description: get_texts(&alert.description_text),
+        affects: get_affects(get_impacted_objects(alert, model), model),
+    };
+    match get_reason(alert) {
+        Reason::Miscellaneous(r) => situation.miscellaneous_reason = Some(r.to_owned()),
+        Reason::Personnel(r) => situation.personnel_reason = Some(r.to_owned()),
+        Reason::Equipment(r) => situation.equipment_reason = Some(r.to_owned()),
+        Reason::Environment(r) => situation.environment_reason = Some(r.to_owned()),
+    }
+    situation
+}
+
+pub(crate) fn situation_exchange(
+    request: Params,
+    rt_dataset: &RealTimeDataset,
+) -> Result<SiriResponse, SiriError> {
+    let dataset = get_siri_dataset(&rt_dataset.base_schedule_dataset)?;
+    let timezone = dataset.timezone;
+
+    let requested_dt = request
+        .request_timestamp
+        .map(|d| d.naive_in(timezone))
+        .unwrap_or_else(|| chrono::Utc::now().with_timezone(&timezone).naive_local());
+    let feed = read_feed(rt_dataset)?;
+    // the GTFS-RT does not give the creation of the alerts, we use the datetime of its update
+    let creation_time = rt_dataset
+        .gtfs_rt
+        .as_ref()
+        .map(|rt| rt.datetime)
+        .unwrap_or_else(chrono::Utc::now)
+        .with_timezone(&timezone)
+        .naive_local();
+    let participant_ref = dataset.feed_construction_info.dataset_info.producer_ref();
+
+    let situations = feed
+        .entity
+        .iter()
+        .filter(|e| e.is_deleted != Some(true))
+        .filter_map(|e| e.alert.as_ref().map(|a| (e, a)))
+        .filter(|(_, a)| display_alert(a, requested_dt, timezone))
+        .map(|(e, a)| {
+            make_situation(
+                &e.id,
+                a,
+                creation_time,
+                participant_ref,
+                timezone,
+                &dataset.ntm,
+            )
+        })
+        .collect();
+
+    Ok(SiriResponse {
+        siri: Siri {
+            service_delivery: Some(ServiceDelivery {
+                situation_exchange_delivery: vec![sx::SituationExchangeDelivery {
+                    common: CommonDelivery::default(),
+                    situations,
+                }],
+                ..ServiceDelivery::new(participant_ref)
+            }),
+            ..Default::default()
+        },
+    })
+}
+
+pub async fn situation_exchange_query(
+    web::Query(query): web::Query<Params>,
+    realtime_dataset_wrapper: RealTimeDatasetWrapper,
+    format: SiriFormat,
+    message_identifier: MessageIdentifier,
+    profile: RequestedProfile,
+) -> Result<HttpResponse, SiriErrorResponse> {
+    situation_exchange(query, &realtime_dataset_wrapper)
+        .map(|mut r| {
+            if let Ok(data) = realtime_dataset_wrapper.get_siri_base_schedule_dataset() {
+                profile.apply(&mut r, data);
+            }
+            r.set_request_message_ref(&message_identifier.0);
+            format.response(&r)
+        })
+        .map_err(|e| SiriErrorResponse::new(Delivery::SituationExchange, format, e))
+}
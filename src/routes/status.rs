@@ -9,6 +9,8 @@ pub struct Status {
     #[serde(flatten)]
     dataset: super::ExposedDataset,
     loaded_at: chrono::DateTime<chrono::Utc>,
+    /// Version of the base schedules (ETag, Last-Modified date or modification time and size of the files)
+    source_version: Option<String>,
     #[serde(flatten)]
     pub links: Links,
 }
@@ -24,12 +26,18 @@ pub async fn status_query(
     Ok(web::Json(Status {
         dataset: (&dataset.feed_construction_info.dataset_info).into(),
         loaded_at: dataset.loaded_at,
+        source_version: dataset.source_version.clone(),
         links: btreemap! {
             "gtfs-rt" => Link::from_scoped_url(&req, "gtfs_rt_protobuf", &dataset_id),
             "gtfs-rt.json" => Link::from_scoped_url(&req, "gtfs_rt_json", &dataset_id),
             "stop-monitoring" => Link::from_scoped_url(&req, "stop_monitoring_query", &dataset_id),
             "stoppoints-discovery" => Link::from_scoped_url(&req, "stoppoints_discovery_query", &dataset_id),
+            "lines-discovery" => Link::from_scoped_url(&req, "lines_discovery_query", &dataset_id),
             "general-message" => Link::from_scoped_url(&req, "general_message_query", &dataset_id),
+            "situation-exchange" => Link::from_scoped_url(&req, "situation_exchange_query", &dataset_id),
+            "check-status" => Link::from_scoped_url(&req, "check_status_query", &dataset_id),
+            "production-timetable" => Link::from_scoped_url(&req, "production_timetable_query", &dataset_id),
+            "stop-timetable" => Link::from_scoped_url(&req, "stop_timetable_query", &dataset_id),
             "siri-lite" => Link::from_scoped_url(&req, "siri_endpoint", &dataset_id),
         }
         .into(),
@@ -1,9 +1,17 @@
+use super::message_identifier::MessageIdentifier;
 use super::open_api::make_param;
-use crate::datasets::{Connection, Dataset, RealTimeConnection, UpdatedTimetable};
-use crate::extractors::RealTimeDatasetWrapper;
+use super::siri_error::{Delivery, SiriErrorResponse};
+use super::siri_format::SiriFormat;
+use super::siri_profile::RequestedProfile;
+use super::trip::add_trip_links;
+use crate::datasets::{Connection, Dataset, RealTimeConnection, RealTimeDataset, UpdatedTimetable};
+use crate::extractors::{get_siri_dataset, RealTimeDatasetWrapper};
+use crate::siri_lite::profile::local_id;
+use crate::siri_lite::shared::SiriError;
+use crate::siri_lite::subscription::StopMonitoringRequest;
 use crate::siri_lite::{self, service_delivery as model, SiriResponse};
 use crate::utils;
-use actix_web::{error, web};
+use actix_web::{web, HttpRequest, HttpResponse};
 use openapi_schema::OpenapiSchema;
 use transit_model::collection::Idx;
 use transit_model::objects::StopPoint;
@@ -61,10 +69,30 @@ impl Params {
             make_param::<DataFreshness>(spec, "DataFreshness", false),
             make_param::<utils::Duration>(spec, "PreviewInterval", false),
             make_param::<u16>(spec, "MaximumStopVisits", false),
+            MessageIdentifier::openapi_param(spec),
+            RequestedProfile::openapi_param(spec),
         ]
     }
 }
 
+impl From<&StopMonitoringRequest> for Params {
+    fn from(request: &StopMonitoringRequest) -> Self {
+        Params {
+            _requestor_ref: None,
+            monitoring_ref: request.monitoring_ref.clone(),
+            line_ref: request.line_ref.clone(),
+            _destination_ref: None,
+            // the subscriptions are always on the next departures
+            start_time: None,
+            preview_interval: request.preview_interval.clone(),
+            data_freshness: DataFreshness::RealTime,
+            maximum_stop_visits: request
+                .maximum_stop_visits
+                .unwrap_or_else(default_stop_visits),
+        }
+    }
+}
+
 fn create_monitored_stop_visit(
     data: &Dataset,
     connection: &Connection,
@@ -94,26 +122,39 @@ fn create_monitored_stop_visit(
         vehicle_at_stop: None,
         destination_display: None,
         arrival_status: None,
-        aimed_arrival_time: Some(siri_lite::DateTime(connection.arr_time)),
-        aimed_departure_time: Some(siri_lite::DateTime(connection.dep_time)),
+        aimed_arrival_time: Some(siri_lite::DateTime::at_stop(
+            data,
+            connection,
+            connection.arr_time,
+        )),
+        aimed_departure_time: Some(siri_lite::DateTime::at_stop(
+            data,
+            connection,
+            connection.dep_time,
+        )),
         expected_arrival_time: updated_connection
             .and_then(|c| c.arr_time)
-            .map(siri_lite::DateTime),
+            .map(|dt| siri_lite::DateTime::at_stop(data, connection, dt)),
         expected_departure_time: updated_connection
             .and_then(|c| c.dep_time)
-            .map(siri_lite::DateTime),
+            .map(|dt| siri_lite::DateTime::at_stop(data, connection, dt)),
     };
 
     model::MonitoredStopVisit {
         monitoring_ref: stop.id.clone(),
         monitored_vehicle_journey: model::MonitoredVehicleJourney {
             line_ref,
+            framed_vehicle_journey_ref: Some(model::FramedVehicleJourneyRef {
+                data_frame_ref: connection.dated_vj.date.to_string(),
+                dated_vehicle_journey_ref: vj.id.clone(),
+            }),
             service_info: model::ServiceInfoGroup { operator_ref },
             journey_pattern_ref: None,
             monitored_call: Some(call),
         },
         recorded_at_time: update_time,
         item_identifier: format!("{}:{}", &stop.id, &vj.id),
+        links: Default::default(),
     }
 }
 
@@ -143,12 +184,16 @@ fn create_stop_monitoring(
     request: &Params,
 ) -> Vec<model::StopMonitoringDelivery> {
     // if we want to datetime in the query, we get the current_time (in the timezone of the dataset)
-    let requested_start_time = request.start_time.as_ref().map(|d| d.0).unwrap_or_else(|| {
-        chrono::Utc::now()
-            .with_timezone(&data.timezone)
-            .naive_local()
-    });
-    let requested_line_ref = request.line_ref.as_deref();
+    let requested_start_time = request
+        .start_time
+        .as_ref()
+        .map(|d| d.naive_in(data.timezone))
+        .unwrap_or_else(|| {
+            chrono::Utc::now()
+                .with_timezone(&data.timezone)
+                .naive_local()
+        });
+    let requested_line_ref = request.line_ref.as_deref().map(local_id);
     let stop_visit = data
         .timetable
         .connections
@@ -176,35 +221,38 @@ fn create_stop_monitoring(
 
     vec![model::StopMonitoringDelivery {
         version: "2.0".to_owned(),
-        response_time_stamp: chrono::Local::now().to_rfc3339(),
+        response_time_stamp: chrono::Utc::now().to_rfc3339(),
         request_message_ref: None,
+        subscriber_ref: None,
+        subscription_ref: None,
         status: true,
+        error_condition: None,
         monitored_stop_visit: stop_visit,
     }]
 }
 
-fn validate_params(request: &mut Params) -> actix_web::Result<()> {
+fn validate_params(request: &mut Params) -> Result<(), SiriError> {
     // we silently bound the maximum stop visits to 20
     request.maximum_stop_visits = std::cmp::min(request.maximum_stop_visits, 20);
     Ok(())
 }
 
-fn stop_monitoring(
+pub(crate) fn stop_monitoring(
     mut request: Params,
-    rt_dataset_wrapper: RealTimeDatasetWrapper,
-) -> actix_web::Result<siri_lite::SiriResponse> {
-    let data = rt_dataset_wrapper.get_base_schedule_dataset()?;
+    rt_dataset: &RealTimeDataset,
+) -> Result<siri_lite::SiriResponse, SiriError> {
+    let data = get_siri_dataset(&rt_dataset.base_schedule_dataset)?;
 
-    let updated_timetable = &rt_dataset_wrapper.updated_timetable;
+    let updated_timetable = &rt_dataset.updated_timetable;
 
     validate_params(&mut request)?;
 
     let stop_idx = data
         .ntm
         .stop_points
-        .get_idx(&request.monitoring_ref)
+        .get_idx(local_id(&request.monitoring_ref))
         .ok_or_else(|| {
-            error::ErrorNotFound(format!(
+            SiriError::InvalidDataReferences(format!(
                 "impossible to find stop: '{}'",
                 &request.monitoring_ref
             ))
@@ -213,14 +261,15 @@ fn stop_monitoring(
     Ok(siri_lite::SiriResponse {
         siri: siri_lite::Siri {
             service_delivery: Some(model::ServiceDelivery {
-                producer_ref: None, // TODO take the id of the dataset ?
                 stop_monitoring_delivery: create_stop_monitoring(
                     stop_idx,
                     &data,
                     updated_timetable,
                     &request,
                 ),
-                ..Default::default()
+                ..model::ServiceDelivery::new(
+                    data.feed_construction_info.dataset_info.producer_ref(),
+                )
             }),
             ..Default::default()
         },
@@ -228,8 +277,22 @@ fn stop_monitoring(
 }
 
 pub async fn stop_monitoring_query(
+    req: HttpRequest,
     web::Query(query): web::Query<Params>,
     rt_dataset_wrapper: RealTimeDatasetWrapper,
-) -> actix_web::Result<web::Json<SiriResponse>> {
-    Ok(web::Json(stop_monitoring(query, rt_dataset_wrapper)?))
+    format: SiriFormat,
+    message_identifier: MessageIdentifier,
+    profile: RequestedProfile,
+) -> Result<HttpResponse, SiriErrorResponse> {
+    stop_monitoring(query, &rt_dataset_wrapper)
+        .map(|mut r| {
+            if let Ok(data) = rt_dataset_wrapper.get_siri_base_schedule_dataset() {
+                let dataset_id = &data.feed_construction_info.dataset_info.id;
+                add_trip_links(&mut r, &req, dataset_id);
+                profile.apply(&mut r, data);
+            }
+            r.set_request_message_ref(&message_identifier.0);
+            format.response(&r)
+        })
+        .map_err(|e| SiriErrorResponse::new(Delivery::StopMonitoring, format, e))
 }
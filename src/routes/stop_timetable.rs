@@ -0,0 +1,145 @@
+use super::estimated_timetable::get_line_ref;
+use super::message_identifier::MessageIdentifier;
+use super::open_api::make_param;
+use super::production_timetable::requested_period;
+use super::siri_error::{Delivery, SiriErrorResponse};
+use super::siri_format::SiriFormat;
+use super::siri_profile::RequestedProfile;
+use crate::datasets::{Connection, Dataset, RealTimeDataset};
+use crate::extractors::{get_siri_dataset, RealTimeDatasetWrapper};
+use crate::siri_lite::profile::local_id;
+use crate::siri_lite::service_delivery::{FramedVehicleJourneyRef, ServiceDelivery};
+use crate::siri_lite::shared::{CommonDelivery, SiriError};
+use crate::siri_lite::{stop_timetable as model, DateTime, Siri, SiriResponse};
+use actix_web::{web, HttpResponse};
+
+#[derive(Deserialize, Debug)]
+#[serde(rename_all = "PascalCase")]
+pub struct Params {
+    /// Id of the stop_point on which we want the scheduled visits
+    monitoring_ref: String,
+    /// Filter the visits of the given line's id
+    line_ref: Option<String>,
+    /// Beginning of the departure window
+    /// The default is the current time of the query
+    start_time: Option<DateTime>,
+    /// End of the departure window
+    /// The default is the end of the data horizon
+    end_time: Option<DateTime>,
+}
+
+impl Params {
+    pub fn openapi_description(spec: &mut openapi::v3_0::Spec) -> Vec<openapi::v3_0::Parameter> {
+        vec![
+            make_param::<String>(spec, "MonitoringRef", true),
+            make_param::<String>(spec, "LineRef", false),
+            make_param::<DateTime>(spec, "StartTime", false),
+            make_param::<DateTime>(spec, "EndTime", false),
+            MessageIdentifier::openapi_param(spec),
+            RequestedProfile::openapi_param(spec),
+        ]
+    }
+}
+
+fn create_timetabled_stop_visit(
+    data: &Dataset,
+    connection: &Connection,
+) -> model::TimetabledStopVisit {
+    let stop = &data.ntm.stop_points[connection.stop_point_idx];
+    let vj = &data.ntm.vehicle_journeys[connection.dated_vj.vj_idx];
+    // we consider that the siri's operator in transmodel's company
+    let operator_ref = data
+        .ntm
+        .get_corresponding_from_idx(connection.dated_vj.vj_idx)
+        .into_iter()
+        .next()
+        .map(|idx| data.ntm.companies[idx].id.clone());
+
+    model::TimetabledStopVisit {
+        monitoring_ref: stop.id.clone(),
+        // there is no realtime data in the timetable, it is only the base schedule
+        recorded_at_time: data.loaded_at,
+        item_identifier: format!("{}:{}", &stop.id, &vj.id),
+        targeted_vehicle_journey: model::TargetedVehicleJourney {
+            line_ref: get_line_ref(&connection.dated_vj, data)
+                .unwrap_or("")
+                .to_owned(),
+            framed_vehicle_journey_ref: FramedVehicleJourneyRef {
+                data_frame_ref: connection.dated_vj.date.to_string(),
+                dated_vehicle_journey_ref: vj.id.clone(),
+            },
+            operator_ref,
+            targeted_call: model::TargetedCall {
+                order: connection.sequence as u16,
+                stop_point_name: stop.name.clone(),
+                aimed_arrival_time: DateTime::at_stop(data, connection, connection.arr_time),
+                aimed_departure_time: DateTime::at_stop(data, connection, connection.dep_time),
+            },
+        },
+    }
+}
+
+/// Create the scheduled visits at a stop point in the requested departure window
+pub(crate) fn stop_timetable(
+    request: Params,
+    rt_dataset: &RealTimeDataset,
+) -> Result<SiriResponse, SiriError> {
+    let data = get_siri_dataset(&rt_dataset.base_schedule_dataset)?;
+    let (start, end) = requested_period(data, &request.start_time, &request.end_time)?;
+
+    let stop_idx = data
+        .ntm
+        .stop_points
+        .get_idx(local_id(&request.monitoring_ref))
+        .ok_or_else(|| {
+            SiriError::InvalidDataReferences(format!(
+                "impossible to find stop: '{}'",
+                &request.monitoring_ref
+            ))
+        })?;
+    let requested_line_ref = request.line_ref.as_deref().map(local_id);
+
+    let visits = data
+        .timetable
+        .connections
+        .iter()
+        .skip_while(|c| c.dep_time < start)
+        .take_while(|c| c.dep_time <= end)
+        .filter(|c| c.stop_point_idx == stop_idx)
+        .filter(|c| {
+            requested_line_ref.is_none() || requested_line_ref == get_line_ref(&c.dated_vj, data)
+        })
+        .map(|c| create_timetabled_stop_visit(data, c))
+        .collect();
+
+    Ok(SiriResponse {
+        siri: Siri {
+            service_delivery: Some(ServiceDelivery {
+                stop_timetable_delivery: vec![model::StopTimetableDelivery {
+                    common: CommonDelivery::default(),
+                    timetabled_stop_visit: visits,
+                }],
+                ..ServiceDelivery::new(data.feed_construction_info.dataset_info.producer_ref())
+            }),
+            ..Default::default()
+        },
+    })
+}
+
+pub async fn stop_timetable_query(
+    web::Query(query): web::Query<Params>,
+    rt_dataset_wrapper: RealTimeDatasetWrapper,
+    format: SiriFormat,
+    message_identifier: MessageIdentifier,
+    profile: RequestedProfile,
+) -> Result<HttpResponse, SiriErrorResponse> {
+    stop_timetable(query, &rt_dataset_wrapper)
+        .map(|mut r| {
+            if let Ok(data) = rt_dataset_wrapper.get_siri_base_schedule_dataset() {
+                profile.apply(&mut r, data);
+            }
+            r.set_request_message_ref(&message_identifier.0);
+            format.response(&r)
+        })
+        .map_err(|e| SiriErrorResponse::new(Delivery::StopTimetable, format, e))
+}
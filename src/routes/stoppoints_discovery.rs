@@ -1,14 +1,29 @@
+use super::message_identifier::MessageIdentifier;
 use super::open_api::make_param;
+use super::siri_error::{Delivery, SiriErrorResponse};
+use super::siri_format::SiriFormat;
+use super::siri_profile::RequestedProfile;
 use crate::extractors::DatasetWrapper;
+use crate::search::tokenize;
+use crate::siri_lite::lines_delivery::vehicle_mode;
+use crate::siri_lite::profile::local_id;
 use crate::siri_lite::shared::CommonDelivery;
-use crate::siri_lite::stop_points_delivery::{AnnotatedStopPoint, StopPointsDelivery};
+use crate::siri_lite::stop_points_delivery::{self, AnnotatedStopPoint, StopPointsDelivery};
 use crate::siri_lite::{Siri, SiriResponse};
-use actix_web::web;
+use actix_web::{web, HttpResponse};
+use std::collections::{BTreeSet, HashSet};
+use transit_model::collection::Idx;
+use transit_model::objects::{Coord, StopPoint};
+use transit_model::Model;
 
 fn default_limit() -> usize {
     20
 }
 
+fn default_radius() -> f64 {
+    500.
+}
+
 #[derive(Deserialize, Clone)]
 pub struct Params {
     q: Option<String>,
@@ -20,6 +35,23 @@ pub struct Params {
     lower_right_longitude: Option<f64>,
     #[serde(rename = "BoundingBoxStructure.LowerRight.Latitude")]
     lower_right_latitude: Option<f64>,
+    /// location of a proximity search, the stop points are then sorted by distance
+    #[serde(rename = "Latitude")]
+    latitude: Option<f64>,
+    #[serde(rename = "Longitude")]
+    longitude: Option<f64>,
+    /// radius in meters of the proximity search
+    #[serde(rename = "Radius", default = "default_radius")]
+    radius: f64,
+    /// only the stop points served by this line
+    #[serde(rename = "LineRef")]
+    line_ref: Option<String>,
+    /// only the stop points served by this mode of vehicle (bus, tram, metro, rail, ...)
+    #[serde(rename = "VehicleMode")]
+    vehicle_mode: Option<String>,
+    /// only the stop points with (or without) a wheelchair access
+    #[serde(rename = "WheelchairAccessible")]
+    wheelchair_accessible: Option<bool>,
     #[serde(default = "default_limit")]
     limit: usize,
     #[serde(default)]
@@ -34,8 +66,16 @@ impl Params {
             make_param::<f64>(spec, "BoundingBoxStructure.UpperLeft.Latitude", false),
             make_param::<f64>(spec, "BoundingBoxStructure.LowerRight.Longitude", false),
             make_param::<f64>(spec, "BoundingBoxStructure.LowerRight.Latitude", false),
+            make_param::<f64>(spec, "Latitude", false),
+            make_param::<f64>(spec, "Longitude", false),
+            make_param::<f64>(spec, "Radius", false),
+            make_param::<String>(spec, "LineRef", false),
+            make_param::<String>(spec, "VehicleMode", false),
+            make_param::<bool>(spec, "WheelchairAccessible", false),
             make_param::<usize>(spec, "limit", false),
             make_param::<usize>(spec, "offset", false),
+            MessageIdentifier::openapi_param(spec),
+            RequestedProfile::openapi_param(spec),
         ]
     }
 }
@@ -50,21 +90,95 @@ fn bounding_box_matches(
     coord.lon >= min_lon && coord.lon <= max_lon && coord.lat >= min_lat && coord.lat <= max_lat
 }
 
+// stop points served by a line, an unknown line serves no stop point
+fn line_stop_points(line_ref: &str, model: &Model) -> BTreeSet<Idx<StopPoint>> {
+    model
+        .lines
+        .get_idx(line_ref)
+        .map(|idx| model.get_corresponding_from_idx(idx))
+        .unwrap_or_default()
+}
+
+// stop points served by a siri vehicle mode
+fn mode_stop_points(mode: &str, model: &Model) -> BTreeSet<Idx<StopPoint>> {
+    model
+        .physical_modes
+        .iter()
+        .filter(|(_, m)| vehicle_mode(m).eq_ignore_ascii_case(mode))
+        .flat_map(|(idx, _)| model.get_corresponding_from_idx::<_, StopPoint>(idx))
+        .collect()
+}
+
 pub fn filter(data: &crate::datasets::Dataset, request: Params) -> SiriResponse {
     let model = &data.ntm;
 
-    let q = request.q.unwrap_or_default().to_lowercase();
+    let q = request.q.unwrap_or_default();
     let min_lon = request.upper_left_longitude.unwrap_or(-180.);
     let max_lon = request.lower_right_longitude.unwrap_or(180.);
     let min_lat = request.lower_right_latitude.unwrap_or(-90.);
     let max_lat = request.upper_left_latitude.unwrap_or(90.);
 
-    let filtered = model
-        .stop_points
-        .iter()
-        .filter(|(_, s)| s.name.to_lowercase().contains(&q))
-        .filter(|(_, s)| bounding_box_matches(&s.coord, min_lon, max_lon, min_lat, max_lat))
-        .map(|(id, _)| AnnotatedStopPoint::from(id, &model))
+    let stop_points: Vec<(Idx<StopPoint>, Option<f64>)> =
+        match (request.latitude, request.longitude) {
+            (Some(lat), Some(lon)) => {
+                // for a proximity search, the query only filters the stop points,
+                // they are sorted by distance
+                let matching: Option<HashSet<Idx<StopPoint>>> = if tokenize(&q).is_empty() {
+                    None
+                } else {
+                    Some(data.stop_points_index.search(&q).into_iter().collect())
+                };
+                data.stop_points_spatial_index
+                    .around(&Coord { lon, lat }, request.radius)
+                    .into_iter()
+                    .filter(|(idx, _)| matching.as_ref().map_or(true, |m| m.contains(idx)))
+                    .map(|(idx, distance)| (idx, Some(distance.round())))
+                    .collect()
+            }
+            // the stop points are ranked by relevance for the query
+            _ => data
+                .stop_points_index
+                .search(&q)
+                .into_iter()
+                .map(|idx| (idx, None))
+                .collect(),
+        };
+
+    let line_stop_points = request
+        .line_ref
+        .as_ref()
+        .map(|l| line_stop_points(local_id(l), model));
+    let mode_stop_points = request
+        .vehicle_mode
+        .as_ref()
+        .map(|m| mode_stop_points(m, model));
+    let wheelchair_access =
+        request
+            .wheelchair_accessible
+            .map(|accessible| if accessible { "true" } else { "false" });
+
+    let filtered = stop_points
+        .into_iter()
+        .filter(|(idx, _)| line_stop_points.as_ref().map_or(true, |s| s.contains(idx)))
+        .filter(|(idx, _)| mode_stop_points.as_ref().map_or(true, |s| s.contains(idx)))
+        .filter(|(idx, _)| {
+            wheelchair_access.map_or(true, |access| {
+                stop_points_delivery::wheelchair_access(&model.stop_points[*idx], model) == access
+            })
+        })
+        .filter(|(idx, _)| {
+            bounding_box_matches(
+                &model.stop_points[*idx].coord,
+                min_lon,
+                max_lon,
+                min_lat,
+                max_lat,
+            )
+        })
+        .map(|(idx, distance)| AnnotatedStopPoint {
+            distance,
+            ..AnnotatedStopPoint::from(idx, model)
+        })
         .skip(request.offset)
         .take(request.limit)
         .collect();
@@ -83,8 +197,16 @@ pub fn filter(data: &crate::datasets::Dataset, request: Params) -> SiriResponse
 pub async fn stoppoints_discovery_query(
     web::Query(query): web::Query<Params>,
     dataset_wrapper: DatasetWrapper,
-) -> actix_web::Result<web::Json<SiriResponse>> {
-    let dataset = dataset_wrapper.get_dataset()?;
+    format: SiriFormat,
+    message_identifier: MessageIdentifier,
+    profile: RequestedProfile,
+) -> Result<HttpResponse, SiriErrorResponse> {
+    let dataset = dataset_wrapper
+        .get_siri_dataset()
+        .map_err(|e| SiriErrorResponse::new(Delivery::StopPointsDiscovery, format, e))?;
 
-    Ok(web::Json(filter(&dataset, query)))
+    let mut response = filter(&dataset, query);
+    profile.apply(&mut response, dataset);
+    response.set_request_message_ref(&message_identifier.0);
+    Ok(format.response(&response))
 }
@@ -0,0 +1,68 @@
+use super::siri_error::{Delivery, SiriErrorResponse};
+use super::siri_format::SiriFormat;
+use crate::actors::{Subscribe, TerminateSubscription};
+use crate::extractors::DatasetActorsWrapper;
+use crate::siri_lite::shared::SiriError;
+use crate::siri_lite::subscription::SiriRequest;
+use crate::siri_lite::{Siri, SiriResponse};
+use actix_web::{web, HttpResponse};
+
+fn mailbox_error(e: actix::MailboxError) -> SiriError {
+    log::error!("error while querying the subscription actor: {:?}", e);
+    SiriError::Other("impossible to handle the subscriptions".to_owned())
+}
+
+/// Subscribe to some siri deliveries
+///
+/// The notifications are pushed as siri-lite json to the `ConsumerAddress` of the request
+/// each time the realtime data are updated
+pub async fn subscribe_query(
+    web::Json(request): web::Json<SiriRequest>,
+    dataset: DatasetActorsWrapper,
+) -> Result<HttpResponse, SiriErrorResponse> {
+    let to_error = |e| SiriErrorResponse::new(Delivery::Subscription, SiriFormat::Json, e);
+    let request = request.siri.subscription_request.ok_or_else(|| {
+        to_error(SiriError::InvalidRequest(
+            "no SubscriptionRequest in the request".to_owned(),
+        ))
+    })?;
+    let response = dataset
+        .actors
+        .subscriptions
+        .send(Subscribe(request))
+        .await
+        .map_err(|e| to_error(mailbox_error(e)))?;
+
+    Ok(SiriFormat::Json.response(&SiriResponse {
+        siri: Siri {
+            subscription_response: Some(response),
+            ..Default::default()
+        },
+    }))
+}
+
+/// Terminate some subscriptions
+pub async fn terminate_subscription_query(
+    web::Json(request): web::Json<SiriRequest>,
+    dataset: DatasetActorsWrapper,
+) -> Result<HttpResponse, SiriErrorResponse> {
+    let to_error = |e| SiriErrorResponse::new(Delivery::TerminateSubscription, SiriFormat::Json, e);
+    let request = request.siri.terminate_subscription_request.ok_or_else(|| {
+        to_error(SiriError::InvalidRequest(
+            "no TerminateSubscriptionRequest in the request".to_owned(),
+        ))
+    })?;
+    let response = dataset
+        .actors
+        .subscriptions
+        .send(TerminateSubscription(request))
+        .await
+        .map_err(|e| to_error(mailbox_error(e)))?;
+
+    Ok(SiriFormat::Json.response(&SiriResponse {
+        siri: Siri {
+            terminate_subscription_response: Some(response),
+            ..Default::default()
+        },
+    }))
+}
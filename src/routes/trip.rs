@@ -0,0 +1,299 @@
+use super::open_api::make_param;
+use crate::datasets::{Connection, Dataset, DatedVehicleJourney, ScheduleRelationship};
+use crate::extractors::RealTimeDatasetWrapper;
+use crate::routes::Link;
+use crate::siri_lite::{service_delivery::MonitoredStopVisit, DateTime, SiriResponse};
+use crate::transit_realtime;
+use actix_web::{error, web, HttpRequest};
+use maplit::btreemap;
+use openapi_schema::OpenapiSchema;
+
+#[derive(Deserialize, Debug)]
+pub struct Params {
+    /// date of the vehicle journey (YYYY-MM-DD)
+    /// The default is the current date in the dataset's timezone
+    date: Option<chrono::NaiveDate>,
+}
+
+impl Params {
+    pub fn openapi_description(spec: &mut openapi::v3_0::Spec) -> Vec<openapi::v3_0::Parameter> {
+        vec![
+            openapi::v3_0::Parameter {
+                location: "path".to_owned(),
+                ..make_param::<String>(spec, "vehicle_journey_id", true)
+            },
+            make_param::<String>(spec, "date", false),
+        ]
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, OpenapiSchema)]
+#[serde(rename_all = "PascalCase")]
+pub struct Call {
+    pub stop_point_ref: String,
+    pub stop_point_name: String,
+    pub order: u16,
+    /// Scheduled arrival time
+    pub aimed_arrival_time: DateTime,
+    /// Estimated arrival time
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub expected_arrival_time: Option<DateTime>,
+    /// Scheduled departure time
+    pub aimed_departure_time: DateTime,
+    /// Estimated departure time
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub expected_departure_time: Option<DateTime>,
+    /// true if the vehicle does not stop anymore at this stop
+    pub skipped: bool,
+}
+
+#[derive(Serialize, Deserialize, Debug, OpenapiSchema)]
+#[serde(rename_all = "PascalCase")]
+pub struct VehicleLocation {
+    pub longitude: f64,
+    pub latitude: f64,
+}
+
+#[derive(Serialize, Deserialize, Debug, OpenapiSchema)]
+#[serde(rename_all = "PascalCase")]
+pub struct Trip {
+    /// Id of the vehicle journey
+    pub dated_vehicle_journey_ref: String,
+    /// Date of the vehicle journey
+    pub data_frame_ref: String,
+    pub line_ref: String,
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub line_name: Option<String>,
+    pub route_ref: String,
+    /// Headsign of the vehicle journey
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub destination_display: Option<String>,
+    /// Id of the operator
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub operator_ref: Option<String>,
+    /// true if the whole vehicle journey has been cancelled
+    pub cancellation: bool,
+    /// Last known position of the vehicle
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub vehicle_location: Option<VehicleLocation>,
+    /// Occupancy of the vehicle, `seatsAvailable`, `standingAvailable` or `full`
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub occupancy: Option<String>,
+    /// All the calls of the vehicle journey
+    pub calls: Vec<Call>,
+}
+
+fn occupancy(status: transit_realtime::vehicle_position::OccupancyStatus) -> &'static str {
+    use transit_realtime::vehicle_position::OccupancyStatus;
+    match status {
+        OccupancyStatus::Empty
+        | OccupancyStatus::ManySeatsAvailable
+        | OccupancyStatus::FewSeatsAvailable => "seatsAvailable",
+        OccupancyStatus::StandingRoomOnly | OccupancyStatus::CrushedStandingRoomOnly => {
+            "standingAvailable"
+        }
+        OccupancyStatus::Full | OccupancyStatus::NotAcceptingPassengers => "full",
+    }
+}
+
+// the vehicle position of the dated vehicle journey, if the GTFS-RT gives it
+fn find_vehicle_position<'a>(
+    feed: &'a transit_realtime::FeedMessage,
+    vj_id: &str,
+    date: chrono::NaiveDate,
+) -> Option<&'a transit_realtime::VehiclePosition> {
+    let gtfs_date = date.format("%Y%m%d").to_string();
+    feed.entity
+        .iter()
+        .filter(|e| e.is_deleted != Some(true))
+        .filter_map(|e| e.vehicle.as_ref())
+        .find(|v| {
+            v.trip.as_ref().map_or(false, |t| {
+                t.trip_id.as_deref() == Some(vj_id)
+                    && t.start_date.as_ref().map_or(true, |d| *d == gtfs_date)
+            })
+        })
+}
+
+fn create_trip(
+    data: &Dataset,
+    dated_vj: &DatedVehicleJourney,
+    connections: &[(usize, &Connection)],
+    rt_dataset: &crate::datasets::RealTimeDataset,
+) -> Trip {
+    let model = &data.ntm;
+    let vj = &model.vehicle_journeys[dated_vj.vj_idx];
+    let line = model
+        .routes
+        .get(&vj.route_id)
+        .and_then(|r| model.lines.get(&r.line_id));
+    let realtime_connections = &rt_dataset.updated_timetable.realtime_connections;
+
+    let calls = connections
+        .iter()
+        .map(|(idx, connection)| {
+            let updated_connection = realtime_connections.get(idx);
+            let stop = &model.stop_points[connection.stop_point_idx];
+            Call {
+                stop_point_ref: stop.id.clone(),
+                stop_point_name: stop.name.clone(),
+                order: connection.sequence as u16,
+                aimed_arrival_time: DateTime::at_stop(data, connection, connection.arr_time),
+                expected_arrival_time: updated_connection
+                    .and_then(|c| c.arr_time)
+                    .map(|dt| DateTime::at_stop(data, connection, dt)),
+                aimed_departure_time: DateTime::at_stop(data, connection, connection.dep_time),
+                expected_departure_time: updated_connection
+                    .and_then(|c| c.dep_time)
+                    .map(|dt| DateTime::at_stop(data, connection, dt)),
+                skipped: matches!(
+                    updated_connection.map(|c| &c.schedule_relationship),
+                    Some(ScheduleRelationship::Skipped)
+                ),
+            }
+        })
+        .collect();
+
+    let cancellation = connections.iter().any(|(idx, _)| {
+        matches!(
+            realtime_connections
+                .get(idx)
+                .map(|c| &c.schedule_relationship),
+            Some(ScheduleRelationship::Cancelled)
+        )
+    });
+
+    // the vehicle position and the occupancy are directly read from the GTFS-RT
+    let feed = super::general_message::read_feed(rt_dataset).ok();
+    let vehicle_position = feed
+        .as_ref()
+        .and_then(|f| find_vehicle_position(f, &vj.id, dated_vj.date));
+
+    Trip {
+        dated_vehicle_journey_ref: vj.id.clone(),
+        data_frame_ref: dated_vj.date.to_string(),
+        line_ref: line.map_or_else(|| vj.route_id.clone(), |l| l.id.clone()),
+        line_name: line.map(|l| l.name.clone()),
+        route_ref: vj.route_id.clone(),
+        destination_display: vj.headsign.clone(),
+        // we consider that the siri's operator in transmodel's company
+        operator_ref: model
+            .get_corresponding_from_idx(dated_vj.vj_idx)
+            .into_iter()
+            .next()
+            .map(|idx| model.companies[idx].id.clone()),
+        cancellation,
+        vehicle_location: vehicle_position.and_then(|v| v.position.as_ref()).map(|p| {
+            VehicleLocation {
+                longitude: f64::from(p.longitude),
+                latitude: f64::from(p.latitude),
+            }
+        }),
+        occupancy: vehicle_position
+            .filter(|v| v.occupancy_status.is_some())
+            .map(|v| occupancy(v.occupancy_status()).to_owned()),
+        calls,
+    }
+}
+
+/// Link to the detail of a dated vehicle journey
+pub(crate) fn trip_link(
+    req: &HttpRequest,
+    dataset_id: &str,
+    vehicle_journey_id: &str,
+    date: &str,
+) -> Option<Link> {
+    req.url_for("trip_query", &[dataset_id, vehicle_journey_id])
+        .map(|mut url| {
+            url.set_query(Some(&format!("date={}", date)));
+            Link {
+                href: url.to_string(),
+                ..Default::default()
+            }
+        })
+        .ok()
+}
+
+/// Add to each stop visit a link to the detail of its vehicle journey
+pub(crate) fn add_trip_links(response: &mut SiriResponse, req: &HttpRequest, dataset_id: &str) {
+    let visits = response
+        .siri
+        .service_delivery
+        .iter_mut()
+        .flat_map(|d| d.stop_monitoring_delivery.iter_mut())
+        .flat_map(|d| d.monitored_stop_visit.iter_mut());
+    for visit in visits {
+        let MonitoredStopVisit {
+            monitored_vehicle_journey,
+            links,
+            ..
+        } = visit;
+        if let Some(link) = monitored_vehicle_journey
+            .framed_vehicle_journey_ref
+            .as_ref()
+            .and_then(|vj| {
+                trip_link(
+                    req,
+                    dataset_id,
+                    &vj.dated_vehicle_journey_ref,
+                    &vj.data_frame_ref,
+                )
+            })
+        {
+            *links = btreemap! {"trip" => link}.into();
+        }
+    }
+}
+
+pub async fn trip_query(
+    path: web::Path<(String, String)>,
+    web::Query(query): web::Query<Params>,
+    rt_dataset_wrapper: RealTimeDatasetWrapper,
+) -> actix_web::Result<web::Json<Trip>> {
+    let data = rt_dataset_wrapper.get_base_schedule_dataset()?;
+    // the path is `/{id}/trips/{vehicle_journey_id}/`
+    let (_, vehicle_journey_id) = path.into_inner();
+
+    let vj_idx = data
+        .ntm
+        .vehicle_journeys
+        .get_idx(&vehicle_journey_id)
+        .ok_or_else(|| {
+            error::ErrorNotFound(format!(
+                "impossible to find vehicle journey '{}'",
+                vehicle_journey_id
+            ))
+        })?;
+    let date = query.date.unwrap_or_else(|| {
+        chrono::Utc::now()
+            .with_timezone(&data.timezone)
+            .date()
+            .naive_local()
+    });
+    let dated_vj = DatedVehicleJourney { vj_idx, date };
+
+    let mut connections: Vec<(usize, &Connection)> = data
+        .connections_by_dated_vj
+        .get(&dated_vj)
+        .map(|indexes| {
+            indexes
+                .iter()
+                .map(|idx| (*idx, &data.timetable.connections[*idx]))
+                .collect()
+        })
+        .unwrap_or_default();
+    if connections.is_empty() {
+        return Err(error::ErrorNotFound(format!(
+            "the vehicle journey '{}' does not run on {}",
+            vehicle_journey_id, date
+        )));
+    }
+    connections.sort_by_key(|(_, c)| c.sequence);
+
+    Ok(web::Json(create_trip(
+        data,
+        &dated_vj,
+        &connections,
+        &rt_dataset_wrapper,
+    )))
+}
@@ -0,0 +1,281 @@
+//! Text search of the stop points, used by the stoppoints discovery
+//!
+//! The names of the stop points and of their stop areas are normalized (unicode decomposition,
+//! accent folding and lowercase) and split in tokens once, when the dataset is loaded.
+//! A query matches a stop point if each of its tokens matches a token of the stop point's name
+//! (or of its stop area's name), either exactly, as a prefix, or with a small typo.
+
+use std::collections::HashMap;
+use std::hash::Hash;
+use transit_model::collection::Idx;
+use transit_model::objects::{StopArea, StopPoint};
+use transit_model::Model;
+use unicode_normalization::{char::is_combining_mark, UnicodeNormalization};
+
+const EXACT_MATCH_SCORE: u32 = 3;
+const PREFIX_MATCH_SCORE: u32 = 2;
+const TYPO_MATCH_SCORE: u32 = 1;
+
+/// lowercase, remove the accents and split the text in alphanumeric tokens
+pub fn tokenize(text: &str) -> Vec<String> {
+    text.nfd()
+        .filter(|c| !is_combining_mark(*c))
+        .flat_map(char::to_lowercase)
+        .collect::<String>()
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|t| !t.is_empty())
+        .map(str::to_owned)
+        .collect()
+}
+
+// number of typos tolerated for a query token, the short tokens must match without typo
+fn max_typos(token: &str) -> usize {
+    match token.chars().count() {
+        0..=3 => 0,
+        4..=7 => 1,
+        _ => 2,
+    }
+}
+
+// optimal string alignment distance (a levenshtein distance with the transpositions)
+fn typo_distance(a: &[char], b: &[char]) -> usize {
+    let mut distances = vec![vec![0; b.len() + 1]; a.len() + 1];
+    for (i, row) in distances.iter_mut().enumerate() {
+        row[0] = i;
+    }
+    for j in 0..=b.len() {
+        distances[0][j] = j;
+    }
+    for i in 1..=a.len() {
+        for j in 1..=b.len() {
+            let cost = if a[i - 1] == b[j - 1] { 0 } else { 1 };
+            let mut d = (distances[i - 1][j] + 1)
+                .min(distances[i][j - 1] + 1)
+                .min(distances[i - 1][j - 1] + cost);
+            if i > 1 && j > 1 && a[i - 1] == b[j - 2] && a[i - 2] == b[j - 1] {
+                d = d.min(distances[i - 2][j - 2] + 1);
+            }
+            distances[i][j] = d;
+        }
+    }
+    distances[a.len()][b.len()]
+}
+
+/// score of the best match of a query token in the tokens of a name
+fn token_score(query_token: &str, name_tokens: &[String]) -> Option<u32> {
+    let query_chars: Vec<char> = query_token.chars().collect();
+    name_tokens
+        .iter()
+        .filter_map(|t| {
+            if t == query_token {
+                Some(EXACT_MATCH_SCORE)
+            } else if t.starts_with(query_token) {
+                Some(PREFIX_MATCH_SCORE)
+            } else {
+                let token_chars: Vec<char> = t.chars().collect();
+                // a typo is also tolerated in the prefix of the token
+                let prefix = &token_chars[..token_chars.len().min(query_chars.len())];
+                let max = max_typos(query_token);
+                if max > 0
+                    && (typo_distance(&query_chars, &token_chars) <= max
+                        || typo_distance(&query_chars, prefix) <= max)
+                {
+                    Some(TYPO_MATCH_SCORE)
+                } else {
+                    None
+                }
+            }
+        })
+        .max()
+}
+
+/// score of a name for the query, None if one of the query tokens does not match
+fn name_score(query_tokens: &[String], name_tokens: &[String]) -> Option<u32> {
+    query_tokens
+        .iter()
+        .map(|q| token_score(q, name_tokens))
+        .sum()
+}
+
+struct IndexedItem<K, G> {
+    key: K,
+    group: Option<G>,
+    name: String,
+    tokens: Vec<String>,
+}
+
+/// Search index of some named items, that can be grouped
+/// (like the stop points in their stop areas)
+pub struct SearchIndex<K, G> {
+    items: Vec<IndexedItem<K, G>>,
+    groups_tokens: HashMap<G, Vec<String>>,
+}
+
+/// Search index of the stop points, grouped by stop area
+pub type StopPointsIndex = SearchIndex<Idx<StopPoint>, Idx<StopArea>>;
+
+impl<K: Copy + Ord, G: Copy + Eq + Hash> SearchIndex<K, G> {
+    pub fn new<'a>(
+        items: impl IntoIterator<Item = (K, Option<G>, &'a str)>,
+        groups: impl IntoIterator<Item = (G, &'a str)>,
+    ) -> Self {
+        Self {
+            items: items
+                .into_iter()
+                .map(|(key, group, name)| IndexedItem {
+                    key,
+                    group,
+                    name: name.to_lowercase(),
+                    tokens: tokenize(name),
+                })
+                .collect(),
+            groups_tokens: groups
+                .into_iter()
+                .map(|(group, name)| (group, tokenize(name)))
+                .collect(),
+        }
+    }
+
+    /// Items matching the query, ranked by relevance
+    ///
+    /// An item matches if its name or its group's name matches the query.
+    /// Each item is given once, and the items of a same group are given together.
+    /// Without query all the items are given, in the index order.
+    pub fn search(&self, query: &str) -> Vec<K> {
+        let query_tokens = tokenize(query);
+        if query_tokens.is_empty() {
+            return self.items.iter().map(|i| i.key).collect();
+        }
+
+        // the items found by their own name are more relevant than the ones found by their group
+        let mut matches: Vec<(u32, bool, &IndexedItem<K, G>)> = self
+            .items
+            .iter()
+            .filter_map(|i| {
+                let own_score = name_score(&query_tokens, &i.tokens);
+                let group_score = i
+                    .group
+                    .and_then(|g| self.groups_tokens.get(&g))
+                    .and_then(|tokens| name_score(&query_tokens, tokens));
+                let score = own_score.max(group_score)?;
+                Some((score, own_score == Some(score), i))
+            })
+            .collect();
+
+        matches.sort_by(|(score_a, own_a, a), (score_b, own_b, b)| {
+            score_b
+                .cmp(score_a)
+                .then_with(|| own_b.cmp(own_a))
+                // at equal score, the shortest names are the most relevant
+                .then_with(|| a.tokens.len().cmp(&b.tokens.len()))
+                .then_with(|| a.name.cmp(&b.name))
+                .then_with(|| a.key.cmp(&b.key))
+        });
+
+        // the items of a group are given together, at the rank of the group's best item
+        let mut groups_positions: HashMap<G, usize> = HashMap::new();
+        let mut ranked: Vec<Vec<K>> = vec![];
+        for (_, _, i) in matches {
+            match i.group {
+                Some(g) => {
+                    let position = *groups_positions.entry(g).or_insert_with(|| {
+                        ranked.push(vec![]);
+                        ranked.len() - 1
+                    });
+                    ranked[position].push(i.key);
+                }
+                None => ranked.push(vec![i.key]),
+            }
+        }
+        ranked.into_iter().flatten().collect()
+    }
+}
+
+impl StopPointsIndex {
+    pub fn from_model(model: &Model) -> Self {
+        Self::new(
+            model.stop_points.iter().map(|(idx, sp)| {
+                (
+                    idx,
+                    model.stop_areas.get_idx(&sp.stop_area_id),
+                    sp.name.as_str(),
+                )
+            }),
+            model
+                .stop_areas
+                .iter()
+                .map(|(idx, sa)| (idx, sa.name.as_str())),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{tokenize, SearchIndex};
+
+    // items are given as (key, name, group), the groups' names being the group keys
+    fn search(items: &[(&'static str, &'static str, &'static str)], q: &str) -> Vec<&'static str> {
+        SearchIndex::new(
+            items
+                .iter()
+                .map(|(key, name, group)| (*key, Some(*group), *name)),
+            items.iter().map(|(_, _, group)| (*group, *group)),
+        )
+        .search(q)
+    }
+
+    #[test]
+    fn tokenization() {
+        assert_eq!(
+            tokenize("Château-d'Eau / Gare"),
+            vec!["chateau", "d", "eau", "gare"]
+        );
+        assert_eq!(tokenize("  "), Vec::<String>::new());
+    }
+
+    #[test]
+    fn accents_are_folded() {
+        let items = [("sp1", "Château d'Eau", "a"), ("sp2", "Gare", "b")];
+        assert_eq!(search(&items, "chateau"), vec!["sp1"]);
+        assert_eq!(search(&items, "CHÂTEAU"), vec!["sp1"]);
+    }
+
+    #[test]
+    fn prefix_and_typos() {
+        let items = [("sp1", "Bellecour", "a"), ("sp2", "Gare Part-Dieu", "b")];
+        assert_eq!(search(&items, "belle"), vec!["sp1"]);
+        assert_eq!(search(&items, "part dieu"), vec!["sp2"]);
+        assert_eq!(search(&items, "belecour"), vec!["sp1"]);
+        assert_eq!(search(&items, "gare prat dieu"), vec!["sp2"]);
+        // too many typos
+        assert!(search(&items, "bolocour").is_empty());
+        // all the words must match
+        assert!(search(&items, "gare bellecour").is_empty());
+    }
+
+    #[test]
+    fn results_are_ranked() {
+        let items = [
+            ("sp1", "Gares", "a"),
+            ("sp2", "Gare de Lyon", "b"),
+            ("sp3", "Gare", "c"),
+            ("sp4", "Gore", "d"),
+        ];
+        // exact matches first (the shortest names first), then prefix and typos
+        assert_eq!(search(&items, "gare"), vec!["sp3", "sp2", "sp1", "sp4"]);
+    }
+
+    #[test]
+    fn items_are_grouped() {
+        let items = [
+            ("sp1", "Quai A", "Hôtel de Ville"),
+            ("sp2", "Mairie", "Mairie"),
+            ("sp3", "Hôtel de Ville", "Hôtel de Ville"),
+        ];
+        // the items are found by their group's name, and given together
+        assert_eq!(search(&items, "hotel ville"), vec!["sp3", "sp1"]);
+        assert_eq!(search(&items, "mairie"), vec!["sp2"]);
+        // without query, all the items are given, in their order
+        assert_eq!(search(&items, ""), vec!["sp1", "sp2", "sp3"]);
+    }
+}
@@ -1,30 +1,74 @@
-use crate::actors::{BaseScheduleReloader, DatasetActor, RealTimeReloader};
+use crate::actors::{
+    BaseScheduleReloader, DatasetActor, DatasetLoader, DatasetsRegistry, LoadDataset,
+    RealTimeReloader, ReloaderState, Shutdown, SubscriptionActor, UpdateDatasets,
+};
 use crate::datasets;
-use crate::datasets::{Dataset, DatasetInfo, Datasets, Period};
+use crate::datasets::{DatasetInfo, Datasets, GenerationPeriod};
+use crate::extractors::AdminToken;
 use crate::routes::{
-    documentation, entry_point, general_message_query, gtfs_rt_json, gtfs_rt_protobuf,
-    siri_endpoint, status_query, stop_monitoring_query, stoppoints_discovery_query,
+    admin_pause_query, admin_realtime_refresh_query, admin_reload_query, admin_resume_query,
+    admin_state_query, check_status_query, documentation, entry_point, general_message_query,
+    gtfs_rt_json, gtfs_rt_protobuf, lines_discovery_query, production_timetable_query,
+    siri_endpoint, siri_json_config, siri_query_config, siri_soap_query, situation_exchange_query,
+    status_query, stop_monitoring_query, stop_timetable_query, stoppoints_discovery_query,
+    subscribe_query, terminate_subscription_query, trip_query, Delivery,
 };
 use actix::{Actor, Addr};
 use actix_web::web;
-use std::collections::BTreeMap;
 use std::sync::Arc;
 
+/// Addresses of the actors of a dataset
+#[derive(Clone)]
+pub struct DatasetActors {
+    pub dataset: Addr<DatasetActor>,
+    pub subscriptions: Addr<SubscriptionActor>,
+    pub base_schedule_reloader: Addr<BaseScheduleReloader>,
+    pub realtime_reloader: Addr<RealTimeReloader>,
+}
+
+impl DatasetActors {
+    /// Stop all the actors of a dataset
+    pub fn stop(&self) {
+        self.dataset.do_send(Shutdown);
+        self.subscriptions.do_send(Shutdown);
+        self.base_schedule_reloader.do_send(Shutdown);
+        self.realtime_reloader.do_send(Shutdown);
+    }
+}
+
 async fn create_dataset_actors_impl(
     dataset_info: DatasetInfo,
-    generation_period: &Period,
+    generation_period: &GenerationPeriod,
+    dataset_loader: &Addr<DatasetLoader>,
+    replaces_dataset: bool,
+    subscription_allowed_hosts: &[String],
     logger: &slog::Logger,
-) -> (DatasetInfo, Result<Addr<DatasetActor>, anyhow::Error>) {
+) -> (DatasetInfo, Result<DatasetActors, anyhow::Error>) {
     log::info!("creating actors");
-    let dataset = Dataset::try_from_dataset_info(dataset_info.clone(), &generation_period);
+    let source_version = datasets::base_schedule_version(&dataset_info).await;
+    // the dataset is read by the DatasetLoader, for the actors not to be blocked while it is loaded
+    let dataset = match dataset_loader
+        .send(LoadDataset {
+            dataset_info: dataset_info.clone(),
+            generation_period: generation_period.clone(),
+            source_version,
+            log: logger.clone(),
+        })
+        .await
+    {
+        // the dataset still served with its previous configuration is kept,
+        // it is not replaced by actors on a dataset that cannot be loaded
+        Ok(Err(e)) if replaces_dataset => return (dataset_info, Err(e)),
+        Ok(dataset) => dataset,
+        Err(e) => return (dataset_info, Err(anyhow::anyhow!("mailbox error: {}", e))),
+    };
 
     let arc_dataset = Arc::new(dataset);
-    let rt_dataset =
-        datasets::RealTimeDataset::new(arc_dataset.clone(), &dataset_info.gtfs_rt_urls);
-    let dataset_actors = DatasetActor {
-        gtfs: arc_dataset,
-        realtime: Arc::new(rt_dataset),
-    };
+    let rt_dataset = Arc::new(datasets::RealTimeDataset::new(
+        arc_dataset.clone(),
+        &dataset_info.gtfs_rt_urls,
+    ));
+    let dataset_actors = DatasetActor::new(arc_dataset, rt_dataset.clone(), dataset_loader.clone());
     let dataset_actors_addr = dataset_actors.start();
     let base_schedule_reloader = BaseScheduleReloader {
         feed_construction_info: datasets::FeedConstructionInfo {
@@ -32,117 +76,310 @@ async fn create_dataset_actors_impl(
             generation_period: generation_period.clone(),
         },
         dataset_actor: dataset_actors_addr.clone(),
+        dataset_loader: dataset_loader.clone(),
         log: logger.clone(),
+        state: ReloaderState::default(),
+        loading: false,
     };
-    base_schedule_reloader.start();
+    let base_schedule_reloader_addr = base_schedule_reloader.start();
+    let subscription_actor_addr = SubscriptionActor::new(
+        dataset_info.producer_ref(),
+        rt_dataset,
+        subscription_allowed_hosts,
+        logger.clone(),
+    )
+    .start();
     let realtime_reloader = RealTimeReloader {
         dataset_id: dataset_info.id.clone(),
         gtfs_rt_urls: dataset_info.gtfs_rt_urls.clone(),
         dataset_actor: dataset_actors_addr.clone(),
+        subscription_actor: subscription_actor_addr.clone(),
         log: logger.clone(),
+        state: ReloaderState::default(),
     };
-    // we fetch a first time the gtfs_rt feeds, for them to be available on startup
-    realtime_reloader.update_realtime_data().await;
-    realtime_reloader.start();
+    // the dataset is ready as soon as its base schedule is loaded,
+    // the realtime reloader fetches the gtfs_rt feeds in the background
+    let realtime_reloader_addr = realtime_reloader.start();
 
-    (dataset_info, Ok(dataset_actors_addr))
+    (
+        dataset_info,
+        Ok(DatasetActors {
+            dataset: dataset_actors_addr,
+            subscriptions: subscription_actor_addr,
+            base_schedule_reloader: base_schedule_reloader_addr,
+            realtime_reloader: realtime_reloader_addr,
+        }),
+    )
 }
 
-async fn create_dataset_actors(
+/// Create the actors of a dataset
+///
+/// If the dataset `replaces_dataset` already served, an error is given when its base schedule
+/// cannot be loaded, else the actors are created and the routes give the loading error
+pub(crate) async fn create_dataset_actors(
     dataset_info: DatasetInfo,
-    generation_period: &Period,
-) -> (DatasetInfo, Result<Addr<DatasetActor>, anyhow::Error>) {
+    generation_period: &GenerationPeriod,
+    dataset_loader: &Addr<DatasetLoader>,
+    replaces_dataset: bool,
+    subscription_allowed_hosts: &[String],
+) -> (DatasetInfo, Result<DatasetActors, anyhow::Error>) {
     use slog_scope_futures::FutureExt;
     let logger = slog_scope::logger().new(slog::o!("instance" => dataset_info.id.clone()));
-    create_dataset_actors_impl(dataset_info, generation_period, &logger)
-        .with_logger(&logger)
-        .await
+    create_dataset_actors_impl(
+        dataset_info,
+        generation_period,
+        dataset_loader,
+        replaces_dataset,
+        subscription_allowed_hosts,
+        &logger,
+    )
+    .with_logger(&logger)
+    .await
 }
 
-pub async fn create_all_actors(
+/// Create the registry giving the actors of the datasets to the routes
+///
+/// The datasets are loaded in the background, the routes of a dataset answer
+/// a `503 Service Unavailable` until it is ready.
+/// At most `max_concurrent_loads` datasets are loaded at the same time, to bound the memory peaks.
+/// The subscriptions' notifications can be pushed to the internal (loopback, private or link-local)
+/// addresses only on the `subscription_allowed_hosts`.
+pub fn create_all_actors(
     datasets: Datasets,
-    generation_period: &Period,
-) -> BTreeMap<DatasetInfo, Addr<DatasetActor>> {
-    let actors = datasets
-        .datasets
-        .into_iter()
-        .map(|d| create_dataset_actors(d, &generation_period));
+    generation_period: &GenerationPeriod,
+    max_concurrent_loads: usize,
+    subscription_allowed_hosts: Vec<String>,
+) -> Addr<DatasetsRegistry> {
+    let dataset_loader = DatasetLoader::start_threads(max_concurrent_loads, Arc::default());
+    create_all_actors_with_loader(
+        datasets,
+        generation_period,
+        dataset_loader,
+        subscription_allowed_hosts,
+    )
+}
 
-    async move {
-        futures::future::join_all(actors)
-            .await
-            .into_iter()
-            .filter_map(|(d, r)| match r {
-                Ok(a) => Some((d, a)),
-                Err(e) => {
-                    // the invalid datasets are filtered
-                    let msg = format!("impossible to create dataset {}: {}", &d.id, e);
-                    sentry::capture_message(&msg, sentry::Level::Error);
-                    log::error!("{}", &msg);
-                    None
-                }
-            })
-            .collect()
-    }
-    .await
+/// Create the registry of the datasets, loaded by the given `DatasetLoader`
+pub fn create_all_actors_with_loader(
+    datasets: Datasets,
+    generation_period: &GenerationPeriod,
+    dataset_loader: Addr<DatasetLoader>,
+    subscription_allowed_hosts: Vec<String>,
+) -> Addr<DatasetsRegistry> {
+    let registry = DatasetsRegistry::new(
+        generation_period.clone(),
+        dataset_loader,
+        subscription_allowed_hosts,
+    )
+    .start();
+    registry.do_send(UpdateDatasets(datasets));
+    registry
 }
 
-fn register_dataset_routes(
-    cfg: &mut web::ServiceConfig,
-    datasets_actors: &BTreeMap<DatasetInfo, Addr<DatasetActor>>,
-) {
-    for (d, dataset_actor) in datasets_actors {
-        cfg.service(
-            web::scope(&format!("/{id}", id = &d.id))
-                .data(dataset_actor.clone())
-                .service(
-                    web::resource("/")
-                        .name(&format!("{}/status_query", &d.id))
-                        .route(web::get().to(status_query)),
-                )
-                .service(
-                    web::resource("/gtfs-rt/")
-                        .name(&format!("{}/gtfs_rt_protobuf", &d.id))
-                        .route(web::get().to(gtfs_rt_protobuf)),
-                )
-                .service(
-                    web::resource("/gtfs-rt.json/")
-                        .name(&format!("{}/gtfs_rt_json", &d.id))
-                        .route(web::get().to(gtfs_rt_json)),
-                )
-                .service(
-                    web::resource("/siri/2.0/")
-                        .name(&format!("{}/siri_endpoint", &d.id))
-                        .route(web::get().to(siri_endpoint)),
-                )
-                .service(
-                    web::resource("/siri/2.0/stoppoints-discovery.json/")
-                        .name(&format!("{}/stoppoints_discovery_query", &d.id))
-                        .route(web::get().to(stoppoints_discovery_query)),
-                )
-                .service(
-                    web::resource("/siri/2.0/stop-monitoring.json/")
-                        .name(&format!("{}/stop_monitoring_query", &d.id))
-                        .route(web::get().to(stop_monitoring_query)),
-                )
-                .service(
-                    web::resource("/siri/2.0/general-message.json/")
-                        .name(&format!("{}/general_message_query", &d.id))
-                        .route(web::get().to(general_message_query)),
-                ),
-        );
-    }
+/// The routes of the datasets are registered once in a `/{id}` scope,
+/// the dataset is found at request time, so the datasets can be added or removed without restarting
+fn register_dataset_routes(cfg: &mut web::ServiceConfig) {
+    cfg.service(
+        web::scope("/{id}")
+            .service(
+                web::resource("/")
+                    .name("status_query")
+                    .route(web::get().to(status_query)),
+            )
+            .service(
+                web::resource("/gtfs-rt/")
+                    .name("gtfs_rt_protobuf")
+                    .route(web::get().to(gtfs_rt_protobuf)),
+            )
+            .service(
+                web::resource("/gtfs-rt.json/")
+                    .name("gtfs_rt_json")
+                    .route(web::get().to(gtfs_rt_json)),
+            )
+            .service(
+                web::resource("/trips/{vehicle_journey_id}/")
+                    .name("trip_query")
+                    .route(web::get().to(trip_query)),
+            )
+            .service(
+                web::resource("/siri/2.0/")
+                    .name("siri_endpoint")
+                    .route(web::get().to(siri_endpoint)),
+            )
+            .service(
+                web::resource("/siri/2.0/check-status.json/")
+                    .name("check_status_query")
+                    .route(web::get().to(check_status_query)),
+            )
+            .service(
+                web::resource("/siri/2.0/check-status.xml/")
+                    .name("check_status_query_xml")
+                    .route(web::get().to(check_status_query)),
+            )
+            .service(
+                web::resource("/siri/2.0/stoppoints-discovery.json/")
+                    .name("stoppoints_discovery_query")
+                    .app_data(Delivery::StopPointsDiscovery)
+                    .app_data(siri_query_config(Delivery::StopPointsDiscovery))
+                    .route(web::get().to(stoppoints_discovery_query)),
+            )
+            .service(
+                web::resource("/siri/2.0/stoppoints-discovery.xml/")
+                    .name("stoppoints_discovery_query_xml")
+                    .app_data(Delivery::StopPointsDiscovery)
+                    .app_data(siri_query_config(Delivery::StopPointsDiscovery))
+                    .route(web::get().to(stoppoints_discovery_query)),
+            )
+            .service(
+                web::resource("/siri/2.0/lines-discovery.json/")
+                    .name("lines_discovery_query")
+                    .app_data(Delivery::LinesDiscovery)
+                    .app_data(siri_query_config(Delivery::LinesDiscovery))
+                    .route(web::get().to(lines_discovery_query)),
+            )
+            .service(
+                web::resource("/siri/2.0/lines-discovery.xml/")
+                    .name("lines_discovery_query_xml")
+                    .app_data(Delivery::LinesDiscovery)
+                    .app_data(siri_query_config(Delivery::LinesDiscovery))
+                    .route(web::get().to(lines_discovery_query)),
+            )
+            .service(
+                web::resource("/siri/2.0/stop-monitoring.json/")
+                    .name("stop_monitoring_query")
+                    .app_data(Delivery::StopMonitoring)
+                    .app_data(siri_query_config(Delivery::StopMonitoring))
+                    .route(web::get().to(stop_monitoring_query)),
+            )
+            .service(
+                web::resource("/siri/2.0/stop-monitoring.xml/")
+                    .name("stop_monitoring_query_xml")
+                    .app_data(Delivery::StopMonitoring)
+                    .app_data(siri_query_config(Delivery::StopMonitoring))
+                    .route(web::get().to(stop_monitoring_query)),
+            )
+            .service(
+                web::resource("/siri/2.0/general-message.json/")
+                    .name("general_message_query")
+                    .app_data(Delivery::GeneralMessage)
+                    .app_data(siri_query_config(Delivery::GeneralMessage))
+                    .route(web::get().to(general_message_query)),
+            )
+            .service(
+                web::resource("/siri/2.0/soap/")
+                    .name("siri_soap_query")
+                    .route(web::post().to(siri_soap_query)),
+            )
+            .service(
+                web::resource("/siri/2.0/general-message.xml/")
+                    .name("general_message_query_xml")
+                    .app_data(Delivery::GeneralMessage)
+                    .app_data(siri_query_config(Delivery::GeneralMessage))
+                    .route(web::get().to(general_message_query)),
+            )
+            .service(
+                web::resource("/siri/2.0/situation-exchange.json/")
+                    .name("situation_exchange_query")
+                    .app_data(Delivery::SituationExchange)
+                    .app_data(siri_query_config(Delivery::SituationExchange))
+                    .route(web::get().to(situation_exchange_query)),
+            )
+            .service(
+                web::resource("/siri/2.0/situation-exchange.xml/")
+                    .name("situation_exchange_query_xml")
+                    .app_data(Delivery::SituationExchange)
+                    .app_data(siri_query_config(Delivery::SituationExchange))
+                    .route(web::get().to(situation_exchange_query)),
+            )
+            .service(
+                web::resource("/siri/2.0/production-timetable.json/")
+                    .name("production_timetable_query")
+                    .app_data(Delivery::ProductionTimetable)
+                    .app_data(siri_query_config(Delivery::ProductionTimetable))
+                    .route(web::get().to(production_timetable_query)),
+            )
+            .service(
+                web::resource("/siri/2.0/production-timetable.xml/")
+                    .name("production_timetable_query_xml")
+                    .app_data(Delivery::ProductionTimetable)
+                    .app_data(siri_query_config(Delivery::ProductionTimetable))
+                    .route(web::get().to(production_timetable_query)),
+            )
+            .service(
+                web::resource("/siri/2.0/stop-timetable.json/")
+                    .name("stop_timetable_query")
+                    .app_data(Delivery::StopTimetable)
+                    .app_data(siri_query_config(Delivery::StopTimetable))
+                    .route(web::get().to(stop_timetable_query)),
+            )
+            .service(
+                web::resource("/siri/2.0/stop-timetable.xml/")
+                    .name("stop_timetable_query_xml")
+                    .app_data(Delivery::StopTimetable)
+                    .app_data(siri_query_config(Delivery::StopTimetable))
+                    .route(web::get().to(stop_timetable_query)),
+            )
+            .service(
+                web::resource("/siri/2.0/subscribe.json/")
+                    .name("subscribe_query")
+                    .app_data(Delivery::Subscription)
+                    .app_data(siri_json_config(Delivery::Subscription))
+                    .route(web::post().to(subscribe_query)),
+            )
+            .service(
+                web::resource("/siri/2.0/terminate-subscription.json/")
+                    .name("terminate_subscription_query")
+                    .app_data(Delivery::TerminateSubscription)
+                    .app_data(siri_json_config(Delivery::TerminateSubscription))
+                    .route(web::post().to(terminate_subscription_query)),
+            ),
+    );
 }
 
-pub fn init_routes(
-    cfg: &mut web::ServiceConfig,
-    datasets_actors: &BTreeMap<DatasetInfo, Addr<DatasetActor>>,
-) {
-    let datasets = Datasets {
-        datasets: datasets_actors.keys().cloned().collect(),
-    };
-    cfg.data(datasets)
+pub fn init_routes(cfg: &mut web::ServiceConfig, datasets_registry: &Addr<DatasetsRegistry>) {
+    cfg.data(datasets_registry.clone())
         .service(documentation)
         .service(entry_point);
-    register_dataset_routes(cfg, datasets_actors);
+    register_dataset_routes(cfg);
+}
+
+/// Register the admin routes, only accessible with the given token
+///
+/// Note: those routes must be registered before the ones of `init_routes`,
+/// else the `/admin` path would be taken for a dataset id
+pub fn init_admin_routes(
+    cfg: &mut web::ServiceConfig,
+    datasets_registry: &Addr<DatasetsRegistry>,
+    admin_token: &str,
+) {
+    cfg.service(
+        web::scope("/admin/{id}")
+            .data(datasets_registry.clone())
+            .data(AdminToken(admin_token.to_owned()))
+            .service(
+                web::resource("/")
+                    .name("admin_state_query")
+                    .route(web::get().to(admin_state_query)),
+            )
+            .service(
+                web::resource("/reload/")
+                    .name("admin_reload_query")
+                    .route(web::post().to(admin_reload_query)),
+            )
+            .service(
+                web::resource("/realtime/refresh/")
+                    .name("admin_realtime_refresh_query")
+                    .route(web::post().to(admin_realtime_refresh_query)),
+            )
+            .service(
+                web::resource("/pause/")
+                    .name("admin_pause_query")
+                    .route(web::post().to(admin_pause_query)),
+            )
+            .service(
+                web::resource("/resume/")
+                    .name("admin_resume_query")
+                    .route(web::post().to(admin_resume_query)),
+            ),
+    );
 }
@@ -0,0 +1,31 @@
+use crate::siri_lite::shared::ErrorCondition;
+use openapi_schema::OpenapiSchema;
+
+/// Response of the `CheckStatus` service, to know if the producer is up and when its data changed
+#[derive(Debug, Serialize, Deserialize, OpenapiSchema)]
+#[serde(rename_all = "PascalCase")]
+pub struct CheckStatusResponse {
+    pub response_timestamp: chrono::DateTime<chrono::Utc>,
+    /// Id of the producer
+    pub producer_ref: String,
+    /// Id of the query
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub request_message_ref: Option<String>,
+    /// true if the producer can answer the siri requests
+    pub status: bool,
+    /// true if the base schedule data are loaded
+    pub data_ready: bool,
+    /// true if some realtime data are available
+    /// Note: this is not in the siri specifications
+    pub real_time_data_ready: bool,
+    /// Description of the error, only set if status is false
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error_condition: Option<ErrorCondition>,
+    /// Datetime of the loading of the base schedule data
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub service_started_time: Option<chrono::DateTime<chrono::Utc>>,
+    /// Datetime of the last update of the realtime data
+    /// Note: this is not in the siri specifications
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub real_time_data_updated_time: Option<chrono::DateTime<chrono::Utc>>,
+}
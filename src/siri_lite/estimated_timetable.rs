@@ -0,0 +1,56 @@
+use crate::siri_lite::shared::CommonDelivery;
+use crate::siri_lite::DateTime;
+use openapi_schema::OpenapiSchema;
+
+#[derive(Debug, Serialize, Deserialize, OpenapiSchema)]
+#[serde(rename_all = "PascalCase")]
+pub struct EstimatedCall {
+    /// Id of the stop point
+    pub stop_point_ref: String,
+    pub order: u16,
+    pub stop_point_name: String,
+    /// Scheduled arrival time
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub aimed_arrival_time: Option<DateTime>,
+    /// Estimated arrival time
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub expected_arrival_time: Option<DateTime>,
+    /// Scheduled departure time
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub aimed_departure_time: Option<DateTime>,
+    /// Estimated departure time
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub expected_departure_time: Option<DateTime>,
+}
+
+#[derive(Debug, Serialize, Deserialize, OpenapiSchema)]
+#[serde(rename_all = "PascalCase")]
+pub struct EstimatedVehicleJourney {
+    /// Id of the line
+    pub line_ref: String,
+    /// Id of the vehicle journey and its date
+    pub dated_vehicle_journey_ref: String,
+    /// Id of the operator
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub operator_ref: Option<String>,
+    /// Datetime of the information update
+    pub recorded_at_time: chrono::DateTime<chrono::Utc>,
+    /// All the calls of the vehicle journey
+    pub estimated_calls: Vec<EstimatedCall>,
+}
+
+#[derive(Debug, Serialize, Deserialize, OpenapiSchema)]
+#[serde(rename_all = "PascalCase")]
+pub struct EstimatedJourneyVersionFrame {
+    /// Datetime of the frame's production
+    pub recorded_at_time: chrono::DateTime<chrono::Utc>,
+    pub estimated_vehicle_journey: Vec<EstimatedVehicleJourney>,
+}
+
+#[derive(Debug, Serialize, Deserialize, OpenapiSchema, Default)]
+#[serde(rename_all = "PascalCase")]
+pub struct EstimatedTimetableDelivery {
+    #[serde(flatten)]
+    pub common: CommonDelivery,
+    pub estimated_journey_version_frame: Vec<EstimatedJourneyVersionFrame>,
+}
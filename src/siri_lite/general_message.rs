@@ -55,7 +55,8 @@ pub struct InfoMessage {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub format: Option<String>,
     /// datetime of the recording of the message
-    /// Note: this field is mandatory for the idf profile, but we cannot easily fill it with gtfs-rt
+    /// Note: this field is mandatory for the idf profile, but we cannot easily fill it with gtfs-rt,
+    /// so the `SiriProfile::Idf` fills it with the time of the response
     #[serde(skip_serializing_if = "Option::is_none")]
     pub recorded_at_time: Option<DateTime>,
     /// Uniq identifier of the message
@@ -69,6 +70,9 @@ pub struct InfoMessage {
     /// version of this info message
     #[serde(skip_serializing_if = "Option::is_none")]
     pub info_message_version: Option<String>,
+    /// Channel of the message (`Perturbation` or `Information`)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub info_channel_ref: Option<String>,
     /// Datetime until this message is valid
     #[serde(skip_serializing_if = "Option::is_none")]
     pub valid_until_time: Option<DateTime>,
@@ -94,5 +98,5 @@ pub struct GeneralMessageDelivery {
     #[serde(flatten)]
     pub common: crate::siri_lite::shared::CommonDelivery,
     pub info_messages: Vec<InfoMessage>,
-    pub info_messages_cancellation: Vec<InfoMessage>,
+    pub info_messages_cancellation: Vec<InfoMessageCancellation>,
 }
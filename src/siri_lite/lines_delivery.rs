@@ -0,0 +1,125 @@
+use openapi_schema::OpenapiSchema;
+use std::collections::BTreeSet;
+use transit_model::collection::Idx;
+use transit_model::objects::{Company, Line, PhysicalMode, StopPoint, VehicleJourney};
+use transit_model::Model;
+
+#[derive(Debug, Serialize, Deserialize, OpenapiSchema)]
+#[serde(rename_all = "PascalCase")]
+pub struct Destination {
+    /// Id of the destination's stop point
+    pub destination_ref: String,
+    /// Name of the destination
+    pub place_name: String,
+}
+
+#[derive(Debug, Serialize, Deserialize, OpenapiSchema)]
+#[serde(rename_all = "PascalCase")]
+pub struct LineStopPoint {
+    pub stop_point_ref: String,
+    pub stop_name: String,
+}
+
+#[derive(Debug, Serialize, Deserialize, OpenapiSchema)]
+#[serde(rename_all = "PascalCase")]
+pub struct AnnotatedLineRef {
+    pub line_ref: String,
+    pub line_name: String,
+    /// Public code of the line
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub line_code: Option<String>,
+    /// Colour of the line, as a RGB hexadecimal string
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub line_colour: Option<String>,
+    /// Modes of the vehicles of the line
+    pub vehicle_mode: Vec<String>,
+    /// Id of the operator
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub operator_ref: Option<String>,
+    /// Terminus of the vehicle journeys of the line
+    pub destinations: Vec<Destination>,
+    /// Stop points served by the line
+    pub stop_points: Vec<LineStopPoint>,
+}
+
+#[derive(Debug, Serialize, Deserialize, OpenapiSchema)]
+#[serde(rename_all = "PascalCase")]
+pub struct LinesDelivery {
+    #[serde(flatten)]
+    pub common: crate::siri_lite::shared::CommonDelivery,
+    pub annotated_line_ref: Vec<AnnotatedLineRef>,
+}
+
+/// Siri's mode of a transmodel's physical mode
+pub(crate) fn vehicle_mode(physical_mode: &PhysicalMode) -> &'static str {
+    match physical_mode.id.as_str() {
+        "Bus" | "BusRapidTransit" | "Shuttle" | "Taxi" => "bus",
+        "Coach" => "coach",
+        "Tramway" => "tram",
+        "Metro" => "metro",
+        "Train" | "LocalTrain" | "LongDistanceTrain" | "RapidTransit" | "RailShuttle" => "rail",
+        "Ferry" | "Boat" => "water",
+        "Air" => "air",
+        "Funicular" => "funicular",
+        "SuspendedCableCar" => "telecabin",
+        _ => "unknown",
+    }
+}
+
+/// Colour of a line, as a RGB hexadecimal string
+pub(crate) fn line_colour(line: &Line) -> Option<String> {
+    line.color
+        .as_ref()
+        .map(|c| format!("{:02X}{:02X}{:02X}", c.red, c.green, c.blue))
+}
+
+impl AnnotatedLineRef {
+    pub fn from(line_idx: Idx<Line>, model: &Model) -> Self {
+        let line = &model.lines[line_idx];
+
+        let modes: BTreeSet<Idx<PhysicalMode>> = model.get_corresponding_from_idx(line_idx);
+        let vehicle_mode: BTreeSet<_> = modes
+            .into_iter()
+            .map(|idx| vehicle_mode(&model.physical_modes[idx]).to_owned())
+            .collect();
+
+        // we consider that the siri's operator in transmodel's company
+        let companies: BTreeSet<Idx<Company>> = model.get_corresponding_from_idx(line_idx);
+        let operator_ref = companies
+            .into_iter()
+            .next()
+            .map(|idx| model.companies[idx].id.clone());
+
+        let vjs: BTreeSet<Idx<VehicleJourney>> = model.get_corresponding_from_idx(line_idx);
+        let destinations: BTreeSet<Idx<StopPoint>> = vjs
+            .into_iter()
+            .filter_map(|idx| model.vehicle_journeys[idx].stop_times.last())
+            .map(|st| st.stop_point_idx)
+            .collect();
+
+        let stop_points: BTreeSet<Idx<StopPoint>> = model.get_corresponding_from_idx(line_idx);
+
+        Self {
+            line_ref: line.id.clone(),
+            line_name: line.name.clone(),
+            line_code: line.code.clone(),
+            line_colour: line_colour(line),
+            vehicle_mode: vehicle_mode.into_iter().collect(),
+            operator_ref,
+            destinations: destinations
+                .into_iter()
+                .map(|idx| Destination {
+                    destination_ref: model.stop_points[idx].id.clone(),
+                    place_name: model.stop_points[idx].name.clone(),
+                })
+                .collect(),
+            stop_points: stop_points
+                .into_iter()
+                .map(|idx| LineStopPoint {
+                    stop_point_ref: model.stop_points[idx].id.clone(),
+                    stop_name: model.stop_points[idx].name.clone(),
+                })
+                .collect(),
+        }
+    }
+}
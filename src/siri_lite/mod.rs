@@ -1,11 +1,26 @@
+pub mod check_status;
+pub mod estimated_timetable;
 pub mod general_message;
+pub mod lines_delivery;
+pub mod production_timetable;
+pub mod profile;
 pub mod service_delivery;
 pub mod shared;
+pub mod situation_exchange;
 pub mod stop_points_delivery;
+pub mod stop_timetable;
+pub mod subscription;
+pub mod xml;
 
+use check_status::CheckStatusResponse;
+use lines_delivery::LinesDelivery;
 use openapi_schema::OpenapiSchema;
 use service_delivery::ServiceDelivery;
 use stop_points_delivery::StopPointsDelivery;
+use subscription::{
+    HeartbeatNotification, SubscriptionResponse, SubscriptionTerminatedNotification,
+    TerminateSubscriptionResponse,
+};
 
 pub use shared::DateTime;
 
@@ -15,7 +30,19 @@ pub struct Siri {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub stop_points_delivery: Option<StopPointsDelivery>,
     #[serde(skip_serializing_if = "Option::is_none")]
+    pub lines_delivery: Option<LinesDelivery>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub service_delivery: Option<ServiceDelivery>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub subscription_response: Option<SubscriptionResponse>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub terminate_subscription_response: Option<TerminateSubscriptionResponse>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub heartbeat_notification: Option<HeartbeatNotification>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub subscription_terminated_notification: Option<SubscriptionTerminatedNotification>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub check_status_response: Option<CheckStatusResponse>,
 }
 
 #[derive(Debug, Serialize, Deserialize, OpenapiSchema)]
@@ -23,3 +50,39 @@ pub struct Siri {
 pub struct SiriResponse {
     pub siri: Siri,
 }
+
+impl SiriResponse {
+    /// Set the reference of the request message in all the deliveries of the response
+    pub fn set_request_message_ref(&mut self, request_message_ref: &Option<String>) {
+        if request_message_ref.is_none() {
+            return;
+        }
+        if let Some(delivery) = &mut self.siri.stop_points_delivery {
+            delivery.common.request_message_ref = request_message_ref.clone();
+        }
+        if let Some(response) = &mut self.siri.check_status_response {
+            response.request_message_ref = request_message_ref.clone();
+        }
+        if let Some(delivery) = &mut self.siri.lines_delivery {
+            delivery.common.request_message_ref = request_message_ref.clone();
+        }
+        if let Some(delivery) = &mut self.siri.service_delivery {
+            delivery.common.request_message_ref = request_message_ref.clone();
+            for sm in &mut delivery.stop_monitoring_delivery {
+                sm.request_message_ref = request_message_ref.clone();
+            }
+            for gm in &mut delivery.general_message_delivery {
+                gm.common.request_message_ref = request_message_ref.clone();
+            }
+            for sx in &mut delivery.situation_exchange_delivery {
+                sx.common.request_message_ref = request_message_ref.clone();
+            }
+            for pt in &mut delivery.production_timetable_delivery {
+                pt.common.request_message_ref = request_message_ref.clone();
+            }
+            for st in &mut delivery.stop_timetable_delivery {
+                st.common.request_message_ref = request_message_ref.clone();
+            }
+        }
+    }
+}
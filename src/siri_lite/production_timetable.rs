@@ -0,0 +1,47 @@
+use crate::siri_lite::shared::CommonDelivery;
+use crate::siri_lite::DateTime;
+use openapi_schema::OpenapiSchema;
+
+#[derive(Debug, Serialize, Deserialize, OpenapiSchema)]
+#[serde(rename_all = "PascalCase")]
+pub struct DatedCall {
+    /// Id of the stop point
+    pub stop_point_ref: String,
+    pub order: u16,
+    pub stop_point_name: String,
+    /// Scheduled arrival time
+    pub aimed_arrival_time: DateTime,
+    /// Scheduled departure time
+    pub aimed_departure_time: DateTime,
+}
+
+#[derive(Debug, Serialize, Deserialize, OpenapiSchema)]
+#[serde(rename_all = "PascalCase")]
+pub struct DatedVehicleJourney {
+    /// Id of the vehicle journey and its date
+    pub dated_vehicle_journey_code: String,
+    /// Id of the operator
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub operator_ref: Option<String>,
+    /// All the scheduled calls of the vehicle journey
+    pub dated_calls: Vec<DatedCall>,
+}
+
+#[derive(Debug, Serialize, Deserialize, OpenapiSchema)]
+#[serde(rename_all = "PascalCase")]
+pub struct DatedTimetableVersionFrame {
+    /// Datetime of the loading of the base schedule
+    pub recorded_at_time: chrono::DateTime<chrono::Utc>,
+    /// Id of the line
+    pub line_ref: String,
+    pub dated_vehicle_journey: Vec<DatedVehicleJourney>,
+}
+
+#[derive(Debug, Serialize, Deserialize, OpenapiSchema, Default)]
+#[serde(rename_all = "PascalCase")]
+pub struct ProductionTimetableDelivery {
+    #[serde(flatten)]
+    pub common: CommonDelivery,
+    /// One frame by line
+    pub dated_timetable_version_frame: Vec<DatedTimetableVersionFrame>,
+}
@@ -0,0 +1,440 @@
+//! Siri profiles
+//!
+//! The siri specification leaves a lot of fields optional and the format of the ids free,
+//! a profile (like the french IDF profile) fixes them.
+//!
+//! The responses are always built with the generic profile (the ids of the dataset, and only
+//! the fields we can fill), then adapted to the profile asked by the client with `apply`.
+
+use crate::datasets::Dataset;
+use crate::siri_lite::{shared, DateTime, SiriResponse};
+use openapi_schema::OpenapiSchema;
+
+/// Siri profile of the responses
+#[derive(
+    Serialize, Deserialize, Debug, Clone, Copy, OpenapiSchema, PartialEq, Eq, PartialOrd, Ord,
+)]
+#[serde(rename_all = "kebab-case")]
+pub enum SiriProfile {
+    // the ids of the dataset, and only the fields we can fill
+    Generic,
+    // french profile of Ile-de-France Mobilités, with `STIF:StopPoint:Q:<id>:` like ids
+    Idf,
+}
+
+impl Default for SiriProfile {
+    fn default() -> Self {
+        SiriProfile::Generic
+    }
+}
+
+/// Type of the objects referenced in the siri responses
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ObjectType {
+    StopPoint,
+    StopArea,
+    Line,
+    Operator,
+    VehicleJourney,
+    JourneyPattern,
+    Item,
+    InfoMessage,
+    ResponseMessage,
+}
+
+const OBJECT_TYPES: [ObjectType; 9] = [
+    ObjectType::StopPoint,
+    ObjectType::StopArea,
+    ObjectType::Line,
+    ObjectType::Operator,
+    ObjectType::VehicleJourney,
+    ObjectType::JourneyPattern,
+    ObjectType::Item,
+    ObjectType::InfoMessage,
+    ObjectType::ResponseMessage,
+];
+
+impl ObjectType {
+    fn name(self) -> &'static str {
+        match self {
+            ObjectType::StopPoint => "StopPoint",
+            ObjectType::StopArea => "StopArea",
+            ObjectType::Line => "Line",
+            ObjectType::Operator => "Operator",
+            ObjectType::VehicleJourney => "VehicleJourney",
+            ObjectType::JourneyPattern => "JourneyPattern",
+            ObjectType::Item => "Item",
+            ObjectType::InfoMessage => "InfoMessage",
+            ObjectType::ResponseMessage => "ResponseMessage",
+        }
+    }
+}
+
+/// Id of the dataset's object referenced by a siri ref
+///
+/// The refs in the IDF format (like `STIF:StopPoint:Q:<id>:` or `<producer>:Item::<id>:LOC`)
+/// are accepted whatever the profile of the dataset, the other refs are kept as is.
+pub fn local_id(siri_ref: &str) -> &str {
+    let parts: Vec<&str> = siri_ref.splitn(4, ':').collect();
+    if let [codespace, object_type, _, rest] = parts.as_slice() {
+        let is_object_type = OBJECT_TYPES.iter().any(|t| t.name() == *object_type);
+        if is_object_type && (*codespace == "STIF" || rest.ends_with(":LOC")) {
+            if let Some(id) = rest.strip_suffix(":LOC").or_else(|| rest.strip_suffix(':')) {
+                return id;
+            }
+        }
+    }
+    siri_ref
+}
+
+/// Current datetime in the timezone of the dataset
+fn now(dataset: &Dataset) -> DateTime {
+    DateTime::in_timezone(
+        chrono::Utc::now()
+            .with_timezone(&dataset.timezone)
+            .naive_local(),
+        dataset.timezone,
+    )
+}
+
+impl SiriProfile {
+    /// Siri ref of an object of the dataset
+    pub fn format_ref(self, object_type: ObjectType, producer_ref: &str, id: &str) -> String {
+        match (self, object_type) {
+            // the generated ids are prefixed by the producer, so they are unique between the datasets
+            (SiriProfile::Generic, ObjectType::ResponseMessage) => {
+                format!("{}:{}:{}", producer_ref, object_type.name(), id)
+            }
+            (SiriProfile::Generic, _) => id.to_owned(),
+            // the stops and the lines have a referential shared by all the IDF producers
+            (SiriProfile::Idf, ObjectType::StopPoint) => format!("STIF:StopPoint:Q:{}:", id),
+            (SiriProfile::Idf, ObjectType::StopArea) => format!("STIF:StopArea:SP:{}:", id),
+            (SiriProfile::Idf, ObjectType::Line) => format!("STIF:Line::{}:", id),
+            (SiriProfile::Idf, _) => format!("{}:{}::{}:LOC", producer_ref, object_type.name(), id),
+        }
+    }
+
+    /// Adapt a response built with the generic profile to this profile
+    ///
+    /// Note: the refs must not have been formatted yet
+    pub fn apply(self, response: &mut SiriResponse, dataset: &Dataset) {
+        if self == SiriProfile::Generic {
+            return;
+        }
+        let producer_ref = dataset.feed_construction_info.dataset_info.producer_ref();
+        let format = |object_type: ObjectType, id: &mut String| {
+            *id = self.format_ref(object_type, producer_ref, id);
+        };
+        let format_all = |object_type: ObjectType, ids: &mut Vec<String>| {
+            ids.iter_mut().for_each(|id| format(object_type, id));
+        };
+        let siri = &mut response.siri;
+
+        if let Some(delivery) = &mut siri.stop_points_delivery {
+            for stop in &mut delivery.annotated_stop_point {
+                format(ObjectType::StopPoint, &mut stop.stop_point_ref);
+                format(ObjectType::StopArea, &mut stop.stop_area_ref);
+                for line in &mut stop.lines {
+                    format(ObjectType::Line, &mut line.line_ref);
+                }
+            }
+        }
+
+        if let Some(delivery) = &mut siri.lines_delivery {
+            for line in &mut delivery.annotated_line_ref {
+                format(ObjectType::Line, &mut line.line_ref);
+                line.operator_ref
+                    .iter_mut()
+                    .for_each(|id| format(ObjectType::Operator, id));
+                for destination in &mut line.destinations {
+                    format(ObjectType::StopPoint, &mut destination.destination_ref);
+                }
+                for stop in &mut line.stop_points {
+                    format(ObjectType::StopPoint, &mut stop.stop_point_ref);
+                }
+            }
+        }
+
+        if let Some(delivery) = &mut siri.service_delivery {
+            // the response identifier is mandatory and has its own format in the IDF profile
+            delivery.response_message_identifier = Some(self.format_ref(
+                ObjectType::ResponseMessage,
+                producer_ref,
+                &shared::new_message_id(),
+            ));
+
+            for sm in &mut delivery.stop_monitoring_delivery {
+                for visit in &mut sm.monitored_stop_visit {
+                    format(ObjectType::StopPoint, &mut visit.monitoring_ref);
+                    format(ObjectType::Item, &mut visit.item_identifier);
+                    // the links are not part of the siri specifications
+                    visit.links = Default::default();
+                    let vj = &mut visit.monitored_vehicle_journey;
+                    format(ObjectType::Line, &mut vj.line_ref);
+                    if let Some(framed_vj) = &mut vj.framed_vehicle_journey_ref {
+                        format(
+                            ObjectType::VehicleJourney,
+                            &mut framed_vj.dated_vehicle_journey_ref,
+                        );
+                    }
+                    vj.service_info
+                        .operator_ref
+                        .iter_mut()
+                        .for_each(|id| format(ObjectType::Operator, id));
+                    vj.journey_pattern_ref
+                        .iter_mut()
+                        .for_each(|id| format(ObjectType::JourneyPattern, id));
+                    if let Some(call) = &mut vj.monitored_call {
+                        call.vehicle_at_stop.get_or_insert(false);
+                    }
+                }
+            }
+
+            for gm in &mut delivery.general_message_delivery {
+                for message in &mut gm.info_messages {
+                    message.format.get_or_insert_with(|| "STIF-IDF".to_owned());
+                    message.recorded_at_time.get_or_insert_with(|| now(dataset));
+                    message
+                        .item_identifier
+                        .iter_mut()
+                        .for_each(|id| format(ObjectType::Item, id));
+                    message
+                        .info_message_identifier
+                        .iter_mut()
+                        .for_each(|id| format(ObjectType::InfoMessage, id));
+                    format_all(ObjectType::Line, &mut message.content.line_ref);
+                    format_all(ObjectType::StopPoint, &mut message.content.stop_point_ref);
+                    format_all(ObjectType::StopPoint, &mut message.content.destination_ref);
+                }
+                for cancellation in &mut gm.info_messages_cancellation {
+                    cancellation
+                        .item_identifier
+                        .iter_mut()
+                        .for_each(|id| format(ObjectType::Item, id));
+                    cancellation
+                        .info_message_identifier
+                        .iter_mut()
+                        .for_each(|id| format(ObjectType::InfoMessage, id));
+                }
+            }
+
+            for sx in &mut delivery.situation_exchange_delivery {
+                for situation in &mut sx.situations {
+                    let affects = &mut situation.affects;
+                    for network in &mut affects.affected_network {
+                        for line in &mut network.affected_line {
+                            format(ObjectType::Line, &mut line.line_ref);
+                        }
+                    }
+                    for stop in &mut affects.affected_stop_point {
+                        format(ObjectType::StopPoint, &mut stop.stop_point_ref);
+                    }
+                    for stop in &mut affects.affected_stop_place {
+                        format(ObjectType::StopArea, &mut stop.stop_place_ref);
+                    }
+                    for vj in &mut affects.affected_vehicle_journey {
+                        format(ObjectType::VehicleJourney, &mut vj.vehicle_journey_ref);
+                        vj.line_ref
+                            .iter_mut()
+                            .for_each(|id| format(ObjectType::Line, id));
+                    }
+                }
+            }
+
+            for et in &mut delivery.estimated_timetable_delivery {
+                let journeys = et
+                    .estimated_journey_version_frame
+                    .iter_mut()
+                    .flat_map(|f| f.estimated_vehicle_journey.iter_mut());
+                for vj in journeys {
+                    format(ObjectType::Line, &mut vj.line_ref);
+                    format(
+                        ObjectType::VehicleJourney,
+                        &mut vj.dated_vehicle_journey_ref,
+                    );
+                    vj.operator_ref
+                        .iter_mut()
+                        .for_each(|id| format(ObjectType::Operator, id));
+                    for call in &mut vj.estimated_calls {
+                        format(ObjectType::StopPoint, &mut call.stop_point_ref);
+                    }
+                }
+            }
+
+            for pt in &mut delivery.production_timetable_delivery {
+                for frame in &mut pt.dated_timetable_version_frame {
+                    format(ObjectType::Line, &mut frame.line_ref);
+                    for vj in &mut frame.dated_vehicle_journey {
+                        format(
+                            ObjectType::VehicleJourney,
+                            &mut vj.dated_vehicle_journey_code,
+                        );
+                        vj.operator_ref
+                            .iter_mut()
+                            .for_each(|id| format(ObjectType::Operator, id));
+                        for call in &mut vj.dated_calls {
+                            format(ObjectType::StopPoint, &mut call.stop_point_ref);
+                        }
+                    }
+                }
+            }
+
+            for st in &mut delivery.stop_timetable_delivery {
+                for visit in &mut st.timetabled_stop_visit {
+                    format(ObjectType::StopPoint, &mut visit.monitoring_ref);
+                    format(ObjectType::Item, &mut visit.item_identifier);
+                    let vj = &mut visit.targeted_vehicle_journey;
+                    format(ObjectType::Line, &mut vj.line_ref);
+                    format(
+                        ObjectType::VehicleJourney,
+                        &mut vj.framed_vehicle_journey_ref.dated_vehicle_journey_ref,
+                    );
+                    vj.operator_ref
+                        .iter_mut()
+                        .for_each(|id| format(ObjectType::Operator, id));
+                }
+            }
+        }
+    }
+
+    /// The fields required by the profile that are missing in a response,
+    /// and the refs that are not in the format of the profile
+    pub fn check(self, response: &SiriResponse) -> Vec<String> {
+        let mut errors = vec![];
+        let siri = &response.siri;
+        let mut check_ref = |field: &str, siri_ref: &str| {
+            if self == SiriProfile::Idf && local_id(siri_ref) == siri_ref {
+                errors.push(format!("{} '{}' is not in the IDF format", field, siri_ref));
+            }
+        };
+
+        if let Some(delivery) = &siri.stop_points_delivery {
+            for stop in &delivery.annotated_stop_point {
+                check_ref("StopPointRef", &stop.stop_point_ref);
+                check_ref("StopAreaRef", &stop.stop_area_ref);
+            }
+        }
+        if let Some(delivery) = &siri.lines_delivery {
+            for line in &delivery.annotated_line_ref {
+                check_ref("LineRef", &line.line_ref);
+            }
+        }
+        let delivery = match &siri.service_delivery {
+            Some(d) => d,
+            None => return errors,
+        };
+        let visits = delivery
+            .stop_monitoring_delivery
+            .iter()
+            .flat_map(|sm| sm.monitored_stop_visit.iter());
+        for visit in visits.clone() {
+            check_ref("MonitoringRef", &visit.monitoring_ref);
+            check_ref("ItemIdentifier", &visit.item_identifier);
+            check_ref("LineRef", &visit.monitored_vehicle_journey.line_ref);
+        }
+        let frames = delivery
+            .production_timetable_delivery
+            .iter()
+            .flat_map(|pt| pt.dated_timetable_version_frame.iter());
+        for frame in frames {
+            check_ref("LineRef", &frame.line_ref);
+            for vj in &frame.dated_vehicle_journey {
+                check_ref("DatedVehicleJourneyCode", &vj.dated_vehicle_journey_code);
+            }
+        }
+        let timetabled_visits = delivery
+            .stop_timetable_delivery
+            .iter()
+            .flat_map(|st| st.timetabled_stop_visit.iter());
+        for visit in timetabled_visits {
+            check_ref("MonitoringRef", &visit.monitoring_ref);
+            check_ref("LineRef", &visit.targeted_vehicle_journey.line_ref);
+        }
+
+        let mut missing = |field: &str, is_missing: bool| {
+            if is_missing {
+                errors.push(format!("{} is missing", field));
+            }
+        };
+        missing("ProducerRef", delivery.producer_ref.is_none());
+        missing(
+            "ResponseMessageIdentifier",
+            delivery.response_message_identifier.is_none(),
+        );
+        if self == SiriProfile::Idf {
+            for visit in visits {
+                let call = visit.monitored_vehicle_journey.monitored_call.as_ref();
+                missing(
+                    "VehicleAtStop",
+                    call.map_or(true, |c| c.vehicle_at_stop.is_none()),
+                );
+            }
+            let info_messages = delivery
+                .general_message_delivery
+                .iter()
+                .flat_map(|gm| gm.info_messages.iter());
+            for message in info_messages {
+                missing("RecordedAtTime", message.recorded_at_time.is_none());
+                missing("FormatRef", message.format.is_none());
+                missing(
+                    "MessageType",
+                    message
+                        .content
+                        .message
+                        .iter()
+                        .any(|m| m.message_type.is_none()),
+                );
+            }
+        }
+        errors
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{local_id, ObjectType, SiriProfile};
+
+    #[test]
+    fn format_refs() {
+        let generic = SiriProfile::Generic;
+        assert_eq!(
+            generic.format_ref(ObjectType::StopPoint, "TR", "EMSI"),
+            "EMSI"
+        );
+        assert_eq!(
+            generic.format_ref(ObjectType::ResponseMessage, "TR", "42"),
+            "TR:ResponseMessage:42"
+        );
+
+        let idf = SiriProfile::Idf;
+        assert_eq!(
+            idf.format_ref(ObjectType::StopPoint, "TR", "EMSI"),
+            "STIF:StopPoint:Q:EMSI:"
+        );
+        assert_eq!(
+            idf.format_ref(ObjectType::StopArea, "TR", "EMSI"),
+            "STIF:StopArea:SP:EMSI:"
+        );
+        assert_eq!(
+            idf.format_ref(ObjectType::Line, "TR", "CITY"),
+            "STIF:Line::CITY:"
+        );
+        assert_eq!(
+            idf.format_ref(ObjectType::VehicleJourney, "TR", "CITY1"),
+            "TR:VehicleJourney::CITY1:LOC"
+        );
+    }
+
+    #[test]
+    fn local_ids() {
+        assert_eq!(local_id("STIF:StopPoint:Q:EMSI:"), "EMSI");
+        assert_eq!(local_id("STIF:Line::CITY:"), "CITY");
+        assert_eq!(local_id("TR:VehicleJourney::CITY1:LOC"), "CITY1");
+        // the ids of the dataset can contain some ':'
+        assert_eq!(local_id("STIF:StopPoint:Q:stop:1:"), "stop:1");
+        // the other refs are kept as is
+        assert_eq!(local_id("EMSI"), "EMSI");
+        assert_eq!(local_id("network:stop:1"), "network:stop:1");
+        assert_eq!(local_id("STIF:Unknown:Q:EMSI:"), "STIF:Unknown:Q:EMSI:");
+    }
+}
@@ -1,4 +1,10 @@
+use crate::siri_lite::estimated_timetable::EstimatedTimetableDelivery;
 use crate::siri_lite::general_message::GeneralMessageDelivery;
+use crate::siri_lite::production_timetable::ProductionTimetableDelivery;
+use crate::siri_lite::profile::{ObjectType, SiriProfile};
+use crate::siri_lite::shared::ErrorCondition;
+use crate::siri_lite::situation_exchange::SituationExchangeDelivery;
+use crate::siri_lite::stop_timetable::StopTimetableDelivery;
 use crate::siri_lite::DateTime;
 use openapi_schema::OpenapiSchema;
 
@@ -56,11 +62,23 @@ pub struct ServiceInfoGroup {
     */
 }
 
+#[derive(Debug, Serialize, Deserialize, OpenapiSchema)]
+#[serde(rename_all = "PascalCase")]
+pub struct FramedVehicleJourneyRef {
+    /// Date of the vehicle journey
+    pub data_frame_ref: String,
+    /// Id of the vehicle journey
+    pub dated_vehicle_journey_ref: String,
+}
+
 #[derive(Debug, Serialize, Deserialize, OpenapiSchema)]
 #[serde(rename_all = "PascalCase")]
 pub struct MonitoredVehicleJourney {
     /// Id of the line
     pub line_ref: String,
+    /// Reference of the dated vehicle journey
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub framed_vehicle_journey_ref: Option<FramedVehicleJourneyRef>,
     #[serde(flatten)]
     pub service_info: ServiceInfoGroup,
     /// Id of the journey pattern
@@ -80,6 +98,9 @@ pub struct MonitoredStopVisit {
     /// Id of the couple Stop / VehicleJourney
     pub item_identifier: String,
     pub monitored_vehicle_journey: MonitoredVehicleJourney,
+    /// Link to the detail of the vehicle journey
+    #[serde(flatten, default)]
+    pub links: crate::routes::Links,
 }
 
 #[derive(Debug, Serialize, Deserialize, OpenapiSchema)]
@@ -91,9 +112,18 @@ pub struct StopMonitoringDelivery {
     pub response_time_stamp: String,
     /// Id of the query
     #[serde(skip_serializing_if = "Option::is_none")]
-    pub request_message_ref: Option<String>, // Note: this is mandatory for idf profil
+    pub request_message_ref: Option<String>, // Note: this is mandatory for idf profil, it is given with the `MessageIdentifier` of the request
+    /// Id of the subscriber, only set in the subscription's notifications
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub subscriber_ref: Option<String>,
+    /// Id of the subscription, only set in the subscription's notifications
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub subscription_ref: Option<String>,
     /// Status of the response, true if the response has been correctly treated, false otherwise
     pub status: bool,
+    /// Description of the error, only set if status is false
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error_condition: Option<ErrorCondition>,
     pub monitored_stop_visit: Vec<MonitoredStopVisit>,
 }
 
@@ -110,9 +140,34 @@ pub struct ServiceDelivery {
     pub address: Option<String>,
     /// Id of the response
     #[serde(skip_serializing_if = "Option::is_none")]
-    pub response_message_identifier: Option<String>, // Note: this is mandatory for idf profil
+    pub response_message_identifier: Option<String>,
     #[serde(skip_serializing_if = "Vec::is_empty", default)]
     pub stop_monitoring_delivery: Vec<StopMonitoringDelivery>,
     #[serde(skip_serializing_if = "Vec::is_empty", default)]
     pub general_message_delivery: Vec<GeneralMessageDelivery>,
+    #[serde(skip_serializing_if = "Vec::is_empty", default)]
+    pub estimated_timetable_delivery: Vec<EstimatedTimetableDelivery>,
+    #[serde(skip_serializing_if = "Vec::is_empty", default)]
+    pub situation_exchange_delivery: Vec<SituationExchangeDelivery>,
+    #[serde(skip_serializing_if = "Vec::is_empty", default)]
+    pub production_timetable_delivery: Vec<ProductionTimetableDelivery>,
+    #[serde(skip_serializing_if = "Vec::is_empty", default)]
+    pub stop_timetable_delivery: Vec<StopTimetableDelivery>,
+}
+
+impl ServiceDelivery {
+    /// Empty delivery of a producer, with a new unique response identifier
+    ///
+    /// Note: the ids are formatted with the generic profile
+    pub fn new(producer_ref: &str) -> Self {
+        Self {
+            producer_ref: Some(producer_ref.to_owned()),
+            response_message_identifier: Some(SiriProfile::Generic.format_ref(
+                ObjectType::ResponseMessage,
+                producer_ref,
+                &crate::siri_lite::shared::new_message_id(),
+            )),
+            ..Default::default()
+        }
+    }
 }
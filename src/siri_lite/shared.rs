@@ -1,11 +1,75 @@
 use openapi_schema::OpenapiSchema;
 
+/// Local datetime of siri, with its offset to UTC
+///
+/// The responses always give the offset, but it can be omitted in the requests,
+/// the datetime is then in the timezone of the dataset
 #[derive(Debug)]
-pub struct DateTime(pub chrono::NaiveDateTime);
+pub struct DateTime(pub chrono::NaiveDateTime, pub Option<chrono::FixedOffset>);
 
-impl std::string::ToString for DateTime {
-    fn to_string(&self) -> String {
-        self.0.format("%Y-%m-%dT%H:%M:%S").to_string()
+impl From<chrono::NaiveDateTime> for DateTime {
+    fn from(dt: chrono::NaiveDateTime) -> Self {
+        DateTime(dt, None)
+    }
+}
+
+impl DateTime {
+    /// Local datetime in a timezone, with the offset of the timezone at this datetime
+    pub fn in_timezone(dt: chrono::NaiveDateTime, timezone: chrono_tz::Tz) -> Self {
+        use chrono::{Offset, TimeZone};
+        let offset = timezone
+            .offset_from_local_datetime(&dt)
+            .earliest()
+            .map(|offset| offset.fix());
+        DateTime(dt, offset)
+    }
+
+    /// Datetime of a connection at its stop point
+    ///
+    /// The connections are in the timezone of the dataset, the datetime is given in
+    /// the timezone of the stop point (or of the vehicle journey's network)
+    pub fn at_stop(
+        dataset: &crate::datasets::Dataset,
+        connection: &crate::datasets::Connection,
+        dt: chrono::NaiveDateTime,
+    ) -> Self {
+        use chrono::{Offset, TimeZone};
+        let timezone = dataset
+            .timezones
+            .of_stop_point(connection.stop_point_idx, connection.dated_vj.vj_idx);
+        dataset.timezone.from_local_datetime(&dt).earliest().map_or(
+            DateTime(dt, None),
+            |dataset_dt| {
+                let local_dt = dataset_dt.with_timezone(&timezone);
+                DateTime(local_dt.naive_local(), Some(local_dt.offset().fix()))
+            },
+        )
+    }
+
+    /// Local datetime in a timezone
+    ///
+    /// A datetime without offset is considered to already be in this timezone
+    pub fn naive_in(&self, timezone: chrono_tz::Tz) -> chrono::NaiveDateTime {
+        match self.1 {
+            Some(offset) => {
+                chrono::DateTime::<chrono::FixedOffset>::from_utc(self.0 - offset, offset)
+                    .with_timezone(&timezone)
+                    .naive_local()
+            }
+            None => self.0,
+        }
+    }
+}
+
+impl std::fmt::Display for DateTime {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.1 {
+            Some(offset) => {
+                let dt = chrono::DateTime::<chrono::FixedOffset>::from_utc(self.0 - offset, offset);
+                write!(f, "{}", dt.format("%Y-%m-%dT%H:%M:%S%:z"))
+            }
+            None => write!(f, "{}", self.0.format("%Y-%m-%dT%H:%M:%S")),
+        }
     }
 }
 
@@ -24,10 +88,14 @@ impl<'de> ::serde::Deserialize<'de> for DateTime {
         D: ::serde::Deserializer<'de>,
     {
         let s = String::deserialize(deserializer)?;
+        if let Ok(dt) = chrono::DateTime::parse_from_rfc3339(&s) {
+            return Ok(DateTime(dt.naive_local(), Some(*dt.offset())));
+        }
         Ok(DateTime(
             chrono::NaiveDateTime::parse_from_str(&s, "%Y-%m-%dT%H:%M:%S").map_err(|e| {
                 serde::de::Error::custom(format!("datetime format not valid: {}", e))
             })?,
+            None,
         ))
     }
 }
@@ -44,18 +112,130 @@ impl OpenapiSchema for DateTime {
     }
 }
 
+/// Errors that can be returned by a siri service
+///
+/// They are given back to the client in the `ErrorCondition` of the delivery,
+/// with a `Status` set to false
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SiriError {
+    /// An object referenced in the request (stop, line, ...) does not exist
+    InvalidDataReferences(String),
+    /// There is no information available for the requested topic
+    NoInfoForTopic(String),
+    /// The request uses a capability not handled by this service
+    CapabilityNotSupported(String),
+    /// The requested period is outside the horizon of the available data
+    BeyondDataHorizon(String),
+    /// The service is temporarily unavailable (the data cannot be loaded for example)
+    ServiceNotAvailable(String),
+    /// The parameters of the request are not valid
+    InvalidRequest(String),
+    /// Any other error
+    Other(String),
+}
+
+impl SiriError {
+    pub fn text(&self) -> &str {
+        match self {
+            SiriError::InvalidDataReferences(t)
+            | SiriError::NoInfoForTopic(t)
+            | SiriError::CapabilityNotSupported(t)
+            | SiriError::BeyondDataHorizon(t)
+            | SiriError::ServiceNotAvailable(t)
+            | SiriError::InvalidRequest(t)
+            | SiriError::Other(t) => t,
+        }
+    }
+
+    /// http status corresponding to the error
+    pub fn status_code(&self) -> actix_web::http::StatusCode {
+        use actix_web::http::StatusCode;
+        match self {
+            SiriError::InvalidDataReferences(_) | SiriError::NoInfoForTopic(_) => {
+                StatusCode::NOT_FOUND
+            }
+            SiriError::CapabilityNotSupported(_)
+            | SiriError::BeyondDataHorizon(_)
+            | SiriError::InvalidRequest(_) => StatusCode::BAD_REQUEST,
+            SiriError::ServiceNotAvailable(_) => StatusCode::BAD_GATEWAY,
+            SiriError::Other(_) => StatusCode::INTERNAL_SERVER_ERROR,
+        }
+    }
+
+    pub fn error_condition(&self) -> ErrorCondition {
+        let error = Some(ErrorDescription {
+            error_text: self.text().to_owned(),
+        });
+        let mut condition = ErrorCondition::default();
+        match self {
+            SiriError::InvalidDataReferences(_) => condition.invalid_data_references_error = error,
+            SiriError::NoInfoForTopic(_) => condition.no_info_for_topic_error = error,
+            SiriError::CapabilityNotSupported(_) => {
+                condition.capability_not_supported_error = error
+            }
+            SiriError::BeyondDataHorizon(_) => condition.beyond_data_horizon = error,
+            SiriError::ServiceNotAvailable(_) => condition.service_not_available_error = error,
+            // there is no specific siri error for invalid requests
+            SiriError::InvalidRequest(_) | SiriError::Other(_) => condition.other_error = error,
+        }
+        condition
+    }
+}
+
+impl std::fmt::Display for SiriError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.text())
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, OpenapiSchema, Clone)]
+#[serde(rename_all = "PascalCase")]
+pub struct ErrorDescription {
+    pub error_text: String,
+}
+
+/// Description of the error of a delivery
+///
+/// Only one of the error is filled
+#[derive(Debug, Serialize, Deserialize, OpenapiSchema, Clone, Default)]
+#[serde(rename_all = "PascalCase")]
+pub struct ErrorCondition {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub invalid_data_references_error: Option<ErrorDescription>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub no_info_for_topic_error: Option<ErrorDescription>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub capability_not_supported_error: Option<ErrorDescription>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub beyond_data_horizon: Option<ErrorDescription>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub service_not_available_error: Option<ErrorDescription>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub other_error: Option<ErrorDescription>,
+}
+
 /// Common fields used by all the siri's Delivery
 ///
 /// Note: it is referenced as `xxxDelivery` in the siri specifications
 #[derive(Serialize, Deserialize, OpenapiSchema, Debug)]
+#[serde(rename_all = "PascalCase")]
 pub struct CommonDelivery {
     pub version: String,
     pub response_time_stamp: String,
     #[serde(skip_serializing_if = "Option::is_none")]
     /// Id of the query
     pub request_message_ref: Option<String>,
+    /// Id of the subscriber, only set in the subscription's notifications
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub subscriber_ref: Option<String>,
+    /// Id of the subscription, only set in the subscription's notifications
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub subscription_ref: Option<String>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub status: Option<bool>,
+    /// Description of the error, only set if status is false
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error_condition: Option<ErrorCondition>,
 }
 
 impl Default for CommonDelivery {
@@ -63,9 +243,79 @@ impl Default for CommonDelivery {
         CommonDelivery {
             version: "2.0".to_string(),
             response_time_stamp: chrono::Utc::now().to_rfc3339(),
-            // error_condition: None,
+            error_condition: None,
             status: Some(true),
             request_message_ref: None,
+            subscriber_ref: None,
+            subscription_ref: None,
+        }
+    }
+}
+
+impl CommonDelivery {
+    pub fn from_error(error: &SiriError) -> Self {
+        CommonDelivery {
+            status: Some(false),
+            error_condition: Some(error.error_condition()),
+            ..Default::default()
         }
     }
 }
+
+static MESSAGE_COUNTER: std::sync::atomic::AtomicU64 = std::sync::atomic::AtomicU64::new(0);
+
+/// Create a unique id for a message
+///
+/// The id is made of the creation time and of a counter,
+/// so it stays unique even for messages created at the same instant
+pub fn new_message_id() -> String {
+    let counter = MESSAGE_COUNTER.fetch_add(1, std::sync::atomic::Ordering::Relaxed);
+    format!("{}-{}", chrono::Utc::now().timestamp_millis(), counter)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::new_message_id;
+
+    #[test]
+    fn unique_message_ids() {
+        assert_ne!(new_message_id(), new_message_id());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::DateTime;
+
+    #[test]
+    fn datetime_offset() {
+        let dt = chrono::NaiveDate::from_ymd(2019, 2, 6).and_hms(10, 0, 0);
+        assert_eq!(DateTime::from(dt).to_string(), "2019-02-06T10:00:00");
+        let with_offset = DateTime(dt, Some(chrono::FixedOffset::east(3600)));
+        assert_eq!(with_offset.to_string(), "2019-02-06T10:00:00+01:00");
+
+        let parsed: DateTime = serde_json::from_str("\"2019-02-06T10:00:00+01:00\"").unwrap();
+        assert_eq!(parsed.0, dt);
+        assert_eq!(parsed.1, Some(chrono::FixedOffset::east(3600)));
+        let parsed: DateTime = serde_json::from_str("\"2019-02-06T10:00:00\"").unwrap();
+        assert_eq!(parsed.1, None);
+    }
+
+    #[test]
+    fn datetime_in_timezone() {
+        let dt = chrono::NaiveDate::from_ymd(2019, 2, 6).and_hms(10, 0, 0);
+        let paris = chrono_tz::Europe::Paris;
+        assert_eq!(
+            DateTime::in_timezone(dt, paris).to_string(),
+            "2019-02-06T10:00:00+01:00"
+        );
+
+        // a datetime without offset is already in the timezone
+        assert_eq!(DateTime::from(dt).naive_in(paris), dt);
+        let utc_dt = DateTime(dt, Some(chrono::FixedOffset::east(0)));
+        assert_eq!(
+            utc_dt.naive_in(paris),
+            chrono::NaiveDate::from_ymd(2019, 2, 6).and_hms(11, 0, 0)
+        );
+    }
+}
@@ -0,0 +1,119 @@
+use crate::siri_lite::general_message::NaturalLangString;
+use crate::siri_lite::DateTime;
+use openapi_schema::OpenapiSchema;
+
+#[derive(Debug, Serialize, Deserialize, OpenapiSchema)]
+#[serde(rename_all = "PascalCase")]
+pub struct HalfOpenTimestampRange {
+    pub start_time: DateTime,
+    /// No end time means that the period is open
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub end_time: Option<DateTime>,
+}
+
+#[derive(Debug, Serialize, Deserialize, OpenapiSchema)]
+#[serde(rename_all = "PascalCase")]
+pub struct SituationSource {
+    /// Type of the source of the situation, `feed` for the situations read from the GTFS-RT
+    pub source_type: String,
+}
+
+#[derive(Debug, Serialize, Deserialize, OpenapiSchema)]
+#[serde(rename_all = "PascalCase")]
+pub struct AffectedLine {
+    pub line_ref: String,
+}
+
+#[derive(Debug, Serialize, Deserialize, OpenapiSchema)]
+#[serde(rename_all = "PascalCase")]
+pub struct AffectedNetwork {
+    /// Id of the network, unknown if the lines cannot be found in the dataset
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub network_ref: Option<String>,
+    /// true if all the lines of the network are affected
+    #[serde(skip_serializing_if = "std::ops::Not::not", default)]
+    pub all_lines: bool,
+    #[serde(skip_serializing_if = "Vec::is_empty", default)]
+    pub affected_line: Vec<AffectedLine>,
+}
+
+#[derive(Debug, Serialize, Deserialize, OpenapiSchema)]
+#[serde(rename_all = "PascalCase")]
+pub struct AffectedStopPoint {
+    pub stop_point_ref: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub stop_point_name: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, OpenapiSchema)]
+#[serde(rename_all = "PascalCase")]
+pub struct AffectedStopPlace {
+    /// Id of the stop area
+    pub stop_place_ref: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub place_name: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, OpenapiSchema)]
+#[serde(rename_all = "PascalCase")]
+pub struct AffectedVehicleJourney {
+    pub vehicle_journey_ref: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub line_ref: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, OpenapiSchema, Default)]
+#[serde(rename_all = "PascalCase")]
+pub struct Affects {
+    #[serde(skip_serializing_if = "Vec::is_empty", default)]
+    pub affected_network: Vec<AffectedNetwork>,
+    #[serde(skip_serializing_if = "Vec::is_empty", default)]
+    pub affected_stop_point: Vec<AffectedStopPoint>,
+    #[serde(skip_serializing_if = "Vec::is_empty", default)]
+    pub affected_stop_place: Vec<AffectedStopPlace>,
+    #[serde(skip_serializing_if = "Vec::is_empty", default)]
+    pub affected_vehicle_journey: Vec<AffectedVehicleJourney>,
+}
+
+#[derive(Debug, Serialize, Deserialize, OpenapiSchema)]
+#[serde(rename_all = "PascalCase")]
+pub struct PtSituationElement {
+    /// Datetime of the creation of the situation
+    /// Note: the GTFS-RT does not give it, so it is the datetime of the GTFS-RT update
+    pub creation_time: DateTime,
+    /// Id of the producer of the situation
+    pub participant_ref: String,
+    /// Uniq identifier of the situation
+    pub situation_number: String,
+    pub source: SituationSource,
+    /// Progress of the situation, always `open` since the closed situations are not in the GTFS-RT
+    pub progress: String,
+    /// Periods when the situation is active
+    pub validity_period: Vec<HalfOpenTimestampRange>,
+    /// Window in which the situation can be published, it covers all the validity periods
+    pub publication_window: HalfOpenTimestampRange,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub miscellaneous_reason: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub personnel_reason: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub equipment_reason: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub environment_reason: Option<String>,
+    /// Severity of the situation
+    pub severity: String,
+    /// Short description of the situation, one by language
+    pub summary: Vec<NaturalLangString>,
+    /// Long description of the situation, one by language
+    #[serde(skip_serializing_if = "Vec::is_empty", default)]
+    pub description: Vec<NaturalLangString>,
+    pub affects: Affects,
+}
+
+#[derive(Debug, Serialize, Deserialize, OpenapiSchema, Default)]
+#[serde(rename_all = "PascalCase")]
+pub struct SituationExchangeDelivery {
+    #[serde(flatten)]
+    pub common: crate::siri_lite::shared::CommonDelivery,
+    pub situations: Vec<PtSituationElement>,
+}
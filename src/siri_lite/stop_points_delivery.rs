@@ -1,12 +1,18 @@
+use crate::siri_lite::lines_delivery::{line_colour, vehicle_mode};
 use openapi_schema::OpenapiSchema;
+use std::collections::BTreeSet;
 use transit_model::collection::Idx;
-use transit_model::objects::StopPoint;
+use transit_model::objects::{Availability, Line as NtmLine, PhysicalMode, StopPoint};
 use transit_model::Model;
 
 #[derive(Debug, Serialize, Deserialize, OpenapiSchema)]
 #[serde(rename_all = "PascalCase")]
 pub struct Line {
     pub line_ref: String,
+    pub line_name: String,
+    /// Colour of the line, as a RGB hexadecimal string
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub line_colour: Option<String>,
 }
 
 #[derive(Debug, Serialize, Deserialize, OpenapiSchema)]
@@ -20,8 +26,19 @@ pub struct Location {
 pub struct AnnotatedStopPoint {
     pub stop_point_ref: String,
     pub stop_name: String,
+    /// Id of the parent stop area
+    pub stop_area_ref: String,
+    pub stop_area_name: String,
+    /// Lines serving the stop point
     pub lines: Vec<Line>,
+    /// Modes of the vehicles serving the stop point
+    pub vehicle_mode: Vec<String>,
+    /// Wheelchair accessibility of the stop point, `true`, `false` or `unknown`
+    pub wheelchair_access: String,
     pub location: Location,
+    /// Distance in meters to the requested location, only given for a proximity search
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub distance: Option<f64>,
 }
 
 #[derive(Debug, Serialize, Deserialize, OpenapiSchema)]
@@ -32,26 +49,120 @@ pub struct StopPointsDelivery {
     pub annotated_stop_point: Vec<AnnotatedStopPoint>,
 }
 
+/// Wheelchair accessibility of a stop point, as a IFOPT limitation status
+///
+/// If the stop point has no information (like a GTFS `wheelchair_boarding=0`),
+/// it inherits the accessibility of its stop area
+pub fn wheelchair_access(stop_point: &StopPoint, model: &Model) -> &'static str {
+    let availability = |equipment_id: &Option<String>| {
+        equipment_id
+            .as_ref()
+            .and_then(|id| model.equipments.get(id))
+            .map(|e| &e.wheelchair_boarding)
+            .filter(|a| !matches!(a, Availability::InformationNotAvailable))
+    };
+    let availability = availability(&stop_point.equipment_id).or_else(|| {
+        model
+            .stop_areas
+            .get(&stop_point.stop_area_id)
+            .and_then(|sa| availability(&sa.equipment_id))
+    });
+    match availability {
+        Some(Availability::Available) => "true",
+        Some(Availability::NotAvailable) => "false",
+        _ => "unknown",
+    }
+}
+
 impl AnnotatedStopPoint {
     pub fn from(stop_point_idx: Idx<StopPoint>, model: &Model) -> Self {
-        let lines = model
-            .get_corresponding_from_idx(stop_point_idx)
+        let lines: BTreeSet<Idx<NtmLine>> = model.get_corresponding_from_idx(stop_point_idx);
+        let modes: BTreeSet<Idx<PhysicalMode>> = model.get_corresponding_from_idx(stop_point_idx);
+        let vehicle_modes: BTreeSet<_> = modes
             .into_iter()
-            .map(|route_id| Line {
-                line_ref: model.routes[route_id].id.clone(),
-            })
+            .map(|idx| vehicle_mode(&model.physical_modes[idx]).to_owned())
             .collect();
 
         let sp = &model.stop_points[stop_point_idx];
+        let stop_area = model.stop_areas.get(&sp.stop_area_id);
 
         Self {
             stop_point_ref: sp.id.clone(),
             stop_name: sp.name.clone(),
-            lines,
+            stop_area_ref: sp.stop_area_id.clone(),
+            stop_area_name: stop_area.map_or_else(|| sp.name.clone(), |sa| sa.name.clone()),
+            lines: lines
+                .into_iter()
+                .map(|idx| {
+                    let line = &model.lines[idx];
+                    Line {
+                        line_ref: line.id.clone(),
+                        line_name: line.name.clone(),
+                        line_colour: line_colour(line),
+                    }
+                })
+                .collect(),
+            vehicle_mode: vehicle_modes.into_iter().collect(),
+            wheelchair_access: wheelchair_access(sp, model).to_owned(),
             location: Location {
                 longitude: sp.coord.lon,
                 latitude: sp.coord.lat,
             },
+            distance: None,
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::wheelchair_access;
+    use transit_model::objects::{Availability, Equipment};
+    use transit_model_builder::ModelBuilder;
+
+    #[test]
+    fn wheelchair_access_inherited_from_stop_area() {
+        let model = ModelBuilder::default()
+            .vj("vj1", |vj_builder| {
+                vj_builder
+                    .st("A", "10:00:00", "10:01:00")
+                    .st("B", "11:00:00", "11:01:00");
+            })
+            .build();
+        let mut collections = model.into_collections();
+        for (id, availability) in &[
+            ("accessible", Availability::Available),
+            ("not_accessible", Availability::NotAvailable),
+            ("unknown", Availability::InformationNotAvailable),
+        ] {
+            collections
+                .equipments
+                .push(Equipment {
+                    id: id.to_string(),
+                    wheelchair_boarding: availability.clone(),
+                    ..Default::default()
+                })
+                .unwrap();
+        }
+        // the stop area of A is accessible, A has no information, B is not accessible
+        let stop_area_a = collections
+            .stop_points
+            .get("A")
+            .unwrap()
+            .stop_area_id
+            .clone();
+        collections.stop_points.get_mut("A").unwrap().equipment_id = Some("unknown".to_owned());
+        collections.stop_points.get_mut("B").unwrap().equipment_id =
+            Some("not_accessible".to_owned());
+        collections
+            .stop_areas
+            .get_mut(&stop_area_a)
+            .unwrap()
+            .equipment_id = Some("accessible".to_owned());
+        let model = transit_model::Model::new(collections).unwrap();
+
+        let stop_point = |id| model.stop_points.get(id).unwrap();
+        assert_eq!(wheelchair_access(stop_point("A"), &model), "true");
+        // the information of the stop point is used first
+        assert_eq!(wheelchair_access(stop_point("B"), &model), "false");
+    }
+}
@@ -0,0 +1,48 @@
+use crate::siri_lite::service_delivery::FramedVehicleJourneyRef;
+use crate::siri_lite::shared::CommonDelivery;
+use crate::siri_lite::DateTime;
+use openapi_schema::OpenapiSchema;
+
+#[derive(Debug, Serialize, Deserialize, OpenapiSchema)]
+#[serde(rename_all = "PascalCase")]
+pub struct TargetedCall {
+    pub order: u16,
+    pub stop_point_name: String,
+    /// Scheduled arrival time
+    pub aimed_arrival_time: DateTime,
+    /// Scheduled departure time
+    pub aimed_departure_time: DateTime,
+}
+
+#[derive(Debug, Serialize, Deserialize, OpenapiSchema)]
+#[serde(rename_all = "PascalCase")]
+pub struct TargetedVehicleJourney {
+    /// Id of the line
+    pub line_ref: String,
+    /// Reference of the dated vehicle journey
+    pub framed_vehicle_journey_ref: FramedVehicleJourneyRef,
+    /// Id of the operator
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub operator_ref: Option<String>,
+    pub targeted_call: TargetedCall,
+}
+
+#[derive(Debug, Serialize, Deserialize, OpenapiSchema)]
+#[serde(rename_all = "PascalCase")]
+pub struct TimetabledStopVisit {
+    /// Id of the stop point
+    pub monitoring_ref: String,
+    /// Datetime of the loading of the base schedule
+    pub recorded_at_time: chrono::DateTime<chrono::Utc>,
+    /// Id of the couple Stop / VehicleJourney
+    pub item_identifier: String,
+    pub targeted_vehicle_journey: TargetedVehicleJourney,
+}
+
+#[derive(Debug, Serialize, Deserialize, OpenapiSchema, Default)]
+#[serde(rename_all = "PascalCase")]
+pub struct StopTimetableDelivery {
+    #[serde(flatten)]
+    pub common: CommonDelivery,
+    pub timetabled_stop_visit: Vec<TimetabledStopVisit>,
+}
@@ -0,0 +1,239 @@
+use crate::siri_lite::shared::{ErrorCondition, SiriError};
+use crate::utils;
+use openapi_schema::OpenapiSchema;
+
+/// Siri request sent in the body of the subscription routes
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+pub struct SiriRequest {
+    pub siri: SiriRequestContent,
+}
+
+#[derive(Debug, Deserialize, Default)]
+#[serde(rename_all = "PascalCase")]
+pub struct SiriRequestContent {
+    pub subscription_request: Option<SubscriptionRequest>,
+    pub terminate_subscription_request: Option<TerminateSubscriptionRequest>,
+}
+
+#[derive(Debug, Deserialize, Clone, Default)]
+#[serde(rename_all = "PascalCase")]
+pub struct SubscriptionContext {
+    /// ISO 8601 duration between 2 heartbeat notifications
+    /// If not given, no heartbeat notifications are sent
+    pub heartbeat_interval: Option<utils::Duration>,
+}
+
+/// Parameters common to all the subscriptions
+#[derive(Debug, Deserialize, Clone)]
+#[serde(rename_all = "PascalCase")]
+pub struct SubscriptionParams {
+    /// Id of the subscription, given by the subscriber
+    pub subscription_identifier: String,
+    /// Datetime when the subscription ends
+    pub initial_termination_time: chrono::DateTime<chrono::FixedOffset>,
+    /// ISO 8601 duration, minimum change of the expected times that triggers a notification
+    /// The default is to notify all the changes
+    pub change_before_updates: Option<utils::Duration>,
+}
+
+#[derive(Debug, Deserialize, Clone)]
+#[serde(rename_all = "PascalCase")]
+pub struct StopMonitoringRequest {
+    /// Id of the stop_point on which we want the next departures
+    pub monitoring_ref: String,
+    /// Filter the departures of the given line's id
+    pub line_ref: Option<String>,
+    /// ISO 8601 duration used to filter the departures/arrivals
+    pub preview_interval: Option<utils::Duration>,
+    /// Maximum number of departures to notify
+    pub maximum_stop_visits: Option<u8>,
+}
+
+#[derive(Debug, Deserialize, Clone)]
+#[serde(rename_all = "PascalCase")]
+pub struct StopMonitoringSubscriptionRequest {
+    #[serde(flatten)]
+    pub subscription: SubscriptionParams,
+    pub stop_monitoring_request: StopMonitoringRequest,
+}
+
+#[derive(Debug, Deserialize, Clone, Default)]
+#[serde(rename_all = "PascalCase")]
+pub struct GeneralMessageRequest {
+    /// Id of the line, to get only its messages
+    pub line_ref: Option<String>,
+    /// Id of the stop point, to get only its messages (and the ones on its stop area)
+    pub stop_point_ref: Option<String>,
+    /// Channel of the messages (`Perturbation` or `Information`)
+    pub info_channel_ref: Option<String>,
+    /// Language of the messages
+    pub language: Option<String>,
+}
+
+#[derive(Debug, Deserialize, Clone)]
+#[serde(rename_all = "PascalCase")]
+pub struct GeneralMessageSubscriptionRequest {
+    #[serde(flatten)]
+    pub subscription: SubscriptionParams,
+    #[serde(default)]
+    pub general_message_request: GeneralMessageRequest,
+}
+
+#[derive(Debug, Deserialize, Clone, Default)]
+#[serde(rename_all = "PascalCase")]
+pub struct EstimatedTimetableRequest {
+    /// Id of the lines we want the vehicle journeys of
+    /// If empty, the vehicle journeys of all the lines are notified
+    #[serde(default)]
+    pub line_ref: Vec<String>,
+}
+
+#[derive(Debug, Deserialize, Clone)]
+#[serde(rename_all = "PascalCase")]
+pub struct EstimatedTimetableSubscriptionRequest {
+    #[serde(flatten)]
+    pub subscription: SubscriptionParams,
+    #[serde(default)]
+    pub estimated_timetable_request: EstimatedTimetableRequest,
+}
+
+#[derive(Debug, Deserialize, Clone)]
+#[serde(rename_all = "PascalCase")]
+pub struct SubscriptionRequest {
+    /// Id of the subscriber
+    pub requestor_ref: String,
+    /// Id of the request
+    pub message_identifier: Option<String>,
+    /// Url where the notifications are pushed
+    pub consumer_address: String,
+    #[serde(default)]
+    pub subscription_context: SubscriptionContext,
+    #[serde(default)]
+    pub stop_monitoring_subscription_request: Vec<StopMonitoringSubscriptionRequest>,
+    #[serde(default)]
+    pub general_message_subscription_request: Vec<GeneralMessageSubscriptionRequest>,
+    #[serde(default)]
+    pub estimated_timetable_subscription_request: Vec<EstimatedTimetableSubscriptionRequest>,
+}
+
+#[derive(Debug, Deserialize, Clone)]
+#[serde(rename_all = "PascalCase")]
+pub struct TerminateSubscriptionRequest {
+    /// Id of the subscriber
+    pub requestor_ref: String,
+    /// Id of the request
+    pub message_identifier: Option<String>,
+    /// Id of the subscriptions to terminate
+    #[serde(default)]
+    pub subscription_ref: Vec<String>,
+    /// If true, all the subscriptions of the subscriber are terminated
+    #[serde(default)]
+    pub all: bool,
+}
+
+#[derive(Debug, Serialize, Deserialize, OpenapiSchema)]
+#[serde(rename_all = "PascalCase")]
+pub struct ResponseStatus {
+    pub response_timestamp: chrono::DateTime<chrono::Utc>,
+    /// Id of the subscription
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub subscription_ref: Option<String>,
+    /// true if the subscription has been accepted
+    pub status: bool,
+    /// Description of the error, only set if status is false
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error_condition: Option<ErrorCondition>,
+    /// Datetime when the subscription ends
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub valid_until: Option<chrono::DateTime<chrono::Utc>>,
+}
+
+impl ResponseStatus {
+    pub fn from_error(subscription_ref: Option<String>, error: &SiriError) -> Self {
+        ResponseStatus {
+            response_timestamp: chrono::Utc::now(),
+            subscription_ref,
+            status: false,
+            error_condition: Some(error.error_condition()),
+            valid_until: None,
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, OpenapiSchema)]
+#[serde(rename_all = "PascalCase")]
+pub struct SubscriptionResponse {
+    pub response_timestamp: chrono::DateTime<chrono::Utc>,
+    /// Id of the producer
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub responder_ref: Option<String>,
+    /// Id of the request
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub request_message_ref: Option<String>,
+    /// Status of each subscription of the request
+    pub response_status: Vec<ResponseStatus>,
+}
+
+#[derive(Debug, Serialize, Deserialize, OpenapiSchema)]
+#[serde(rename_all = "PascalCase")]
+pub struct TerminationResponseStatus {
+    /// Id of the subscription
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub subscription_ref: Option<String>,
+    /// true if the subscription has been terminated
+    pub status: bool,
+    /// Description of the error, only set if status is false
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error_condition: Option<ErrorCondition>,
+}
+
+impl TerminationResponseStatus {
+    pub fn from_error(subscription_ref: Option<String>, error: &SiriError) -> Self {
+        TerminationResponseStatus {
+            subscription_ref,
+            status: false,
+            error_condition: Some(error.error_condition()),
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, OpenapiSchema)]
+#[serde(rename_all = "PascalCase")]
+pub struct TerminateSubscriptionResponse {
+    pub response_timestamp: chrono::DateTime<chrono::Utc>,
+    /// Id of the producer
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub responder_ref: Option<String>,
+    /// Id of the request
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub request_message_ref: Option<String>,
+    /// Status of each terminated subscription
+    pub termination_response_status: Vec<TerminationResponseStatus>,
+}
+
+/// Notification regularly sent to the subscribers to tell them the service is still up
+#[derive(Debug, Serialize, Deserialize, OpenapiSchema)]
+#[serde(rename_all = "PascalCase")]
+pub struct HeartbeatNotification {
+    pub request_timestamp: chrono::DateTime<chrono::Utc>,
+    /// Id of the producer
+    pub producer_ref: String,
+    pub status: bool,
+}
+
+/// Notification sent to the subscriber when a subscription has expired
+#[derive(Debug, Serialize, Deserialize, OpenapiSchema)]
+#[serde(rename_all = "PascalCase")]
+pub struct SubscriptionTerminatedNotification {
+    pub response_timestamp: chrono::DateTime<chrono::Utc>,
+    /// Id of the producer
+    pub producer_ref: String,
+    /// Id of the subscriber
+    pub subscriber_ref: String,
+    /// Id of the terminated subscription
+    pub subscription_ref: String,
+    /// Reason of the termination
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+}
@@ -0,0 +1,917 @@
+//! Serialization of the siri-lite model in SIRI 2.0 XML
+//!
+//! The XML is written by hand (and not derived from the serde serialization)
+//! because the siri XSD requires a strict ordering of the elements that differs
+//! from the siri-lite json, and some fields are XML attributes.
+
+use crate::siri_lite::check_status::CheckStatusResponse;
+use crate::siri_lite::general_message::{
+    GeneralMessageDelivery, GeneralMessageStructure, InfoMessage, InfoMessageCancellation, Message,
+    MessageType, NaturalLangString,
+};
+use crate::siri_lite::lines_delivery::{AnnotatedLineRef, LinesDelivery};
+use crate::siri_lite::production_timetable::{
+    DatedCall, DatedTimetableVersionFrame, DatedVehicleJourney, ProductionTimetableDelivery,
+};
+use crate::siri_lite::service_delivery::{
+    ArrivalStatus, MonitoredCall, MonitoredStopVisit, MonitoredVehicleJourney, ServiceDelivery,
+    StopMonitoringDelivery,
+};
+use crate::siri_lite::shared::{CommonDelivery, ErrorCondition, ErrorDescription};
+use crate::siri_lite::situation_exchange::{
+    Affects, HalfOpenTimestampRange, PtSituationElement, SituationExchangeDelivery,
+};
+use crate::siri_lite::stop_points_delivery::{AnnotatedStopPoint, StopPointsDelivery};
+use crate::siri_lite::stop_timetable::{StopTimetableDelivery, TimetabledStopVisit};
+use crate::siri_lite::SiriResponse;
+
+pub const SIRI_NAMESPACE: &str = "http://www.siri.org.uk/siri";
+
+/// Minimal XML writer
+#[derive(Default)]
+pub struct XmlWriter {
+    buf: String,
+    /// namespace prefix added to all the written elements
+    prefix: Option<String>,
+}
+
+fn escape(s: &str) -> String {
+    let mut escaped = String::with_capacity(s.len());
+    for c in s.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&apos;"),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+impl XmlWriter {
+    fn qualified_name(&self, name: &str) -> String {
+        match &self.prefix {
+            Some(prefix) => format!("{}:{}", prefix, name),
+            None => name.to_owned(),
+        }
+    }
+
+    fn open(&mut self, name: &str, attributes: &[(&str, &str)]) {
+        let name = self.qualified_name(name);
+        self.buf.push('<');
+        self.buf.push_str(&name);
+        for (key, value) in attributes {
+            self.buf
+                .push_str(&format!(" {}=\"{}\"", key, escape(value)));
+        }
+        self.buf.push('>');
+    }
+
+    fn close(&mut self, name: &str) {
+        let name = self.qualified_name(name);
+        self.buf.push_str(&format!("</{}>", name));
+    }
+
+    /// write some content with all the elements prefixed by a namespace prefix
+    /// (the namespace needs to be declared in a parent element)
+    pub fn prefixed<F: FnOnce(&mut Self)>(&mut self, prefix: &str, content: F) {
+        let previous_prefix = self.prefix.replace(prefix.to_owned());
+        content(self);
+        self.prefix = previous_prefix;
+    }
+
+    /// write an element, its content being written by `content`
+    pub fn element<F: FnOnce(&mut Self)>(&mut self, name: &str, content: F) {
+        self.element_with_attributes(name, &[], content)
+    }
+
+    pub fn element_with_attributes<F: FnOnce(&mut Self)>(
+        &mut self,
+        name: &str,
+        attributes: &[(&str, &str)],
+        content: F,
+    ) {
+        self.open(name, attributes);
+        content(self);
+        self.close(name);
+    }
+
+    /// write a simple element with a text value
+    pub fn text<T: ToString>(&mut self, name: &str, value: T) {
+        self.open(name, &[]);
+        self.buf.push_str(&escape(&value.to_string()));
+        self.close(name);
+    }
+
+    /// write a simple element with a text value, only if the value is present
+    pub fn opt_text<T: ToString>(&mut self, name: &str, value: &Option<T>) {
+        if let Some(v) = value {
+            self.text(name, v.to_string());
+        }
+    }
+
+    pub fn into_string(self) -> String {
+        self.buf
+    }
+}
+
+/// Types that can be written as a SIRI XML element
+pub trait ToXml {
+    fn write_xml(&self, w: &mut XmlWriter);
+}
+
+impl<T: ToXml> ToXml for Option<T> {
+    fn write_xml(&self, w: &mut XmlWriter) {
+        if let Some(v) = self {
+            v.write_xml(w);
+        }
+    }
+}
+
+impl<T: ToXml> ToXml for Vec<T> {
+    fn write_xml(&self, w: &mut XmlWriter) {
+        for v in self {
+            v.write_xml(w);
+        }
+    }
+}
+
+/// Serialize a siri response in XML
+pub fn to_xml(response: &SiriResponse) -> String {
+    let mut w = XmlWriter::default();
+    response.write_xml(&mut w);
+    format!(
+        "<?xml version=\"1.0\" encoding=\"UTF-8\"?>{}",
+        w.into_string()
+    )
+}
+
+impl ToXml for SiriResponse {
+    fn write_xml(&self, w: &mut XmlWriter) {
+        let siri = &self.siri;
+        w.element_with_attributes(
+            "Siri",
+            &[("xmlns", SIRI_NAMESPACE), ("version", "2.0")],
+            |w| {
+                siri.service_delivery.write_xml(w);
+                siri.stop_points_delivery.write_xml(w);
+                siri.lines_delivery.write_xml(w);
+                siri.check_status_response.write_xml(w);
+            },
+        );
+    }
+}
+
+impl ToXml for CheckStatusResponse {
+    fn write_xml(&self, w: &mut XmlWriter) {
+        w.element_with_attributes("CheckStatusResponse", &[("version", "2.0")], |w| {
+            w.text("ResponseTimestamp", self.response_timestamp.to_rfc3339());
+            w.text("ProducerRef", &self.producer_ref);
+            w.opt_text("RequestMessageRef", &self.request_message_ref);
+            w.text("Status", self.status);
+            w.text("DataReady", self.data_ready);
+            self.error_condition.write_xml(w);
+            w.opt_text(
+                "ServiceStartedTime",
+                &self.service_started_time.map(|t| t.to_rfc3339()),
+            );
+        });
+    }
+}
+
+impl ToXml for ErrorDescription {
+    fn write_xml(&self, w: &mut XmlWriter) {
+        w.text("ErrorText", &self.error_text);
+    }
+}
+
+impl ToXml for ErrorCondition {
+    fn write_xml(&self, w: &mut XmlWriter) {
+        w.element("ErrorCondition", |w| {
+            let errors = [
+                (
+                    "InvalidDataReferencesError",
+                    &self.invalid_data_references_error,
+                ),
+                ("NoInfoForTopicError", &self.no_info_for_topic_error),
+                (
+                    "CapabilityNotSupportedError",
+                    &self.capability_not_supported_error,
+                ),
+                ("BeyondDataHorizon", &self.beyond_data_horizon),
+                (
+                    "ServiceNotAvailableError",
+                    &self.service_not_available_error,
+                ),
+                ("OtherError", &self.other_error),
+            ];
+            for (name, error) in errors.iter() {
+                if let Some(error) = error {
+                    w.element(name, |w| error.write_xml(w));
+                }
+            }
+        });
+    }
+}
+
+/// The common delivery elements are at the beginning of all deliveries
+/// (but for the `Status` and `ErrorCondition` that are written with `write_common_status`)
+fn write_common_header(common: &CommonDelivery, w: &mut XmlWriter) {
+    w.text("ResponseTimestamp", &common.response_time_stamp);
+    w.opt_text("RequestMessageRef", &common.request_message_ref);
+    w.opt_text("SubscriberRef", &common.subscriber_ref);
+    w.opt_text("SubscriptionRef", &common.subscription_ref);
+}
+
+fn write_common_status(common: &CommonDelivery, w: &mut XmlWriter) {
+    w.opt_text("Status", &common.status);
+    common.error_condition.write_xml(w);
+}
+
+impl ToXml for ServiceDelivery {
+    fn write_xml(&self, w: &mut XmlWriter) {
+        w.element("ServiceDelivery", |w| {
+            w.text("ResponseTimestamp", &self.common.response_time_stamp);
+            w.opt_text("ProducerRef", &self.producer_ref);
+            w.opt_text("Address", &self.address);
+            w.opt_text(
+                "ResponseMessageIdentifier",
+                &self.response_message_identifier,
+            );
+            w.opt_text("RequestMessageRef", &self.common.request_message_ref);
+            write_common_status(&self.common, w);
+            self.stop_monitoring_delivery.write_xml(w);
+            self.general_message_delivery.write_xml(w);
+            self.situation_exchange_delivery.write_xml(w);
+            self.production_timetable_delivery.write_xml(w);
+            self.stop_timetable_delivery.write_xml(w);
+        });
+    }
+}
+
+fn arrival_status(status: &ArrivalStatus) -> &'static str {
+    match status {
+        ArrivalStatus::OnTime => "onTime",
+        ArrivalStatus::Early => "early",
+        ArrivalStatus::Delayed => "delayed",
+        ArrivalStatus::Cancelled => "cancelled",
+        ArrivalStatus::Missed => "missed",
+        ArrivalStatus::Arrived => "arrived",
+        ArrivalStatus::NotExpected => "notExpected",
+        ArrivalStatus::NoReport => "noReport",
+    }
+}
+
+impl ToXml for StopMonitoringDelivery {
+    fn write_xml(&self, w: &mut XmlWriter) {
+        w.element_with_attributes(
+            "StopMonitoringDelivery",
+            &[("version", self.version.as_str())],
+            |w| {
+                w.text("ResponseTimestamp", &self.response_time_stamp);
+                w.opt_text("RequestMessageRef", &self.request_message_ref);
+                w.opt_text("SubscriberRef", &self.subscriber_ref);
+                w.opt_text("SubscriptionRef", &self.subscription_ref);
+                w.text("Status", self.status);
+                self.error_condition.write_xml(w);
+                self.monitored_stop_visit.write_xml(w);
+            },
+        );
+    }
+}
+
+impl ToXml for MonitoredStopVisit {
+    fn write_xml(&self, w: &mut XmlWriter) {
+        w.element("MonitoredStopVisit", |w| {
+            w.text("RecordedAtTime", self.recorded_at_time.to_rfc3339());
+            w.text("ItemIdentifier", &self.item_identifier);
+            w.text("MonitoringRef", &self.monitoring_ref);
+            self.monitored_vehicle_journey.write_xml(w);
+        });
+    }
+}
+
+impl ToXml for MonitoredVehicleJourney {
+    fn write_xml(&self, w: &mut XmlWriter) {
+        w.element("MonitoredVehicleJourney", |w| {
+            w.text("LineRef", &self.line_ref);
+            if let Some(framed_vj) = &self.framed_vehicle_journey_ref {
+                w.element("FramedVehicleJourneyRef", |w| {
+                    w.text("DataFrameRef", &framed_vj.data_frame_ref);
+                    w.text(
+                        "DatedVehicleJourneyRef",
+                        &framed_vj.dated_vehicle_journey_ref,
+                    );
+                });
+            }
+            w.opt_text("JourneyPatternRef", &self.journey_pattern_ref);
+            w.opt_text("OperatorRef", &self.service_info.operator_ref);
+            self.monitored_call.write_xml(w);
+        });
+    }
+}
+
+impl ToXml for MonitoredCall {
+    fn write_xml(&self, w: &mut XmlWriter) {
+        w.element("MonitoredCall", |w| {
+            w.text("Order", self.order);
+            w.text("StopPointName", &self.stop_point_name);
+            w.opt_text("VehicleAtStop", &self.vehicle_at_stop);
+            w.opt_text("DestinationDisplay", &self.destination_display);
+            w.opt_text("AimedArrivalTime", &self.aimed_arrival_time);
+            w.opt_text("ExpectedArrivalTime", &self.expected_arrival_time);
+            if let Some(status) = &self.arrival_status {
+                w.text("ArrivalStatus", arrival_status(status));
+            }
+            w.opt_text("AimedDepartureTime", &self.aimed_departure_time);
+            w.opt_text("ExpectedDepartureTime", &self.expected_departure_time);
+        });
+    }
+}
+
+impl ToXml for GeneralMessageDelivery {
+    fn write_xml(&self, w: &mut XmlWriter) {
+        w.element_with_attributes(
+            "GeneralMessageDelivery",
+            &[("version", self.common.version.as_str())],
+            |w| {
+                write_common_header(&self.common, w);
+                write_common_status(&self.common, w);
+                self.info_messages.write_xml(w);
+                self.info_messages_cancellation.write_xml(w);
+            },
+        );
+    }
+}
+
+impl ToXml for InfoMessage {
+    fn write_xml(&self, w: &mut XmlWriter) {
+        let attributes: Vec<(&str, &str)> = self
+            .format
+            .iter()
+            .map(|f| ("formatRef", f.as_str()))
+            .collect();
+        w.element_with_attributes("GeneralMessage", &attributes, |w| {
+            w.opt_text("RecordedAtTime", &self.recorded_at_time);
+            w.opt_text("ItemIdentifier", &self.item_identifier);
+            w.opt_text("InfoMessageIdentifier", &self.info_message_identifier);
+            w.opt_text("InfoMessageVersion", &self.info_message_version);
+            w.opt_text("InfoChannelRef", &self.info_channel_ref);
+            w.opt_text("ValidUntilTime", &self.valid_until_time);
+            self.content.write_xml(w);
+        });
+    }
+}
+
+impl ToXml for InfoMessageCancellation {
+    fn write_xml(&self, w: &mut XmlWriter) {
+        w.element("GeneralMessageCancellation", |w| {
+            w.text("RecordedAtTime", &self.recorded_at_time);
+            w.opt_text("ItemRef", &self.item_identifier);
+            w.opt_text("InfoMessageIdentifier", &self.info_message_identifier);
+        });
+    }
+}
+
+impl ToXml for GeneralMessageStructure {
+    fn write_xml(&self, w: &mut XmlWriter) {
+        w.element("Content", |w| {
+            for line_ref in &self.line_ref {
+                w.text("LineRef", line_ref);
+            }
+            for stop_point_ref in &self.stop_point_ref {
+                w.text("StopPointRef", stop_point_ref);
+            }
+            for destination_ref in &self.destination_ref {
+                w.text("DestinationRef", destination_ref);
+            }
+            self.message.write_xml(w);
+        });
+    }
+}
+
+fn message_type(message_type: &MessageType) -> &'static str {
+    match message_type {
+        MessageType::shortMessage => "shortMessage",
+        MessageType::longMessage => "longMessage",
+        MessageType::textOnly => "textOnly",
+        MessageType::HTML => "HTML",
+        MessageType::RTF => "RTF",
+    }
+}
+
+impl ToXml for Message {
+    fn write_xml(&self, w: &mut XmlWriter) {
+        w.element("Message", |w| {
+            if let Some(t) = &self.message_type {
+                w.text("MessageType", message_type(t));
+            }
+            write_natural_lang_string("MessageText", &self.message_text, w);
+        });
+    }
+}
+
+fn write_natural_lang_string(name: &str, s: &NaturalLangString, w: &mut XmlWriter) {
+    let attributes: Vec<(&str, &str)> = s.lang.iter().map(|l| ("xml:lang", l.as_str())).collect();
+    w.element_with_attributes(name, &attributes, |w| w.buf.push_str(&escape(&s.value)));
+}
+
+impl ToXml for SituationExchangeDelivery {
+    fn write_xml(&self, w: &mut XmlWriter) {
+        w.element_with_attributes(
+            "SituationExchangeDelivery",
+            &[("version", self.common.version.as_str())],
+            |w| {
+                write_common_header(&self.common, w);
+                write_common_status(&self.common, w);
+                w.element("Situations", |w| self.situations.write_xml(w));
+            },
+        );
+    }
+}
+
+impl ToXml for ProductionTimetableDelivery {
+    fn write_xml(&self, w: &mut XmlWriter) {
+        w.element_with_attributes(
+            "ProductionTimetableDelivery",
+            &[("version", self.common.version.as_str())],
+            |w| {
+                write_common_header(&self.common, w);
+                write_common_status(&self.common, w);
+                self.dated_timetable_version_frame.write_xml(w);
+            },
+        );
+    }
+}
+
+impl ToXml for DatedTimetableVersionFrame {
+    fn write_xml(&self, w: &mut XmlWriter) {
+        w.element("DatedTimetableVersionFrame", |w| {
+            w.text("RecordedAtTime", self.recorded_at_time.to_rfc3339());
+            w.text("LineRef", &self.line_ref);
+            self.dated_vehicle_journey.write_xml(w);
+        });
+    }
+}
+
+impl ToXml for DatedVehicleJourney {
+    fn write_xml(&self, w: &mut XmlWriter) {
+        w.element("DatedVehicleJourney", |w| {
+            w.text("DatedVehicleJourneyCode", &self.dated_vehicle_journey_code);
+            w.opt_text("OperatorRef", &self.operator_ref);
+            w.element("DatedCalls", |w| self.dated_calls.write_xml(w));
+        });
+    }
+}
+
+impl ToXml for DatedCall {
+    fn write_xml(&self, w: &mut XmlWriter) {
+        w.element("DatedCall", |w| {
+            w.text("StopPointRef", &self.stop_point_ref);
+            w.text("Order", self.order);
+            w.text("StopPointName", &self.stop_point_name);
+            w.text("AimedArrivalTime", &self.aimed_arrival_time);
+            w.text("AimedDepartureTime", &self.aimed_departure_time);
+        });
+    }
+}
+
+impl ToXml for StopTimetableDelivery {
+    fn write_xml(&self, w: &mut XmlWriter) {
+        w.element_with_attributes(
+            "StopTimetableDelivery",
+            &[("version", self.common.version.as_str())],
+            |w| {
+                write_common_header(&self.common, w);
+                write_common_status(&self.common, w);
+                self.timetabled_stop_visit.write_xml(w);
+            },
+        );
+    }
+}
+
+impl ToXml for TimetabledStopVisit {
+    fn write_xml(&self, w: &mut XmlWriter) {
+        w.element("TimetabledStopVisit", |w| {
+            w.text("RecordedAtTime", self.recorded_at_time.to_rfc3339());
+            w.text("ItemIdentifier", &self.item_identifier);
+            w.text("MonitoringRef", &self.monitoring_ref);
+            let vj = &self.targeted_vehicle_journey;
+            w.element("TargetedVehicleJourney", |w| {
+                w.text("LineRef", &vj.line_ref);
+                w.element("FramedVehicleJourneyRef", |w| {
+                    w.text(
+                        "DataFrameRef",
+                        &vj.framed_vehicle_journey_ref.data_frame_ref,
+                    );
+                    w.text(
+                        "DatedVehicleJourneyRef",
+                        &vj.framed_vehicle_journey_ref.dated_vehicle_journey_ref,
+                    );
+                });
+                w.opt_text("OperatorRef", &vj.operator_ref);
+                let call = &vj.targeted_call;
+                w.element("TargetedCall", |w| {
+                    w.text("Order", call.order);
+                    w.text("StopPointName", &call.stop_point_name);
+                    w.text("AimedArrivalTime", &call.aimed_arrival_time);
+                    w.text("AimedDepartureTime", &call.aimed_departure_time);
+                });
+            });
+        });
+    }
+}
+
+fn write_period(name: &str, period: &HalfOpenTimestampRange, w: &mut XmlWriter) {
+    w.element(name, |w| {
+        w.text("StartTime", &period.start_time);
+        w.opt_text("EndTime", &period.end_time);
+    });
+}
+
+impl ToXml for PtSituationElement {
+    fn write_xml(&self, w: &mut XmlWriter) {
+        w.element("PtSituationElement", |w| {
+            w.text("CreationTime", &self.creation_time);
+            w.text("ParticipantRef", &self.participant_ref);
+            w.text("SituationNumber", &self.situation_number);
+            w.element("Source", |w| w.text("SourceType", &self.source.source_type));
+            w.text("Progress", &self.progress);
+            for period in &self.validity_period {
+                write_period("ValidityPeriod", period, w);
+            }
+            write_period("PublicationWindow", &self.publication_window, w);
+            w.opt_text("MiscellaneousReason", &self.miscellaneous_reason);
+            w.opt_text("PersonnelReason", &self.personnel_reason);
+            w.opt_text("EquipmentReason", &self.equipment_reason);
+            w.opt_text("EnvironmentReason", &self.environment_reason);
+            w.text("Severity", &self.severity);
+            for summary in &self.summary {
+                write_natural_lang_string("Summary", summary, w);
+            }
+            for description in &self.description {
+                write_natural_lang_string("Description", description, w);
+            }
+            self.affects.write_xml(w);
+        });
+    }
+}
+
+impl ToXml for Affects {
+    fn write_xml(&self, w: &mut XmlWriter) {
+        w.element("Affects", |w| {
+            if !self.affected_network.is_empty() {
+                w.element("Networks", |w| {
+                    for network in &self.affected_network {
+                        w.element("AffectedNetwork", |w| {
+                            w.opt_text("NetworkRef", &network.network_ref);
+                            if network.all_lines {
+                                w.element("AllLines", |_| {});
+                            }
+                            for line in &network.affected_line {
+                                w.element("AffectedLine", |w| w.text("LineRef", &line.line_ref));
+                            }
+                        });
+                    }
+                });
+            }
+            if !self.affected_stop_point.is_empty() {
+                w.element("StopPoints", |w| {
+                    for sp in &self.affected_stop_point {
+                        w.element("AffectedStopPoint", |w| {
+                            w.text("StopPointRef", &sp.stop_point_ref);
+                            w.opt_text("StopPointName", &sp.stop_point_name);
+                        });
+                    }
+                });
+            }
+            if !self.affected_stop_place.is_empty() {
+                w.element("StopPlaces", |w| {
+                    for sa in &self.affected_stop_place {
+                        w.element("AffectedStopPlace", |w| {
+                            w.text("StopPlaceRef", &sa.stop_place_ref);
+                            w.opt_text("PlaceName", &sa.place_name);
+                        });
+                    }
+                });
+            }
+            if !self.affected_vehicle_journey.is_empty() {
+                w.element("VehicleJourneys", |w| {
+                    for vj in &self.affected_vehicle_journey {
+                        w.element("AffectedVehicleJourney", |w| {
+                            w.text("VehicleJourneyRef", &vj.vehicle_journey_ref);
+                            w.opt_text("LineRef", &vj.line_ref);
+                        });
+                    }
+                });
+            }
+        });
+    }
+}
+
+impl ToXml for StopPointsDelivery {
+    fn write_xml(&self, w: &mut XmlWriter) {
+        w.element_with_attributes(
+            "StopPointsDelivery",
+            &[("version", self.common.version.as_str())],
+            |w| write_stop_points_delivery_content(self, w),
+        );
+    }
+}
+
+/// write the content of a `StopPointsDelivery`, without the enclosing element
+pub fn write_stop_points_delivery_content(delivery: &StopPointsDelivery, w: &mut XmlWriter) {
+    write_common_header(&delivery.common, w);
+    write_common_status(&delivery.common, w);
+    delivery.annotated_stop_point.write_xml(w);
+}
+
+impl ToXml for AnnotatedStopPoint {
+    fn write_xml(&self, w: &mut XmlWriter) {
+        w.element("AnnotatedStopPointRef", |w| {
+            w.text("StopPointRef", &self.stop_point_ref);
+            w.text("StopName", &self.stop_name);
+            if !self.lines.is_empty() {
+                w.element("Lines", |w| {
+                    for l in &self.lines {
+                        w.text("LineRef", &l.line_ref);
+                    }
+                });
+            }
+            w.element("Location", |w| {
+                w.text("Longitude", self.location.longitude);
+                w.text("Latitude", self.location.latitude);
+            });
+            // the fields that are not in the siri's AnnotatedStopPointRef are given as extensions
+            w.element("Extensions", |w| {
+                w.text("StopAreaRef", &self.stop_area_ref);
+                w.text("StopAreaName", &self.stop_area_name);
+                if !self.lines.is_empty() {
+                    w.element("Lines", |w| {
+                        for l in &self.lines {
+                            w.element("Line", |w| {
+                                w.text("LineRef", &l.line_ref);
+                                w.text("LineName", &l.line_name);
+                                w.opt_text("LineColour", &l.line_colour);
+                            });
+                        }
+                    });
+                }
+                for mode in &self.vehicle_mode {
+                    w.text("VehicleMode", mode);
+                }
+                w.text("WheelchairAccess", &self.wheelchair_access);
+                w.opt_text("Distance", &self.distance);
+            });
+        });
+    }
+}
+
+impl ToXml for LinesDelivery {
+    fn write_xml(&self, w: &mut XmlWriter) {
+        w.element_with_attributes(
+            "LinesDelivery",
+            &[("version", self.common.version.as_str())],
+            |w| write_lines_delivery_content(self, w),
+        );
+    }
+}
+
+/// write the content of a `LinesDelivery`, without the enclosing element
+pub fn write_lines_delivery_content(delivery: &LinesDelivery, w: &mut XmlWriter) {
+    write_common_header(&delivery.common, w);
+    write_common_status(&delivery.common, w);
+    delivery.annotated_line_ref.write_xml(w);
+}
+
+impl ToXml for AnnotatedLineRef {
+    fn write_xml(&self, w: &mut XmlWriter) {
+        w.element("AnnotatedLineRef", |w| {
+            w.text("LineRef", &self.line_ref);
+            w.text("LineName", &self.line_name);
+            w.text("Monitored", true);
+            if !self.destinations.is_empty() {
+                w.element("Destinations", |w| {
+                    for d in &self.destinations {
+                        w.element("Destination", |w| {
+                            w.text("DestinationRef", &d.destination_ref);
+                            w.text("PlaceName", &d.place_name);
+                        });
+                    }
+                });
+            }
+            // the fields that are not in the siri's AnnotatedLineRef are given as extensions
+            w.element("Extensions", |w| {
+                w.opt_text("LineCode", &self.line_code);
+                w.opt_text("LineColour", &self.line_colour);
+                for mode in &self.vehicle_mode {
+                    w.text("VehicleMode", mode);
+                }
+                w.opt_text("OperatorRef", &self.operator_ref);
+                if !self.stop_points.is_empty() {
+                    w.element("StopPoints", |w| {
+                        for sp in &self.stop_points {
+                            w.element("StopPoint", |w| {
+                                w.text("StopPointRef", &sp.stop_point_ref);
+                                w.text("StopName", &sp.stop_name);
+                            });
+                        }
+                    });
+                }
+            });
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::to_xml;
+    use crate::siri_lite::service_delivery::*;
+    use crate::siri_lite::shared::{CommonDelivery, SiriError};
+    use crate::siri_lite::{DateTime, Siri, SiriResponse};
+    use pretty_assertions::assert_eq;
+
+    fn dt(s: &str) -> DateTime {
+        DateTime::from(chrono::NaiveDateTime::parse_from_str(s, "%Y-%m-%dT%H:%M:%S").unwrap())
+    }
+
+    // the elements should be in the order given by the siri XSD
+    #[test]
+    fn stop_monitoring_xml() {
+        let response = SiriResponse {
+            siri: Siri {
+                service_delivery: Some(ServiceDelivery {
+                    common: CommonDelivery {
+                        response_time_stamp: "2018-12-15T05:22:00+00:00".to_owned(),
+                        ..Default::default()
+                    },
+                    producer_ref: Some("producer".to_owned()),
+                    stop_monitoring_delivery: vec![StopMonitoringDelivery {
+                        version: "2.0".to_owned(),
+                        response_time_stamp: "2018-12-15T05:22:00+00:00".to_owned(),
+                        request_message_ref: None,
+                        subscriber_ref: None,
+                        subscription_ref: None,
+                        status: true,
+                        error_condition: None,
+                        monitored_stop_visit: vec![MonitoredStopVisit {
+                            monitoring_ref: "EMSI".to_owned(),
+                            recorded_at_time: chrono::DateTime::<chrono::Utc>::from_utc(
+                                dt("2018-12-15T05:00:00").0,
+                                chrono::Utc,
+                            ),
+                            item_identifier: "EMSI:CITY1".to_owned(),
+                            monitored_vehicle_journey: MonitoredVehicleJourney {
+                                line_ref: "CITY".to_owned(),
+                                framed_vehicle_journey_ref: None,
+                                service_info: ServiceInfoGroup {
+                                    operator_ref: Some("DTA".to_owned()),
+                                },
+                                journey_pattern_ref: None,
+                                monitored_call: Some(MonitoredCall {
+                                    order: 5,
+                                    stop_point_name: "Main St & <Irving>".to_owned(),
+                                    vehicle_at_stop: None,
+                                    destination_display: None,
+                                    aimed_arrival_time: Some(dt("2018-12-15T06:26:00")),
+                                    aimed_departure_time: Some(dt("2018-12-15T06:28:00")),
+                                    expected_arrival_time: Some(dt("2018-12-15T06:26:30")),
+                                    expected_departure_time: None,
+                                    arrival_status: Some(ArrivalStatus::Delayed),
+                                }),
+                            },
+                            links: Default::default(),
+                        }],
+                    }],
+                    ..Default::default()
+                }),
+                ..Default::default()
+            },
+        };
+
+        assert_eq!(
+            to_xml(&response),
+            "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\
+<Siri xmlns=\"http://www.siri.org.uk/siri\" version=\"2.0\">\
+<ServiceDelivery>\
+<ResponseTimestamp>2018-12-15T05:22:00+00:00</ResponseTimestamp>\
+<ProducerRef>producer</ProducerRef>\
+<Status>true</Status>\
+<StopMonitoringDelivery version=\"2.0\">\
+<ResponseTimestamp>2018-12-15T05:22:00+00:00</ResponseTimestamp>\
+<Status>true</Status>\
+<MonitoredStopVisit>\
+<RecordedAtTime>2018-12-15T05:00:00+00:00</RecordedAtTime>\
+<ItemIdentifier>EMSI:CITY1</ItemIdentifier>\
+<MonitoringRef>EMSI</MonitoringRef>\
+<MonitoredVehicleJourney>\
+<LineRef>CITY</LineRef>\
+<OperatorRef>DTA</OperatorRef>\
+<MonitoredCall>\
+<Order>5</Order>\
+<StopPointName>Main St &amp; &lt;Irving&gt;</StopPointName>\
+<AimedArrivalTime>2018-12-15T06:26:00</AimedArrivalTime>\
+<ExpectedArrivalTime>2018-12-15T06:26:30</ExpectedArrivalTime>\
+<ArrivalStatus>delayed</ArrivalStatus>\
+<AimedDepartureTime>2018-12-15T06:28:00</AimedDepartureTime>\
+</MonitoredCall>\
+</MonitoredVehicleJourney>\
+</MonitoredStopVisit>\
+</StopMonitoringDelivery>\
+</ServiceDelivery>\
+</Siri>"
+        );
+    }
+
+    // the fields that are not in the siri's AnnotatedStopPointRef are given as extensions
+    #[test]
+    fn stop_points_xml() {
+        use crate::siri_lite::stop_points_delivery::*;
+        let response = SiriResponse {
+            siri: Siri {
+                stop_points_delivery: Some(StopPointsDelivery {
+                    common: CommonDelivery {
+                        response_time_stamp: "2018-12-15T05:22:00+00:00".to_owned(),
+                        ..Default::default()
+                    },
+                    annotated_stop_point: vec![AnnotatedStopPoint {
+                        stop_point_ref: "EMSI".to_owned(),
+                        stop_name: "E Main St".to_owned(),
+                        stop_area_ref: "Navitia:EMSI".to_owned(),
+                        stop_area_name: "E Main St".to_owned(),
+                        lines: vec![Line {
+                            line_ref: "CITY".to_owned(),
+                            line_name: "City".to_owned(),
+                            line_colour: Some("FF0000".to_owned()),
+                        }],
+                        vehicle_mode: vec!["bus".to_owned()],
+                        wheelchair_access: "unknown".to_owned(),
+                        location: Location {
+                            longitude: -116.5,
+                            latitude: 36.5,
+                        },
+                        distance: Some(12.0),
+                    }],
+                }),
+                ..Default::default()
+            },
+        };
+
+        assert_eq!(
+            to_xml(&response),
+            "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\
+<Siri xmlns=\"http://www.siri.org.uk/siri\" version=\"2.0\">\
+<StopPointsDelivery version=\"2.0\">\
+<ResponseTimestamp>2018-12-15T05:22:00+00:00</ResponseTimestamp>\
+<Status>true</Status>\
+<AnnotatedStopPointRef>\
+<StopPointRef>EMSI</StopPointRef>\
+<StopName>E Main St</StopName>\
+<Lines><LineRef>CITY</LineRef></Lines>\
+<Location><Longitude>-116.5</Longitude><Latitude>36.5</Latitude></Location>\
+<Extensions>\
+<StopAreaRef>Navitia:EMSI</StopAreaRef>\
+<StopAreaName>E Main St</StopAreaName>\
+<Lines><Line><LineRef>CITY</LineRef><LineName>City</LineName><LineColour>FF0000</LineColour></Line></Lines>\
+<VehicleMode>bus</VehicleMode>\
+<WheelchairAccess>unknown</WheelchairAccess>\
+<Distance>12</Distance>\
+</Extensions>\
+</AnnotatedStopPointRef>\
+</StopPointsDelivery>\
+</Siri>"
+        );
+    }
+
+    #[test]
+    fn error_xml() {
+        let error = SiriError::InvalidDataReferences("unknown stop".to_owned());
+        let mut common = CommonDelivery::from_error(&error);
+        common.response_time_stamp = "2018-12-15T05:22:00+00:00".to_owned();
+        let response = SiriResponse {
+            siri: Siri {
+                service_delivery: Some(ServiceDelivery {
+                    common,
+                    ..Default::default()
+                }),
+                ..Default::default()
+            },
+        };
+
+        assert_eq!(
+            to_xml(&response),
+            "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\
+<Siri xmlns=\"http://www.siri.org.uk/siri\" version=\"2.0\">\
+<ServiceDelivery>\
+<ResponseTimestamp>2018-12-15T05:22:00+00:00</ResponseTimestamp>\
+<Status>false</Status>\
+<ErrorCondition>\
+<InvalidDataReferencesError><ErrorText>unknown stop</ErrorText></InvalidDataReferencesError>\
+</ErrorCondition>\
+</ServiceDelivery>\
+</Siri>"
+        );
+    }
+}
@@ -0,0 +1,152 @@
+//! Spatial index of the stop points, used by the proximity search of the stoppoints discovery
+//!
+//! The items are stored in a grid of cells of a fixed size (in degrees), so only the cells
+//! around the searched location have to be looked at.
+//! Note: the longitude is not wrapped around the antimeridian.
+
+use std::collections::HashMap;
+use transit_model::collection::Idx;
+use transit_model::objects::{Coord, StopPoint};
+use transit_model::Model;
+
+const EARTH_RADIUS: f64 = 6_371_000.;
+// about 1km in latitude
+const CELL_SIZE: f64 = 0.01;
+
+/// great circle distance in meters between 2 coordinates (haversine formula)
+pub fn distance(a: &Coord, b: &Coord) -> f64 {
+    let d_lat = (b.lat - a.lat).to_radians();
+    let d_lon = (b.lon - a.lon).to_radians();
+    let h = (d_lat / 2.).sin().powi(2)
+        + a.lat.to_radians().cos() * b.lat.to_radians().cos() * (d_lon / 2.).sin().powi(2);
+    2. * EARTH_RADIUS * h.sqrt().min(1.).asin()
+}
+
+fn cell(lon: f64, lat: f64) -> (i64, i64) {
+    (
+        (lon / CELL_SIZE).floor() as i64,
+        (lat / CELL_SIZE).floor() as i64,
+    )
+}
+
+/// Spatial index of some located items
+pub struct SpatialIndex<K> {
+    cells: HashMap<(i64, i64), Vec<(K, Coord)>>,
+}
+
+/// Spatial index of the stop points
+pub type StopPointsSpatialIndex = SpatialIndex<Idx<StopPoint>>;
+
+impl<K: Copy + Ord> SpatialIndex<K> {
+    pub fn new(items: impl IntoIterator<Item = (K, Coord)>) -> Self {
+        let mut cells: HashMap<(i64, i64), Vec<(K, Coord)>> = HashMap::new();
+        for (key, coord) in items {
+            cells
+                .entry(cell(coord.lon, coord.lat))
+                .or_default()
+                .push((key, coord));
+        }
+        Self { cells }
+    }
+
+    /// Items within `radius` meters of `center`, with their distance, the nearest first
+    pub fn around(&self, center: &Coord, radius: f64) -> Vec<(K, f64)> {
+        if radius.is_nan() || radius < 0. {
+            return vec![];
+        }
+        let lat_delta = (radius / EARTH_RADIUS).to_degrees().min(180.);
+        let cos_lat = center.lat.to_radians().cos();
+        // near the poles all the longitudes are looked at
+        let lon_delta = if cos_lat > 1e-6 {
+            (lat_delta / cos_lat).min(360.)
+        } else {
+            360.
+        };
+        let (min_x, min_y) = cell(center.lon - lon_delta, center.lat - lat_delta);
+        let (max_x, max_y) = cell(center.lon + lon_delta, center.lat + lat_delta);
+
+        // for a big radius, it is cheaper to look at all the non empty cells
+        let nb_cells = (max_x - min_x + 1).saturating_mul(max_y - min_y + 1);
+        let candidates: Vec<&(K, Coord)> = if nb_cells as usize > self.cells.len() {
+            self.cells.values().flatten().collect()
+        } else {
+            (min_x..=max_x)
+                .flat_map(|x| (min_y..=max_y).map(move |y| (x, y)))
+                .filter_map(|c| self.cells.get(&c))
+                .flatten()
+                .collect()
+        };
+
+        let mut items: Vec<(K, f64)> = candidates
+            .into_iter()
+            .map(|(key, coord)| (*key, distance(center, coord)))
+            .filter(|(_, d)| *d <= radius)
+            .collect();
+        items.sort_by(|(key_a, d_a), (key_b, d_b)| {
+            d_a.partial_cmp(d_b)
+                .unwrap_or(std::cmp::Ordering::Equal)
+                .then_with(|| key_a.cmp(key_b))
+        });
+        items
+    }
+}
+
+impl StopPointsSpatialIndex {
+    pub fn from_model(model: &Model) -> Self {
+        Self::new(model.stop_points.iter().map(|(idx, sp)| {
+            (
+                idx,
+                Coord {
+                    lon: sp.coord.lon,
+                    lat: sp.coord.lat,
+                },
+            )
+        }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{distance, SpatialIndex};
+    use transit_model::objects::Coord;
+
+    fn coord(lon: f64, lat: f64) -> Coord {
+        Coord { lon, lat }
+    }
+
+    #[test]
+    fn haversine_distance() {
+        let paris = coord(2.3522, 48.8566);
+        let lyon = coord(4.8357, 45.7640);
+        let d = distance(&paris, &lyon);
+        assert!(d > 390_000. && d < 393_000., "distance {}", d);
+        assert!(distance(&paris, &paris) < 1e-6);
+    }
+
+    #[test]
+    fn items_around() {
+        // the items are in several cells
+        let index = SpatialIndex::new(vec![
+            ("far", coord(2.40, 48.85)),
+            ("near", coord(2.3501, 48.8500)),
+            ("nearest", coord(2.35, 48.85)),
+            ("medium", coord(2.3600, 48.8500)),
+        ]);
+        let center = coord(2.35, 48.85);
+        let keys = |radius: f64| {
+            index
+                .around(&center, radius)
+                .into_iter()
+                .map(|(k, _)| k)
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(keys(1_000.), vec!["nearest", "near", "medium"]);
+        assert_eq!(keys(100.), vec!["nearest", "near"]);
+        // a big radius looks at all the cells
+        assert_eq!(keys(10_000_000.), vec!["nearest", "near", "medium", "far"]);
+        assert!(keys(-1.).is_empty());
+
+        let around = index.around(&center, 100.);
+        assert!(around[1].1 > 7. && around[1].1 < 8., "{:?}", around);
+    }
+}
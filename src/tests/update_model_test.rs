@@ -1,3 +1,4 @@
+use crate::datasets::Timezones;
 use crate::model_update;
 use crate::tests::test_utils::{create_feed_message, make_stu, trip_update};
 use crate::transit_realtime as tr;
@@ -55,7 +56,9 @@ fn read_simple_gtfs_rt() {
     let model = simple_dataset();
     let gtfs_rt = create_simple_gtfs_rt();
 
-    let model_update = model_update::get_model_update(&model, &[gtfs_rt], chrono_tz::UTC).unwrap();
+    let model_update =
+        model_update::get_model_update(&model, &[], &[gtfs_rt], &Timezones::new(chrono_tz::UTC))
+            .unwrap();
 
     assert_eq!(model_update.trips.len(), 1);
 
@@ -77,6 +80,7 @@ fn read_simple_gtfs_rt() {
             stop_point_idx: model.stop_points.get_idx("B"),
             updated_arrival: Some(ndt("2018-12-15T11:00:30")),
             updated_departure: Some(ndt("2018-12-15T11:01:30")),
+            skipped: false,
         }
     );
     assert_eq!(
@@ -85,6 +89,7 @@ fn read_simple_gtfs_rt() {
             stop_point_idx: model.stop_points.get_idx("D"),
             updated_arrival: Some(ndt("2018-12-15T13:00:30")),
             updated_departure: Some(ndt("2018-12-15T13:01:30")),
+            skipped: false,
         }
     );
 }
@@ -143,7 +148,9 @@ fn feed_on_unknown_stop_and_trip() {
         ),
     ]);
 
-    let model_update = model_update::get_model_update(&model, &[gtfs_rt], chrono_tz::UTC).unwrap();
+    let model_update =
+        model_update::get_model_update(&model, &[], &[gtfs_rt], &Timezones::new(chrono_tz::UTC))
+            .unwrap();
 
     // we should have only 1 trip_update on the 2 from the feed, because one of them is invalid (on an invalid vj)
     assert_eq!(model_update.trips.len(), 1);
@@ -164,6 +171,7 @@ fn feed_on_unknown_stop_and_trip() {
             stop_point_idx: model.stop_points.get_idx("B"),
             updated_arrival: Some(ndt("2018-12-15T11:00:30")),
             updated_departure: Some(ndt("2018-12-15T11:01:30")),
+            skipped: false,
         }
     );
     assert_eq!(
@@ -172,6 +180,7 @@ fn feed_on_unknown_stop_and_trip() {
             stop_point_idx: model.stop_points.get_idx("D"),
             updated_arrival: Some(ndt("2018-12-15T14:00:30")),
             updated_departure: None,
+            skipped: false,
         }
     );
 }
@@ -15,7 +15,7 @@ use openapi::v3_0::{ObjectOrReference, Schema, Spec};
 use openapi_schema::OpenapiSchema;
 
 /// Duration that deseialize to ISO 8601
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct Duration(chrono::Duration);
 
 impl std::ops::Deref for Duration {
@@ -0,0 +1,148 @@
+mod utils;
+use actix_web::http::StatusCode;
+use serde_json::Value;
+use transpo_rt::datasets::DatasetInfo;
+
+const TOKEN: &str = "admin-secret";
+
+async fn admin_get(
+    srv: &mut actix_web::test::TestServer,
+    route: &str,
+    token: Option<&str>,
+) -> (StatusCode, Option<Value>) {
+    let mut request = srv.get(route);
+    if let Some(token) = token {
+        request = request.header("Authorization", format!("Bearer {}", token));
+    }
+    let mut response = request.send().await.unwrap();
+    let status = response.status();
+    (status, response.json().await.ok())
+}
+
+async fn admin_post(srv: &mut actix_web::test::TestServer, route: &str) -> StatusCode {
+    srv.post(route)
+        .header("Authorization", format!("Bearer {}", TOKEN))
+        .send()
+        .await
+        .unwrap()
+        .status()
+}
+
+async fn get_state(srv: &mut actix_web::test::TestServer) -> Value {
+    let (status, state) = admin_get(srv, "/admin/default/", Some(TOKEN)).await;
+    assert_eq!(status, StatusCode::OK);
+    state.unwrap()
+}
+
+#[actix_rt::test]
+async fn admin_integration_test() {
+    let _log_guard = utils::init_log();
+    let mut srv = utils::make_test_server_with_admin(
+        vec![DatasetInfo::new_default(
+            "fixtures/gtfs.zip",
+            &[mockito::server_url() + "/gtfs_rt"],
+        )],
+        Some(TOKEN),
+    )
+    .await;
+
+    test_unauthorized(&mut srv).await;
+    test_state(&mut srv).await;
+    test_pause(&mut srv).await;
+    test_reload(&mut srv).await;
+}
+
+async fn test_unauthorized(srv: &mut actix_web::test::TestServer) {
+    let (status, _) = admin_get(srv, "/admin/default/", None).await;
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+    let (status, _) = admin_get(srv, "/admin/default/", Some("not-the-token")).await;
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+
+    let status = srv
+        .post("/admin/default/reload/")
+        .send()
+        .await
+        .unwrap()
+        .status();
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+}
+
+async fn test_state(srv: &mut actix_web::test::TestServer) {
+    let state = get_state(srv).await;
+
+    let dataset = &state["dataset"];
+    assert!(dataset["error"].is_null());
+    assert!(dataset["loaded_at"].is_string());
+    assert_eq!(dataset["period_begin"], "2018-12-15");
+    assert_eq!(dataset["period_end"], "2018-12-16");
+    assert!(dataset["nb_connections"].as_u64().unwrap() > 0);
+
+    let base_schedule_reloader = &state["base_schedule_reloader"];
+    assert!(base_schedule_reloader["next_run"].is_string());
+    assert_eq!(base_schedule_reloader["paused"], false);
+
+    // there is no gtfs-rt server, the first realtime fetch has failed
+    let realtime_reloader = &state["realtime_reloader"];
+    assert!(realtime_reloader["last_failure"].is_string());
+    assert!(realtime_reloader["last_error"].is_string());
+}
+
+async fn test_pause(srv: &mut actix_web::test::TestServer) {
+    assert_eq!(
+        admin_post(srv, "/admin/default/pause/").await,
+        StatusCode::NO_CONTENT
+    );
+    let state = get_state(srv).await;
+    assert_eq!(state["base_schedule_reloader"]["paused"], true);
+    assert_eq!(state["realtime_reloader"]["paused"], true);
+
+    assert_eq!(
+        admin_post(srv, "/admin/default/resume/").await,
+        StatusCode::NO_CONTENT
+    );
+    let state = get_state(srv).await;
+    assert_eq!(state["base_schedule_reloader"]["paused"], false);
+    assert_eq!(state["realtime_reloader"]["paused"], false);
+}
+
+async fn test_reload(srv: &mut actix_web::test::TestServer) {
+    let loaded_at = get_state(srv).await["dataset"]["loaded_at"].clone();
+
+    assert_eq!(
+        admin_post(srv, "/admin/default/reload/").await,
+        StatusCode::ACCEPTED
+    );
+
+    // the reloading is done in the background
+    for _ in 0..50 {
+        let state = get_state(srv).await;
+        if state["base_schedule_reloader"]["last_success"].is_string() {
+            // the base schedule has been read again even if it has not changed
+            // Note: the dataset is queried again, as the new dataset is sent after the reloading
+            let state = get_state(srv).await;
+            assert_ne!(state["dataset"]["loaded_at"], loaded_at);
+            return;
+        }
+        actix_rt::time::delay_for(std::time::Duration::from_millis(100)).await;
+    }
+    panic!("the base schedule has not been reloaded");
+}
+
+#[actix_rt::test]
+async fn empty_admin_token_test() {
+    let _log_guard = utils::init_log();
+    let mut srv = utils::make_test_server_with_admin(
+        vec![DatasetInfo::new_default(
+            "fixtures/gtfs.zip",
+            &[mockito::server_url() + "/gtfs_rt"],
+        )],
+        Some(""),
+    )
+    .await;
+
+    // an empty token does not give access to the admin api
+    let (status, _) = admin_get(&mut srv, "/admin/default/", Some("")).await;
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+    let (status, _) = admin_get(&mut srv, "/admin/default/", None).await;
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+}
@@ -0,0 +1,83 @@
+use transpo_rt::datasets::DatasetInfo;
+use transpo_rt::transit_realtime;
+mod utils;
+
+fn create_mock_feed_message() -> transit_realtime::FeedMessage {
+    use transpo_rt::transit_realtime::*;
+    FeedMessage {
+        header: FeedHeader {
+            gtfs_realtime_version: "2.0".into(),
+            incrementality: Some(0i32),
+            timestamp: Some(1u64),
+        },
+        entity: vec![],
+    }
+}
+
+#[actix_rt::test]
+async fn check_status_integration_test() {
+    let _log_guard = utils::init_log();
+    let _server = utils::run_simple_gtfs_rt_server(create_mock_feed_message());
+    let valid_dataset =
+        DatasetInfo::new_default("fixtures/gtfs.zip", &[mockito::server_url() + "/gtfs_rt"]);
+    let invalid_dataset = DatasetInfo {
+        id: "invalid".into(),
+        gtfs: "fixtures/this_file_does_not_exist.zip".into(),
+        ..valid_dataset.clone()
+    };
+    let mut srv = utils::make_test_server(vec![valid_dataset, invalid_dataset]).await;
+
+    valid_dataset_status(&mut srv).await;
+    invalid_dataset_status(&mut srv).await;
+    xml_check_status(&mut srv).await;
+}
+
+async fn valid_dataset_status(srv: &mut actix_web::test::TestServer) {
+    let resp: serde_json::Value = utils::get_json(
+        srv,
+        "/default/siri/2.0/check-status.json?MessageIdentifier=my_check",
+    )
+    .await;
+    let status = &resp["Siri"]["CheckStatusResponse"];
+    assert_eq!(status["ProducerRef"], "default");
+    assert_eq!(status["RequestMessageRef"], "my_check");
+    assert_eq!(status["Status"], true);
+    assert_eq!(status["DataReady"], true);
+    assert!(status["ServiceStartedTime"].is_string());
+    assert!(status["ErrorCondition"].is_null());
+}
+
+async fn invalid_dataset_status(srv: &mut actix_web::test::TestServer) {
+    // an unavailable dataset is not an http error, it is only reported in the status
+    let resp: serde_json::Value = utils::get_json(srv, "/invalid/siri/2.0/check-status.json").await;
+    let status = &resp["Siri"]["CheckStatusResponse"];
+    assert_eq!(status["Status"], false);
+    assert_eq!(status["DataReady"], false);
+    assert!(status["ServiceStartedTime"].is_null());
+    assert!(status["ErrorCondition"]["ServiceNotAvailableError"]["ErrorText"].is_string());
+}
+
+async fn xml_check_status(srv: &mut actix_web::test::TestServer) {
+    let mut response = srv
+        .get("/default/siri/2.0/check-status.xml")
+        .send()
+        .await
+        .unwrap();
+    assert!(response.status().is_success());
+    let body = String::from_utf8(response.body().await.unwrap().to_vec()).unwrap();
+    assert!(
+        body.contains("<CheckStatusResponse version=\"2.0\">"),
+        "{}",
+        body
+    );
+    assert!(
+        body.contains("<ProducerRef>default</ProducerRef>"),
+        "{}",
+        body
+    );
+    assert!(
+        body.contains("<Status>true</Status><DataReady>true</DataReady>"),
+        "{}",
+        body
+    );
+}
@@ -0,0 +1,35 @@
+mod utils;
+use std::sync::Arc;
+use transpo_rt::actors::{DatasetLoader, LoadsCounter};
+use transpo_rt::datasets::{DatasetInfo, Datasets};
+
+#[actix_rt::test]
+async fn max_concurrent_loads_test() {
+    let _log_guard = utils::init_log();
+    let datasets = (0..5)
+        .map(|i| DatasetInfo {
+            id: format!("dataset_{}", i),
+            name: format!("dataset {}", i),
+            ..DatasetInfo::new_default("fixtures/gtfs.zip", &[mockito::server_url() + "/gtfs_rt"])
+        })
+        .collect();
+
+    // more datasets than loading threads, the other loadings wait for a thread to be free
+    let loads = Arc::new(LoadsCounter::default());
+    let dataset_loader = DatasetLoader::start_threads(2, loads.clone());
+    let registry = transpo_rt::server::create_all_actors_with_loader(
+        Datasets { datasets },
+        &utils::test_period(),
+        dataset_loader,
+        utils::test_allowed_hosts(),
+    );
+    utils::wait_for_datasets(&registry).await;
+
+    assert!(loads.peak() >= 1);
+    assert!(
+        loads.peak() <= 2,
+        "{} base schedules have been read at the same time",
+        loads.peak()
+    );
+    assert_eq!(loads.in_progress(), 0);
+}
@@ -0,0 +1,118 @@
+mod utils;
+use actix_web::http::StatusCode;
+use serde_json::Value;
+use transpo_rt::actors::UpdateDatasets;
+use transpo_rt::datasets::{DatasetInfo, Datasets};
+use utils::{get_json, get_status};
+
+fn dataset(id: &str, name: &str) -> DatasetInfo {
+    DatasetInfo {
+        id: id.to_owned(),
+        name: name.to_owned(),
+        ..DatasetInfo::new_default("fixtures/gtfs.zip", &[mockito::server_url() + "/gtfs_rt"])
+    }
+}
+
+// (id, name) of the datasets listed in the entry point
+async fn listed_datasets(srv: &mut actix_web::test::TestServer) -> Vec<(String, String)> {
+    let resp: Value = get_json(srv, "/").await;
+    resp["datasets"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|d| {
+            (
+                d["id"].as_str().unwrap().to_owned(),
+                d["name"].as_str().unwrap().to_owned(),
+            )
+        })
+        .collect()
+}
+
+// the new datasets are loaded in the background, we wait for them to be listed
+async fn wait_for_listed_datasets(
+    srv: &mut actix_web::test::TestServer,
+    expected: &[(&str, &str)],
+) {
+    let expected: Vec<(String, String)> = expected
+        .iter()
+        .map(|(id, name)| (id.to_string(), name.to_string()))
+        .collect();
+    for _ in 0..50 {
+        if listed_datasets(srv).await == expected {
+            return;
+        }
+        actix_rt::time::delay_for(std::time::Duration::from_millis(100)).await;
+    }
+    assert_eq!(listed_datasets(srv).await, expected);
+}
+
+#[actix_rt::test]
+async fn datasets_update_test() {
+    let _log_guard = utils::init_log();
+    let registry = utils::create_test_actors(vec![dataset("first", "first dataset")]).await;
+    let mut srv = utils::start_test_server(registry.clone(), None);
+
+    assert_eq!(
+        listed_datasets(&mut srv).await,
+        vec![("first".to_owned(), "first dataset".to_owned())]
+    );
+    assert_eq!(get_status(&mut srv, "/first/").await, StatusCode::OK);
+    assert_eq!(
+        get_status(&mut srv, "/second/").await,
+        StatusCode::NOT_FOUND
+    );
+
+    // a dataset is added, the first one is changed
+    registry.do_send(UpdateDatasets(Datasets {
+        datasets: vec![
+            dataset("first", "renamed dataset"),
+            dataset("second", "second dataset"),
+        ],
+    }));
+    wait_for_listed_datasets(
+        &mut srv,
+        &[("first", "renamed dataset"), ("second", "second dataset")],
+    )
+    .await;
+    assert_eq!(get_status(&mut srv, "/second/").await, StatusCode::OK);
+    assert_eq!(
+        get_status(&mut srv, "/second/siri/2.0/stoppoints-discovery.json").await,
+        StatusCode::OK
+    );
+
+    // the first dataset is removed
+    registry.do_send(UpdateDatasets(Datasets {
+        datasets: vec![dataset("second", "second dataset")],
+    }));
+    wait_for_listed_datasets(&mut srv, &[("second", "second dataset")]).await;
+    assert_eq!(get_status(&mut srv, "/first/").await, StatusCode::NOT_FOUND);
+}
+
+#[actix_rt::test]
+async fn invalid_dataset_update_test() {
+    let _log_guard = utils::init_log();
+    let registry = utils::create_test_actors(vec![dataset("first", "first dataset")]).await;
+    let mut srv = utils::start_test_server(registry.clone(), None);
+
+    // the base schedule of the first dataset is changed to a file that does not exist
+    registry.do_send(UpdateDatasets(Datasets {
+        datasets: vec![DatasetInfo {
+            gtfs: "fixtures/this_file_does_not_exist.zip".to_owned(),
+            ..dataset("first", "broken dataset")
+        }],
+    }));
+    // the loading fails right away, we give it some time to be handled
+    actix_rt::time::delay_for(std::time::Duration::from_millis(500)).await;
+
+    // the previous dataset is still served, and listed with its configuration
+    assert_eq!(
+        listed_datasets(&mut srv).await,
+        vec![("first".to_owned(), "first dataset".to_owned())]
+    );
+    assert_eq!(get_status(&mut srv, "/first/").await, StatusCode::OK);
+    assert_eq!(
+        get_status(&mut srv, "/first/siri/2.0/stoppoints-discovery.json").await,
+        StatusCode::OK
+    );
+}
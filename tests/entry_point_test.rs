@@ -41,6 +41,7 @@ async fn test_entrypoint(srv: &mut actix_web::test::TestServer) {
                         "id": "default",
                         "name": "default name",
                         "extras": {},
+                        "status": "ready",
                     }
                 ]
             }
@@ -51,8 +52,11 @@ async fn test_entrypoint(srv: &mut actix_web::test::TestServer) {
 async fn test_dataset_entrypoint(srv: &mut actix_web::test::TestServer) {
     let mut resp: Value = get_json(srv, "/default/").await;
 
-    // we change the loaded_at datetime to be able to easily compare the response
+    // we change the loaded_at datetime and the version of the base schedule (its modification time and size)
+    // to be able to easily compare the response
     *resp.pointer_mut("/loaded_at").unwrap() = "2019-06-20T10:00:00Z".into();
+    assert!(resp["source_version"].is_string());
+    *resp.pointer_mut("/source_version").unwrap() = "version".into();
     assert_eq!(
         resp,
         serde_json::json! {
@@ -61,8 +65,12 @@ async fn test_dataset_entrypoint(srv: &mut actix_web::test::TestServer) {
                 "id": "default",
                 "gtfs": "fixtures/gtfs.zip",
                 "loaded_at": "2019-06-20T10:00:00Z",
+                "source_version": "version",
                 "extras": {},
                 "_links": {
+                    "check-status": {
+                        "href": &srv.url("/default/siri/2.0/check-status.json/")
+                    },
                     "general-message": {
                         "href": &srv.url("/default/siri/2.0/general-message.json/")
                     },
@@ -72,9 +80,21 @@ async fn test_dataset_entrypoint(srv: &mut actix_web::test::TestServer) {
                     "gtfs-rt.json": {
                         "href": &srv.url("/default/gtfs-rt.json/")
                     },
+                    "lines-discovery": {
+                        "href": &srv.url("/default/siri/2.0/lines-discovery.json/")
+                    },
+                    "production-timetable": {
+                        "href": &srv.url("/default/siri/2.0/production-timetable.json/")
+                    },
+                    "situation-exchange": {
+                        "href": &srv.url("/default/siri/2.0/situation-exchange.json/")
+                    },
                     "stop-monitoring": {
                         "href": &srv.url("/default/siri/2.0/stop-monitoring.json/")
                     },
+                    "stop-timetable": {
+                        "href": &srv.url("/default/siri/2.0/stop-timetable.json/")
+                    },
                     "siri-lite": {
                         "href": &srv.url("/default/siri/2.0/")
                     },
@@ -95,12 +115,27 @@ async fn test_siri_entrypoint(srv: &mut actix_web::test::TestServer) {
         serde_json::json! {
             {
                 "_links": {
+                    "check-status": {
+                        "href": &srv.url("/default/siri/2.0/check-status.json/")
+                    },
                     "general-message": {
                         "href": &srv.url("/default/siri/2.0/general-message.json/")
                     },
+                    "lines-discovery": {
+                        "href": &srv.url("/default/siri/2.0/lines-discovery.json/")
+                    },
+                    "production-timetable": {
+                        "href": &srv.url("/default/siri/2.0/production-timetable.json/")
+                    },
+                    "situation-exchange": {
+                        "href": &srv.url("/default/siri/2.0/situation-exchange.json/")
+                    },
                     "stop-monitoring": {
                         "href": &srv.url("/default/siri/2.0/stop-monitoring.json/")
                     },
+                    "stop-timetable": {
+                        "href": &srv.url("/default/siri/2.0/stop-timetable.json/")
+                    },
                     "stoppoints-discovery": {
                         "href": &srv.url("/default/siri/2.0/stoppoints-discovery.json/")
                     }
@@ -119,15 +154,23 @@ async fn invalid_dataset_test() {
             id: "a_valid_dataset".into(),
             name: "valid dataset".into(),
             gtfs: "fixtures/gtfs.zip".to_owned(),
+            gtfs_sources: vec![],
+            base_schedule_format: Default::default(),
             gtfs_rt_urls: [mockito::server_url() + "/gtfs_rt_1"].to_vec(),
             extras: std::collections::BTreeMap::default(),
+            siri_producer_ref: None,
+            siri_profile: Default::default(),
         },
         DatasetInfo {
             id: "a_non_valid_dataset".into(),
             name: "non valid dataset".into(),
             gtfs: "non_existing_gtfs.zip".to_owned(),
+            gtfs_sources: vec![],
+            base_schedule_format: Default::default(),
             gtfs_rt_urls: [mockito::server_url() + "/gtfs_rt_1"].to_vec(),
             extras: std::collections::BTreeMap::default(),
+            siri_producer_ref: None,
+            siri_profile: Default::default(),
         },
     ])
     .await;
@@ -135,15 +178,18 @@ async fn invalid_dataset_test() {
     let resp: Value = get_json(&mut srv, "/").await;
 
     // The 2 datasets should be loaded
-    // for the moment the '/' route has no information on the status of the dataset
+    // the error of the non valid dataset is only given by its own routes
     assert_eq!(
         resp.get("datasets")
             .and_then(|v| v.as_array())
             .expect("should be an array")
             .iter()
-            .map(|d| d.get("id").unwrap().as_str().unwrap())
+            .map(|d| (
+                d.get("id").unwrap().as_str().unwrap(),
+                d.get("status").unwrap().as_str().unwrap()
+            ))
             .collect::<std::collections::BTreeSet<_>>(),
-        btreeset! {"a_valid_dataset", "a_non_valid_dataset"}
+        btreeset! {("a_valid_dataset", "ready"), ("a_non_valid_dataset", "ready")}
     );
 }
 
@@ -155,15 +201,23 @@ async fn multiple_datasets_test() {
         id: "first_dataset".into(),
         name: "First dataset".into(),
         gtfs: "fixtures/gtfs.zip".to_owned(),
+        gtfs_sources: vec![],
+        base_schedule_format: Default::default(),
         gtfs_rt_urls: [mockito::server_url() + "/gtfs_rt_1"].to_vec(),
         extras: std::collections::BTreeMap::default(),
+        siri_producer_ref: None,
+        siri_profile: Default::default(),
     };
     let second_dataset = DatasetInfo {
         id: "second_dataset".into(),
         name: "Seond dataset".into(),
         gtfs: "fixtures/gtfs.zip".to_owned(),
+        gtfs_sources: vec![],
+        base_schedule_format: Default::default(),
         gtfs_rt_urls: [mockito::server_url() + "/gtfs_rt_1"].to_vec(),
         extras: std::collections::BTreeMap::default(),
+        siri_producer_ref: None,
+        siri_profile: Default::default(),
     };
     let mut srv =
         utils::make_test_server(vec![first_dataset.clone(), second_dataset.clone()]).await;
@@ -194,8 +248,11 @@ async fn multiple_datasets_test() {
 async fn check_datasets_entrypoints(srv: &mut actix_web::test::TestServer, dataset: &DatasetInfo) {
     let mut resp: Value = get_json(srv, &format!("/{}/", dataset.id)).await;
 
-    // we change the loaded_at datetime to be able to easily compare the response
+    // we change the loaded_at datetime and the version of the base schedule (its modification time and size)
+    // to be able to easily compare the response
     *resp.pointer_mut("/loaded_at").unwrap() = "2019-06-20T10:00:00Z".into();
+    assert!(resp["source_version"].is_string());
+    *resp.pointer_mut("/source_version").unwrap() = "version".into();
     assert_eq!(
         resp,
         serde_json::json! {
@@ -204,8 +261,12 @@ async fn check_datasets_entrypoints(srv: &mut actix_web::test::TestServer, datas
                 "id": &dataset.id,
                 "gtfs": "fixtures/gtfs.zip",
                 "loaded_at": "2019-06-20T10:00:00Z",
+                "source_version": "version",
                 "extras": {},
                 "_links": {
+                    "check-status": {
+                        "href": &srv.url(&format!("/{}/siri/2.0/check-status.json/", &dataset.id))
+                    },
                     "general-message": {
                         "href": &srv.url(&format!("/{}/siri/2.0/general-message.json/", &dataset.id))
                     },
@@ -215,9 +276,21 @@ async fn check_datasets_entrypoints(srv: &mut actix_web::test::TestServer, datas
                     "gtfs-rt.json": {
                         "href": &srv.url(&format!("/{}/gtfs-rt.json/", &dataset.id))
                     },
+                    "lines-discovery": {
+                        "href": &srv.url(&format!("/{}/siri/2.0/lines-discovery.json/", &dataset.id))
+                    },
+                    "production-timetable": {
+                        "href": &srv.url(&format!("/{}/siri/2.0/production-timetable.json/", &dataset.id))
+                    },
+                    "situation-exchange": {
+                        "href": &srv.url(&format!("/{}/siri/2.0/situation-exchange.json/", &dataset.id))
+                    },
                     "stop-monitoring": {
                         "href": &srv.url(&format!("/{}/siri/2.0/stop-monitoring.json/", &dataset.id))
                     },
+                    "stop-timetable": {
+                        "href": &srv.url(&format!("/{}/siri/2.0/stop-timetable.json/", &dataset.id))
+                    },
                     "siri-lite": {
                         "href": &srv.url(&format!("/{}/siri/2.0/", &dataset.id))
                     },
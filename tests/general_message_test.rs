@@ -1,6 +1,29 @@
 use transpo_rt::transit_realtime;
 mod utils;
 
+fn active_period() -> Vec<transit_realtime::TimeRange> {
+    vec![transit_realtime::TimeRange {
+        start: Some(utils::to_timestamp("2018-12-15T08:00:00-08:00") as u64),
+        end: Some(utils::to_timestamp("2018-12-15T12:00:00-08:00") as u64),
+    }]
+}
+
+fn make_translated_string(
+    translations: &[(&str, Option<&str>)],
+) -> transit_realtime::TranslatedString {
+    transit_realtime::TranslatedString {
+        translation: translations
+            .iter()
+            .map(
+                |(text, language)| transit_realtime::translated_string::Translation {
+                    text: text.to_string(),
+                    language: language.map(str::to_owned),
+                },
+            )
+            .collect(),
+    }
+}
+
 fn create_mock_feed_message() -> transit_realtime::FeedMessage {
     use transpo_rt::transit_realtime::*;
     FeedMessage {
@@ -9,45 +32,93 @@ fn create_mock_feed_message() -> transit_realtime::FeedMessage {
             incrementality: Some(0i32),
             timestamp: Some(1u64),
         },
-        entity: vec![FeedEntity {
-            id: "delay_on_city1".into(),
-            alert: Some(Alert {
-                informed_entity: vec![
-                    EntitySelector {
-                        route_id: Some("route_1".to_owned()),
-                        ..Default::default()
-                    },
-                    EntitySelector {
-                        stop_id: Some("stop_1".to_owned()),
-                        ..Default::default()
-                    },
-                ],
-                header_text: Some(TranslatedString {
-                    translation: vec![
-                        translated_string::Translation {
-                            text: "huge problem".to_owned(),
-                            language: None,
+        entity: vec![
+            FeedEntity {
+                id: "delay_on_city1".into(),
+                alert: Some(Alert {
+                    informed_entity: vec![
+                        EntitySelector {
+                            route_id: Some("route_1".to_owned()),
+                            ..Default::default()
                         },
-                        translated_string::Translation {
-                            text: "gros probleme".to_owned(),
-                            language: Some("fr".to_owned()),
+                        EntitySelector {
+                            stop_id: Some("stop_1".to_owned()),
+                            ..Default::default()
                         },
                     ],
+                    header_text: Some(TranslatedString {
+                        translation: vec![
+                            translated_string::Translation {
+                                text: "huge problem".to_owned(),
+                                language: None,
+                            },
+                            translated_string::Translation {
+                                text: "gros probleme".to_owned(),
+                                language: Some("fr".to_owned()),
+                            },
+                        ],
+                    }),
+                    description_text: Some(TranslatedString {
+                        translation: vec![translated_string::Translation {
+                            text: "huge problem on the route 1 and stop 1".to_owned(),
+                            language: None,
+                        }],
+                    }),
+                    active_period: vec![TimeRange {
+                        start: Some(utils::to_timestamp("2018-12-15T08:00:00-08:00") as u64),
+                        end: Some(utils::to_timestamp("2018-12-15T12:00:00-08:00") as u64),
+                    }],
+                    ..Default::default()
                 }),
-                description_text: Some(TranslatedString {
-                    translation: vec![translated_string::Translation {
-                        text: "huge problem on the route 1 and stop 1".to_owned(),
-                        language: None,
+                ..Default::default()
+            },
+            // alert on a trip of the line CITY
+            FeedEntity {
+                id: "detour_on_city".into(),
+                alert: Some(Alert {
+                    effect: Some(alert::Effect::Detour as i32),
+                    informed_entity: vec![EntitySelector {
+                        trip: Some(TripDescriptor {
+                            trip_id: Some("CITY1".to_owned()),
+                            ..Default::default()
+                        }),
+                        ..Default::default()
                     }],
+                    header_text: Some(make_translated_string(&[
+                        ("detour", Some("en")),
+                        ("deviation", Some("fr")),
+                    ])),
+                    active_period: active_period(),
+                    ..Default::default()
                 }),
-                active_period: vec![TimeRange {
-                    start: Some(utils::to_timestamp("2018-12-15T08:00:00-08:00") as u64),
-                    end: Some(utils::to_timestamp("2018-12-15T12:00:00-08:00") as u64),
-                }],
                 ..Default::default()
-            }),
-            ..Default::default()
-        }],
+            },
+            // alert on the stop area of EMSI
+            FeedEntity {
+                id: "emsi_closed".into(),
+                alert: Some(Alert {
+                    informed_entity: vec![EntitySelector {
+                        stop_id: Some("Navitia:EMSI".to_owned()),
+                        ..Default::default()
+                    }],
+                    header_text: Some(make_translated_string(&[("stop closed", None)])),
+                    active_period: active_period(),
+                    ..Default::default()
+                }),
+                ..Default::default()
+            },
+            // deleted alert, it is only given as a cancellation
+            FeedEntity {
+                id: "deleted_alert".into(),
+                is_deleted: Some(true),
+                alert: Some(Alert {
+                    header_text: Some(make_translated_string(&[("old problem", None)])),
+                    active_period: active_period(),
+                    ..Default::default()
+                }),
+                ..Default::default()
+            },
+        ],
     }
 }
 
@@ -61,6 +132,25 @@ async fn general_message_integration_test() {
 
     call_in_activity_period(&mut srv).await;
     call_not_in_activity_period(&mut srv).await;
+    filtered_calls(&mut srv).await;
+    cancellations(&mut srv).await;
+}
+
+async fn get_message_ids(srv: &mut actix_web::test::TestServer, filters: &str) -> Vec<String> {
+    let resp: serde_json::Value = utils::get_json(
+        srv,
+        &format!(
+            "/default/siri/2.0/general-message.json?RequestTimestamp=2018-12-15T10:00:00{}",
+            filters
+        ),
+    )
+    .await;
+    resp.pointer("/Siri/ServiceDelivery/GeneralMessageDelivery/0/InfoMessages")
+        .and_then(|m| m.as_array())
+        .unwrap()
+        .iter()
+        .map(|m| m["InfoMessageIdentifier"].as_str().unwrap().to_owned())
+        .collect()
 }
 
 async fn call_in_activity_period(srv: &mut actix_web::test::TestServer) {
@@ -70,12 +160,20 @@ async fn call_in_activity_period(srv: &mut actix_web::test::TestServer) {
     )
     .await;
 
-    let messages = resp.pointer("/Siri/ServiceDelivery/GeneralMessageDelivery/0/InfoMessages");
+    let messages = resp
+        .pointer("/Siri/ServiceDelivery/GeneralMessageDelivery/0/InfoMessages")
+        .and_then(|m| m.as_array())
+        .unwrap();
+    assert_eq!(messages.len(), 3);
 
+    // the identifiers are the ids of the gtfs-rt entities
     assert_eq!(
-        messages,
-        Some(&serde_json::json! ([
+        messages[0],
+        serde_json::json! (
           {
+            "ItemIdentifier": "delay_on_city1",
+            "InfoMessageIdentifier": "delay_on_city1",
+            "InfoChannelRef": "Information",
             "Content": {
               "LineRef": [
                 "route_1"
@@ -105,10 +203,86 @@ async fn call_in_activity_period(srv: &mut actix_web::test::TestServer) {
                 }
               ]
             },
-            "ValidUntilTime": "2018-12-15T12:00:00"
+            "ValidUntilTime": "2018-12-15T12:00:00-08:00"
           }
-        ]))
+        )
+    );
+
+    // the line of the trip is given
+    assert_eq!(messages[1]["InfoMessageIdentifier"], "detour_on_city");
+    assert_eq!(messages[1]["InfoChannelRef"], "Perturbation");
+    assert_eq!(
+        messages[1]["Content"]["LineRef"],
+        serde_json::json!(["CITY"])
+    );
+}
+
+async fn filtered_calls(srv: &mut actix_web::test::TestServer) {
+    assert_eq!(
+        get_message_ids(srv, "&LineRef=CITY").await,
+        vec!["detour_on_city"]
     );
+    // the messages on the stop area of the stop point are given
+    assert_eq!(
+        get_message_ids(srv, "&StopPointRef=EMSI").await,
+        vec!["emsi_closed"]
+    );
+    assert!(get_message_ids(srv, "&StopPointRef=AMV").await.is_empty());
+    assert_eq!(
+        get_message_ids(srv, "&InfoChannelRef=Perturbation").await,
+        vec!["detour_on_city"]
+    );
+    assert_eq!(
+        get_message_ids(srv, "&InfoChannelRef=Information").await,
+        vec!["delay_on_city1", "emsi_closed"]
+    );
+
+    // only the messages in the requested language are given
+    // (and the ones without language)
+    let resp: serde_json::Value = utils::get_json(
+        srv,
+        "/default/siri/2.0/general-message.json?RequestTimestamp=2018-12-15T10:00:00&LineRef=CITY&Language=fr",
+    )
+    .await;
+    assert_eq!(
+        resp.pointer(
+            "/Siri/ServiceDelivery/GeneralMessageDelivery/0/InfoMessages/0/Content/Message"
+        ),
+        Some(&serde_json::json!([{
+            "MessageType": "shortMessage",
+            "MessageText": {
+                "Lang": "fr",
+                "Value": "deviation"
+            }
+        }]))
+    );
+    assert!(get_message_ids(srv, "&LineRef=CITY&Language=de")
+        .await
+        .is_empty());
+
+    let (status, resp): (_, serde_json::Value) = utils::get_json_with_status(
+        srv,
+        "/default/siri/2.0/general-message.json?LineRef=unknown_line",
+    )
+    .await;
+    assert_eq!(status, actix_web::http::StatusCode::NOT_FOUND);
+    assert_eq!(
+        resp.pointer("/Siri/ServiceDelivery/GeneralMessageDelivery/0/ErrorCondition/InvalidDataReferencesError/ErrorText"),
+        Some(&serde_json::json!("impossible to find line: 'unknown_line'"))
+    );
+}
+
+// the deleted alerts are given as cancellations
+async fn cancellations(srv: &mut actix_web::test::TestServer) {
+    let resp: serde_json::Value =
+        utils::get_json(srv, "/default/siri/2.0/general-message.json").await;
+    let cancellations = resp
+        .pointer("/Siri/ServiceDelivery/GeneralMessageDelivery/0/InfoMessagesCancellation")
+        .and_then(|m| m.as_array())
+        .unwrap();
+    assert_eq!(cancellations.len(), 1);
+    assert_eq!(cancellations[0]["ItemIdentifier"], "deleted_alert");
+    assert_eq!(cancellations[0]["InfoMessageIdentifier"], "deleted_alert");
 }
 
 async fn call_not_in_activity_period(srv: &mut actix_web::test::TestServer) {
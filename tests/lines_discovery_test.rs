@@ -0,0 +1,109 @@
+use std::collections::BTreeSet;
+use transpo_rt::siri_lite::SiriResponse;
+
+mod utils;
+
+#[actix_rt::test]
+async fn lines_discovery_integration_test() {
+    let _log_guard = utils::init_log();
+    let mut srv = utils::make_simple_test_server().await;
+
+    filter_query(&mut srv).await;
+    limit_query(&mut srv).await;
+    xml_query(&mut srv).await;
+}
+
+async fn filter_query(srv: &mut actix_web::test::TestServer) {
+    let resp: SiriResponse =
+        utils::get_json(srv, "/default/siri/2.0/lines-discovery.json?q=bullfrog").await;
+    let ld = resp.siri.lines_delivery.unwrap();
+    assert_eq!(ld.common.version, "2.0");
+    assert_eq!(ld.common.status, Some(true));
+    assert_eq!(
+        ld.annotated_line_ref
+            .iter()
+            .map(|l| l.line_ref.as_str())
+            .collect::<BTreeSet<_>>(),
+        vec!["AB", "BFC"].into_iter().collect::<BTreeSet<_>>()
+    );
+
+    let line = ld
+        .annotated_line_ref
+        .iter()
+        .find(|l| l.line_ref == "AB")
+        .unwrap();
+    assert_eq!(line.line_name, "Airport - Bullfrog");
+    assert_eq!(line.line_code, Some("10".to_owned()));
+    assert_eq!(line.vehicle_mode, vec!["bus".to_owned()]);
+    assert_eq!(line.operator_ref, Some("DTA".to_owned()));
+    assert_eq!(
+        line.destinations
+            .iter()
+            .map(|d| (d.destination_ref.as_str(), d.place_name.as_str()))
+            .collect::<BTreeSet<_>>(),
+        vec![
+            ("BEATTY_AIRPORT", "Nye County Airport (Demo)"),
+            ("BULLFROG", "Bullfrog (Demo)")
+        ]
+        .into_iter()
+        .collect::<BTreeSet<_>>()
+    );
+    assert_eq!(
+        line.stop_points
+            .iter()
+            .map(|s| s.stop_point_ref.as_str())
+            .collect::<BTreeSet<_>>(),
+        vec!["BEATTY_AIRPORT", "BULLFROG"]
+            .into_iter()
+            .collect::<BTreeSet<_>>()
+    );
+
+    // the line's code can also be searched
+    let resp: SiriResponse =
+        utils::get_json(srv, "/default/siri/2.0/lines-discovery.json?q=40").await;
+    let ld = resp.siri.lines_delivery.unwrap();
+    assert_eq!(ld.annotated_line_ref.len(), 1);
+    assert_eq!(ld.annotated_line_ref[0].line_ref, "CITY");
+}
+
+async fn limit_query(srv: &mut actix_web::test::TestServer) {
+    let resp: SiriResponse = utils::get_json(srv, "/default/siri/2.0/lines-discovery.json").await;
+    let all_lines: Vec<_> = resp
+        .siri
+        .lines_delivery
+        .unwrap()
+        .annotated_line_ref
+        .into_iter()
+        .map(|l| l.line_ref)
+        .collect();
+    assert_eq!(all_lines.len(), 5);
+
+    let resp: SiriResponse = utils::get_json(
+        srv,
+        "/default/siri/2.0/lines-discovery.json?limit=2&offset=1",
+    )
+    .await;
+    let lines: Vec<_> = resp
+        .siri
+        .lines_delivery
+        .unwrap()
+        .annotated_line_ref
+        .into_iter()
+        .map(|l| l.line_ref)
+        .collect();
+    assert_eq!(lines, all_lines[1..3].to_vec());
+}
+
+async fn xml_query(srv: &mut actix_web::test::TestServer) {
+    let mut response = srv
+        .get("/default/siri/2.0/lines-discovery.xml?q=city")
+        .send()
+        .await
+        .unwrap();
+    assert!(response.status().is_success());
+    let body = String::from_utf8(response.body().await.unwrap().to_vec()).unwrap();
+    assert!(body.contains("<LinesDelivery version=\"2.0\">"));
+    assert!(body.contains(
+        "<AnnotatedLineRef><LineRef>CITY</LineRef><LineName>City</LineName><Monitored>true</Monitored>"
+    ));
+}
@@ -0,0 +1,139 @@
+mod utils;
+use actix_web::http::{header, StatusCode};
+use serde_json::Value;
+use transpo_rt::datasets::DatasetInfo;
+use utils::get_json;
+
+fn dataset(id: &str, gtfs: &str, gtfs_rt_url: &str) -> DatasetInfo {
+    DatasetInfo {
+        id: id.to_owned(),
+        name: id.to_owned(),
+        ..DatasetInfo::new_default(gtfs, &[gtfs_rt_url.to_owned()])
+    }
+}
+
+async fn statuses(srv: &mut actix_web::test::TestServer) -> Vec<(String, String)> {
+    let resp: Value = get_json(srv, "/").await;
+    resp["datasets"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|d| {
+            (
+                d["id"].as_str().unwrap().to_owned(),
+                d["status"].as_str().unwrap().to_owned(),
+            )
+        })
+        .collect()
+}
+
+#[actix_rt::test]
+async fn loading_dataset_test() {
+    let _log_guard = utils::init_log();
+    // this server never answers, so the base schedule of the 'slow' dataset is never downloaded
+    // and the gtfs_rt of the 'fast' dataset is never fetched
+    let hanging_server = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+    let hanging_url = format!("http://{}", hanging_server.local_addr().unwrap());
+
+    let registry = utils::start_test_actors(vec![
+        dataset(
+            "fast",
+            "fixtures/gtfs.zip",
+            &format!("{}/gtfs_rt", hanging_url),
+        ),
+        dataset(
+            "slow",
+            &format!("{}/gtfs.zip", hanging_url),
+            &(mockito::server_url() + "/gtfs_rt"),
+        ),
+    ]);
+    // the server is started before the datasets are loaded
+    let mut srv = utils::start_test_server(registry, None);
+
+    // a dataset is ready as soon as its base schedule is loaded, without waiting for its gtfs_rt
+    let expected = vec![
+        ("fast".to_owned(), "ready".to_owned()),
+        ("slow".to_owned(), "loading".to_owned()),
+    ];
+    for _ in 0..50 {
+        if statuses(&mut srv).await == expected {
+            break;
+        }
+        actix_rt::time::delay_for(std::time::Duration::from_millis(100)).await;
+    }
+    assert_eq!(statuses(&mut srv).await, expected);
+
+    assert_eq!(utils::get_status(&mut srv, "/fast/").await, StatusCode::OK);
+    assert_eq!(
+        utils::get_status(
+            &mut srv,
+            "/fast/siri/2.0/stop-monitoring.json?MonitoringRef=EMSI"
+        )
+        .await,
+        StatusCode::OK
+    );
+
+    let mut response = srv.get("/slow/").send().await.unwrap();
+    assert_eq!(response.status(), StatusCode::SERVICE_UNAVAILABLE);
+    assert_eq!(response.headers().get(header::RETRY_AFTER).unwrap(), "30");
+    let body: Value = response.json().await.unwrap();
+    assert_eq!(body["id"], "slow");
+    assert_eq!(body["status"], "loading");
+
+    // on the siri routes, the error is a siri error
+    let mut response = srv
+        .get("/slow/siri/2.0/stop-monitoring.json?MonitoringRef=EMSI")
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::SERVICE_UNAVAILABLE);
+    assert_eq!(response.headers().get(header::RETRY_AFTER).unwrap(), "30");
+    let body: Value = response.json().await.unwrap();
+    assert_eq!(
+        body.pointer(
+            "/Siri/ServiceDelivery/StopMonitoringDelivery/0/ErrorCondition/ServiceNotAvailableError/ErrorText"
+        ),
+        Some(&Value::from("dataset 'slow' is loading"))
+    );
+}
+
+#[actix_rt::test]
+async fn unknown_dataset_test() {
+    let _log_guard = utils::init_log();
+    let mut srv = utils::make_test_server(vec![dataset(
+        "default",
+        "fixtures/gtfs.zip",
+        &(mockito::server_url() + "/gtfs_rt"),
+    )])
+    .await;
+
+    assert_eq!(
+        utils::get_status(&mut srv, "/unknown/").await,
+        StatusCode::NOT_FOUND
+    );
+
+    let mut response = srv
+        .get("/unknown/siri/2.0/general-message.json")
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+    let body: Value = response.json().await.unwrap();
+    assert_eq!(
+        body.pointer(
+            "/Siri/ServiceDelivery/GeneralMessageDelivery/0/ErrorCondition/InvalidDataReferencesError/ErrorText"
+        ),
+        Some(&Value::from("impossible to find dataset 'unknown'"))
+    );
+
+    let response = srv
+        .get("/unknown/siri/2.0/stop-monitoring.xml?MonitoringRef=EMSI")
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+    assert_eq!(
+        response.headers().get(header::CONTENT_TYPE).unwrap(),
+        "application/xml"
+    );
+}
@@ -0,0 +1,108 @@
+use transpo_rt::datasets::{BaseScheduleSource, DatasetInfo};
+use transpo_rt::siri_lite::{DateTime, SiriResponse};
+use transpo_rt::transit_realtime;
+mod utils;
+
+fn string(time: &Option<DateTime>) -> Option<String> {
+    time.as_ref().map(|t| t.to_string())
+}
+
+fn create_mock_feed_message() -> transit_realtime::FeedMessage {
+    use transpo_rt::transit_realtime::*;
+    FeedMessage {
+        header: FeedHeader {
+            gtfs_realtime_version: "2.0".into(),
+            incrementality: Some(0i32),
+            timestamp: Some(1u64),
+        },
+        // the GTFS-RT gives the ids of the GTFS, without the prefix
+        entity: vec![FeedEntity {
+            id: "delay_on_stba".into(),
+            trip_update: Some(TripUpdate {
+                trip: TripDescriptor {
+                    trip_id: Some("STBA".into()),
+                    start_date: Some("20181215".into()),
+                    ..Default::default()
+                },
+                stop_time_update: vec![utils::make_stu(
+                    "BEATTY_AIRPORT",
+                    2,
+                    Some("2018-12-15T06:26:30-08:00"),
+                    Some("2018-12-15T06:28:31-08:00"),
+                )],
+                ..Default::default()
+            }),
+            ..Default::default()
+        }],
+    }
+}
+
+fn source(prefix: &str) -> BaseScheduleSource {
+    BaseScheduleSource {
+        path: "fixtures/gtfs.zip".to_owned(),
+        prefix: Some(prefix.to_owned()),
+    }
+}
+
+#[actix_rt::test]
+async fn multiple_gtfs_integration_test() {
+    let _log_guard = utils::init_log();
+    let _server = utils::run_simple_gtfs_rt_server(create_mock_feed_message());
+    let default_dataset = DatasetInfo::new_default("", &[mockito::server_url() + "/gtfs_rt"]);
+    let prefixed_dataset = DatasetInfo {
+        id: "prefixed".into(),
+        gtfs_sources: vec![source("op1")],
+        ..default_dataset.clone()
+    };
+    let merged_dataset = DatasetInfo {
+        id: "merged".into(),
+        gtfs_sources: vec![source("op1"), source("op2")],
+        ..default_dataset
+    };
+    let mut srv = utils::make_test_server(vec![prefixed_dataset, merged_dataset]).await;
+
+    prefixed_trip_update(&mut srv).await;
+    merged_sources(&mut srv).await;
+}
+
+// the trips of the GTFS-RT are found with the prefixed ids
+async fn prefixed_trip_update(srv: &mut actix_web::test::TestServer) {
+    let resp: SiriResponse = utils::get_json(
+        srv,
+        "/prefixed/siri/2.0/stop-monitoring.json?MonitoringRef=op1:BEATTY_AIRPORT\
+&StartTime=2018-12-15T05:22:00",
+    )
+    .await;
+    let spd = resp.siri.service_delivery.unwrap();
+    let sm = spd.stop_monitoring_delivery.iter().next().unwrap();
+
+    let first_passage = &sm.monitored_stop_visit[0];
+    assert_eq!(first_passage.monitoring_ref, "op1:BEATTY_AIRPORT");
+    let vj = &first_passage.monitored_vehicle_journey;
+    assert_eq!(vj.line_ref, "op1:STBA");
+    assert_eq!(
+        vj.framed_vehicle_journey_ref
+            .as_ref()
+            .unwrap()
+            .dated_vehicle_journey_ref,
+        "op1:STBA"
+    );
+    let first_passage = &vj.monitored_call.as_ref().unwrap();
+    assert_eq!(
+        string(&first_passage.expected_arrival_time),
+        Some("2018-12-15T06:26:30-08:00".to_owned())
+    );
+}
+
+// the lines of all the sources are in the dataset
+async fn merged_sources(srv: &mut actix_web::test::TestServer) {
+    let resp: SiriResponse = utils::get_json(srv, "/merged/siri/2.0/lines-discovery.json").await;
+    let ld = resp.siri.lines_delivery.unwrap();
+    let lines: Vec<&str> = ld
+        .annotated_line_ref
+        .iter()
+        .map(|l| l.line_ref.as_str())
+        .collect();
+    assert!(lines.contains(&"op1:CITY"), "{:?}", lines);
+    assert!(lines.contains(&"op2:CITY"), "{:?}", lines);
+}
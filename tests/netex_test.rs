@@ -0,0 +1,33 @@
+use actix_web::http::StatusCode;
+use std::collections::BTreeSet;
+use transpo_rt::datasets::{BaseScheduleFormat, DatasetInfo};
+use transpo_rt::siri_lite::SiriResponse;
+
+mod utils;
+
+#[actix_rt::test]
+async fn netex_dataset_test() {
+    let _log_guard = utils::init_log();
+    let mut srv = utils::make_test_server(vec![DatasetInfo {
+        id: "netex".into(),
+        base_schedule_format: BaseScheduleFormat::Netex,
+        // no gtfs-rt is served, the base schedule is enough for the dataset to be ready
+        ..DatasetInfo::new_default("fixtures/netex.zip", &["http://localhost:1/gtfs_rt".into()])
+    }])
+    .await;
+
+    assert_eq!(utils::get_status(&mut srv, "/netex").await, StatusCode::OK);
+
+    let resp: SiriResponse =
+        utils::get_json(&mut srv, "/netex/siri/2.0/stoppoints-discovery.json").await;
+    let spd = resp.siri.stop_points_delivery.unwrap();
+    let names: BTreeSet<_> = spd
+        .annotated_stop_point
+        .iter()
+        .map(|s| s.stop_name.as_str())
+        .collect();
+    assert_eq!(
+        names,
+        vec!["Stop A", "Stop B", "Stop C"].into_iter().collect()
+    );
+}
@@ -21,9 +21,15 @@ async fn openapi_test() {
             "/{dataset}",
             "/{dataset}/gtfs-rt",
             "/{dataset}/gtfs-rt.json",
+            "/{dataset}/siri/2.0/check-status.json",
             "/{dataset}/siri/2.0/general-message.json",
+            "/{dataset}/siri/2.0/lines-discovery.json",
+            "/{dataset}/siri/2.0/production-timetable.json",
+            "/{dataset}/siri/2.0/situation-exchange.json",
             "/{dataset}/siri/2.0/stop-monitoring.json",
+            "/{dataset}/siri/2.0/stop-timetable.json",
             "/{dataset}/siri/2.0/stoppoints-discovery.json",
+            "/{dataset}/trips/{vehicle_journey_id}",
         }
     );
 
@@ -40,11 +46,38 @@ async fn openapi_test() {
     assert_eq!(nb_params("/{dataset}"), 1);
     assert_eq!(nb_params("/{dataset}/gtfs-rt"), 1);
     assert_eq!(nb_params("/{dataset}/gtfs-rt.json"), 1);
-    assert_eq!(nb_params("/{dataset}/siri/2.0/general-message.json"), 2);
-    assert_eq!(nb_params("/{dataset}/siri/2.0/stop-monitoring.json"), 7);
+    assert_eq!(nb_params("/{dataset}/siri/2.0/check-status.json"), 2);
+    assert_eq!(nb_params("/{dataset}/siri/2.0/general-message.json"), 8);
+    assert_eq!(nb_params("/{dataset}/trips/{vehicle_journey_id}"), 3);
+    assert_eq!(nb_params("/{dataset}/siri/2.0/lines-discovery.json"), 6);
+    assert_eq!(nb_params("/{dataset}/siri/2.0/situation-exchange.json"), 4);
+    assert_eq!(nb_params("/{dataset}/siri/2.0/stop-monitoring.json"), 9);
+    assert_eq!(
+        nb_params("/{dataset}/siri/2.0/production-timetable.json"),
+        6
+    );
+    assert_eq!(nb_params("/{dataset}/siri/2.0/stop-timetable.json"), 7);
     assert_eq!(
         nb_params("/{dataset}/siri/2.0/stoppoints-discovery.json"),
-        8
+        16
+    );
+
+    // the siri errors are documented
+    let response_codes = |route: &str| {
+        let route = route.replace("/", "~1");
+        let pointer = format!("/paths/{}/get/responses", route);
+        resp.pointer(&pointer)
+            .and_then(|r| r.as_object())
+            .map(|r| r.keys().map(|k| k.to_owned()).collect::<BTreeSet<_>>())
+            .unwrap_or_default()
+    };
+    assert_eq!(
+        response_codes("/{dataset}/siri/2.0/stop-monitoring.json"),
+        btreeset! {"200".to_owned(), "400".to_owned(), "404".to_owned(), "502".to_owned()}
+    );
+    assert_eq!(
+        response_codes("/{dataset}/siri/2.0/general-message.json"),
+        btreeset! {"200".to_owned(), "400".to_owned(), "404".to_owned(), "502".to_owned()}
     );
 
     // we don't check all the responses, just that there is at least the definition of SiriResponse
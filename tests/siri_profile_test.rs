@@ -0,0 +1,164 @@
+use transpo_rt::datasets::DatasetInfo;
+use transpo_rt::siri_lite::profile::SiriProfile;
+use transpo_rt::siri_lite::SiriResponse;
+use transpo_rt::transit_realtime;
+mod utils;
+
+fn create_mock_feed_message() -> transit_realtime::FeedMessage {
+    use transpo_rt::transit_realtime::*;
+    FeedMessage {
+        header: FeedHeader {
+            gtfs_realtime_version: "2.0".into(),
+            incrementality: Some(0i32),
+            timestamp: Some(1u64),
+        },
+        entity: vec![FeedEntity {
+            id: "detour_on_city".into(),
+            alert: Some(Alert {
+                informed_entity: vec![
+                    EntitySelector {
+                        route_id: Some("CITY".to_owned()),
+                        ..Default::default()
+                    },
+                    EntitySelector {
+                        stop_id: Some("EMSI".to_owned()),
+                        ..Default::default()
+                    },
+                ],
+                header_text: Some(TranslatedString {
+                    translation: vec![translated_string::Translation {
+                        text: "detour".to_owned(),
+                        language: None,
+                    }],
+                }),
+                active_period: vec![TimeRange {
+                    start: Some(utils::to_timestamp("2018-12-15T08:00:00-08:00") as u64),
+                    end: Some(utils::to_timestamp("2018-12-15T12:00:00-08:00") as u64),
+                }],
+                ..Default::default()
+            }),
+            ..Default::default()
+        }],
+    }
+}
+
+const QUERIES: [&str; 5] = [
+    "siri/2.0/stop-monitoring.json?MonitoringRef=EMSI&StartTime=2018-12-15T05:22:00",
+    "siri/2.0/general-message.json?RequestTimestamp=2018-12-15T09:00:00",
+    "siri/2.0/situation-exchange.json?RequestTimestamp=2018-12-15T09:00:00",
+    "siri/2.0/stoppoints-discovery.json?q=emsi",
+    "siri/2.0/lines-discovery.json?q=city",
+];
+
+#[actix_rt::test]
+async fn siri_profile_integration_test() {
+    let _log_guard = utils::init_log();
+    let _server = utils::run_simple_gtfs_rt_server(create_mock_feed_message());
+    let generic_dataset =
+        DatasetInfo::new_default("fixtures/gtfs.zip", &[mockito::server_url() + "/gtfs_rt"]);
+    let idf_dataset = DatasetInfo {
+        id: "idf".into(),
+        siri_profile: SiriProfile::Idf,
+        ..generic_dataset.clone()
+    };
+    let mut srv = utils::make_test_server(vec![generic_dataset, idf_dataset]).await;
+
+    // the responses are valid for the profile of the dataset
+    for (dataset, profile) in &[("default", SiriProfile::Generic), ("idf", SiriProfile::Idf)] {
+        for query in QUERIES.iter() {
+            let resp: SiriResponse =
+                utils::get_json(&mut srv, &format!("/{}/{}", dataset, query)).await;
+            assert_eq!(profile.check(&resp), Vec::<String>::new(), "{}", query);
+        }
+    }
+    // and for the profile asked in the request
+    for (requested, profile) in &[("generic", SiriProfile::Generic), ("idf", SiriProfile::Idf)] {
+        for query in QUERIES.iter() {
+            let url = format!("/default/{}&profile={}", query, requested);
+            let resp: SiriResponse = utils::get_json(&mut srv, &url).await;
+            assert_eq!(profile.check(&resp), Vec::<String>::new(), "{}", url);
+        }
+    }
+
+    idf_stop_monitoring(&mut srv).await;
+    idf_general_message(&mut srv).await;
+    invalid_profile(&mut srv).await;
+}
+
+async fn idf_stop_monitoring(srv: &mut actix_web::test::TestServer) {
+    // the IDF refs are accepted in the request
+    let resp: serde_json::Value = utils::get_json(
+        srv,
+        "/idf/siri/2.0/stop-monitoring.json?MonitoringRef=STIF:StopPoint:Q:EMSI:\
+&LineRef=STIF:Line::CITY:&StartTime=2018-12-15T05:22:00",
+    )
+    .await;
+    let delivery = &resp["Siri"]["ServiceDelivery"];
+    assert_eq!(delivery["ProducerRef"], "idf");
+    let identifier = delivery["ResponseMessageIdentifier"].as_str().unwrap();
+    assert!(
+        identifier.starts_with("idf:ResponseMessage::"),
+        "{}",
+        identifier
+    );
+    assert!(identifier.ends_with(":LOC"), "{}", identifier);
+
+    let visit = &delivery["StopMonitoringDelivery"][0]["MonitoredStopVisit"][0];
+    assert_eq!(visit["MonitoringRef"], "STIF:StopPoint:Q:EMSI:");
+    assert!(visit["_links"].is_null());
+    let vj = &visit["MonitoredVehicleJourney"];
+    assert_eq!(vj["LineRef"], "STIF:Line::CITY:");
+    assert_eq!(vj["OperatorRef"], "idf:Operator::DTA:LOC");
+    assert_eq!(
+        vj["FramedVehicleJourneyRef"]["DatedVehicleJourneyRef"],
+        "idf:VehicleJourney::CITY1:LOC"
+    );
+    assert_eq!(vj["MonitoredCall"]["VehicleAtStop"], false);
+
+    // the generic profile keeps the ids of the dataset
+    let resp: serde_json::Value = utils::get_json(
+        srv,
+        "/idf/siri/2.0/stop-monitoring.json?MonitoringRef=EMSI\
+&StartTime=2018-12-15T05:22:00&profile=generic",
+    )
+    .await;
+    let visit =
+        &resp["Siri"]["ServiceDelivery"]["StopMonitoringDelivery"][0]["MonitoredStopVisit"][0];
+    assert_eq!(visit["MonitoringRef"], "EMSI");
+    assert_eq!(visit["MonitoredVehicleJourney"]["LineRef"], "CITY");
+    assert!(visit["MonitoredVehicleJourney"]["MonitoredCall"]["VehicleAtStop"].is_null());
+}
+
+async fn idf_general_message(srv: &mut actix_web::test::TestServer) {
+    let resp: serde_json::Value = utils::get_json(
+        srv,
+        "/idf/siri/2.0/general-message.json?RequestTimestamp=2018-12-15T09:00:00",
+    )
+    .await;
+    let message = &resp["Siri"]["ServiceDelivery"]["GeneralMessageDelivery"][0]["InfoMessages"][0];
+    assert_eq!(message["Format"], "STIF-IDF");
+    assert!(message["RecordedAtTime"].is_string());
+    assert_eq!(message["Content"]["LineRef"][0], "STIF:Line::CITY:");
+    assert_eq!(
+        message["Content"]["StopPointRef"][0],
+        "STIF:StopPoint:Q:EMSI:"
+    );
+}
+
+async fn invalid_profile(srv: &mut actix_web::test::TestServer) {
+    let (status, resp): (_, serde_json::Value) = utils::get_json_with_status(
+        srv,
+        "/default/siri/2.0/stop-monitoring.json?MonitoringRef=EMSI&profile=unknown",
+    )
+    .await;
+    assert_eq!(status, actix_web::http::StatusCode::BAD_REQUEST);
+    // the error is given as a siri error
+    let delivery = resp
+        .pointer("/Siri/ServiceDelivery/StopMonitoringDelivery/0")
+        .unwrap();
+    assert_eq!(delivery["Status"], false);
+    assert!(delivery["ErrorCondition"]["OtherError"]["ErrorText"]
+        .as_str()
+        .unwrap()
+        .starts_with("invalid profile"));
+}
@@ -0,0 +1,123 @@
+mod utils;
+
+async fn post_soap(srv: &mut actix_web::test::TestServer, body: &str) -> (u16, String) {
+    let mut response = srv
+        .post("/default/siri/2.0/soap")
+        .header("Content-Type", "text/xml; charset=utf-8")
+        .send_body(body.to_owned())
+        .await
+        .unwrap();
+    let body = response.body().await.unwrap();
+    (
+        response.status().as_u16(),
+        String::from_utf8(body.to_vec()).unwrap(),
+    )
+}
+
+#[actix_rt::test]
+async fn siri_soap_integration_test() {
+    let _log_guard = utils::init_log();
+    let mut srv = utils::make_simple_test_server().await;
+
+    get_stop_monitoring(&mut srv).await;
+    stop_points_discovery(&mut srv).await;
+    invalid_soap_request(&mut srv).await;
+}
+
+async fn get_stop_monitoring(srv: &mut actix_web::test::TestServer) {
+    let (status, body) = post_soap(
+        srv,
+        r#"<?xml version="1.0" encoding="UTF-8"?>
+<S:Envelope xmlns:S="http://schemas.xmlsoap.org/soap/envelope/"
+            xmlns:sw="http://wsdl.siri.org.uk"
+            xmlns:siri="http://www.siri.org.uk/siri">
+  <S:Body>
+    <sw:GetStopMonitoring>
+      <ServiceRequestInfo>
+        <siri:RequestTimestamp>2018-12-15T05:22:00</siri:RequestTimestamp>
+        <siri:RequestorRef>aggregator</siri:RequestorRef>
+        <siri:MessageIdentifier>msg:42</siri:MessageIdentifier>
+      </ServiceRequestInfo>
+      <Request version="2.0">
+        <siri:MonitoringRef>EMSI</siri:MonitoringRef>
+        <siri:StartTime>2018-12-15T05:22:00</siri:StartTime>
+        <siri:DataFreshness>Scheduled</siri:DataFreshness>
+        <siri:MaximumStopVisits>1</siri:MaximumStopVisits>
+      </Request>
+    </sw:GetStopMonitoring>
+  </S:Body>
+</S:Envelope>"#,
+    )
+    .await;
+
+    assert_eq!(status, 200);
+    assert!(body.contains("<sw:GetStopMonitoringResponse"));
+    assert!(body.contains("<siri:RequestMessageRef>msg:42</siri:RequestMessageRef>"));
+    assert!(body.contains("<Answer><siri:StopMonitoringDelivery version=\"2.0\">"));
+    assert!(body.contains("<siri:MonitoringRef>EMSI</siri:MonitoringRef>"));
+    assert!(
+        body.contains("<siri:AimedArrivalTime>2018-12-15T06:26:00-08:00</siri:AimedArrivalTime>")
+    );
+    assert_eq!(body.matches("<siri:MonitoredStopVisit>").count(), 1);
+
+    // a siri error is given in the answer
+    let (status, body) = post_soap(
+        srv,
+        r#"<S:Envelope xmlns:S="http://schemas.xmlsoap.org/soap/envelope/" xmlns:sw="http://wsdl.siri.org.uk" xmlns:siri="http://www.siri.org.uk/siri">
+  <S:Body>
+    <sw:GetStopMonitoring>
+      <Request version="2.0">
+        <siri:MessageIdentifier>msg:43</siri:MessageIdentifier>
+        <siri:MonitoringRef>unknown_stop</siri:MonitoringRef>
+      </Request>
+    </sw:GetStopMonitoring>
+  </S:Body>
+</S:Envelope>"#,
+    )
+    .await;
+    assert_eq!(status, 200);
+    assert!(body.contains("<siri:RequestMessageRef>msg:43</siri:RequestMessageRef>"));
+    assert!(body.contains(
+        "<siri:Status>false</siri:Status><siri:ErrorCondition><siri:InvalidDataReferencesError>"
+    ));
+}
+
+async fn stop_points_discovery(srv: &mut actix_web::test::TestServer) {
+    let (status, body) = post_soap(
+        srv,
+        r#"<S:Envelope xmlns:S="http://schemas.xmlsoap.org/soap/envelope/" xmlns:sw="http://wsdl.siri.org.uk" xmlns:siri="http://www.siri.org.uk/siri">
+  <S:Body>
+    <sw:StopPointsDiscovery>
+      <Request version="2.0">
+        <siri:RequestTimestamp>2018-12-15T05:22:00</siri:RequestTimestamp>
+        <siri:RequestorRef>aggregator</siri:RequestorRef>
+        <siri:BoundingBox>
+          <siri:UpperLeft>
+            <siri:Longitude>-116.8</siri:Longitude>
+            <siri:Latitude>36.91</siri:Latitude>
+          </siri:UpperLeft>
+          <siri:LowerRight>
+            <siri:Longitude>-116.7</siri:Longitude>
+            <siri:Latitude>36.9</siri:Latitude>
+          </siri:LowerRight>
+        </siri:BoundingBox>
+      </Request>
+    </sw:StopPointsDiscovery>
+  </S:Body>
+</S:Envelope>"#,
+    )
+    .await;
+
+    assert_eq!(status, 200);
+    assert!(body.contains("<sw:StopPointsDiscoveryResponse"));
+    // without MessageIdentifier, there is no RequestMessageRef
+    assert!(!body.contains("RequestMessageRef"));
+    assert!(body.contains("<siri:StopPointRef>EMSI</siri:StopPointRef>"));
+}
+
+async fn invalid_soap_request(srv: &mut actix_web::test::TestServer) {
+    let (status, body) = post_soap(srv, "this is not a soap request").await;
+
+    assert_eq!(status, 500);
+    assert!(body.contains("<S:Fault><faultcode>S:Client</faultcode>"));
+}
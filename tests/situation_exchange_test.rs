@@ -0,0 +1,192 @@
+use serde_json::json;
+use transpo_rt::transit_realtime;
+mod utils;
+
+fn create_mock_feed_message() -> transit_realtime::FeedMessage {
+    use transpo_rt::transit_realtime::*;
+    FeedMessage {
+        header: FeedHeader {
+            gtfs_realtime_version: "2.0".into(),
+            incrementality: Some(0i32),
+            timestamp: Some(1u64),
+        },
+        entity: vec![
+            FeedEntity {
+                id: "emsi_works".into(),
+                alert: Some(Alert {
+                    cause: Some(alert::Cause::Construction as i32),
+                    effect: Some(alert::Effect::NoService as i32),
+                    informed_entity: vec![
+                        EntitySelector {
+                            stop_id: Some("EMSI".to_owned()),
+                            ..Default::default()
+                        },
+                        EntitySelector {
+                            trip: Some(TripDescriptor {
+                                trip_id: Some("CITY1".to_owned()),
+                                ..Default::default()
+                            }),
+                            ..Default::default()
+                        },
+                    ],
+                    header_text: Some(TranslatedString {
+                        translation: vec![
+                            translated_string::Translation {
+                                text: "works at E Main St".to_owned(),
+                                language: Some("en".to_owned()),
+                            },
+                            translated_string::Translation {
+                                text: "travaux a E Main St".to_owned(),
+                                language: Some("fr".to_owned()),
+                            },
+                        ],
+                    }),
+                    description_text: Some(TranslatedString {
+                        translation: vec![translated_string::Translation {
+                            text: "the stop E Main St is not served".to_owned(),
+                            language: Some("en".to_owned()),
+                        }],
+                    }),
+                    active_period: vec![
+                        TimeRange {
+                            start: Some(utils::to_timestamp("2018-12-15T08:00:00-08:00") as u64),
+                            end: Some(utils::to_timestamp("2018-12-15T12:00:00-08:00") as u64),
+                        },
+                        TimeRange {
+                            start: Some(utils::to_timestamp("2018-12-15T14:00:00-08:00") as u64),
+                            end: None,
+                        },
+                    ],
+                    ..Default::default()
+                }),
+                ..Default::default()
+            },
+            // alert on the whole network, without active period
+            FeedEntity {
+                id: "strike".into(),
+                alert: Some(Alert {
+                    cause: Some(alert::Cause::Strike as i32),
+                    effect: Some(alert::Effect::ReducedService as i32),
+                    informed_entity: vec![EntitySelector {
+                        agency_id: Some("DTA".to_owned()),
+                        ..Default::default()
+                    }],
+                    header_text: Some(TranslatedString {
+                        translation: vec![translated_string::Translation {
+                            text: "strike".to_owned(),
+                            language: None,
+                        }],
+                    }),
+                    ..Default::default()
+                }),
+                ..Default::default()
+            },
+        ],
+    }
+}
+
+#[actix_rt::test]
+async fn situation_exchange_integration_test() {
+    let _log_guard = utils::init_log();
+    let gtfs_rt = create_mock_feed_message();
+    let _server = utils::run_simple_gtfs_rt_server(gtfs_rt);
+
+    let mut srv = utils::make_simple_test_server().await;
+
+    situations_query(&mut srv).await;
+    xml_query(&mut srv).await;
+}
+
+async fn get_situations(
+    srv: &mut actix_web::test::TestServer,
+    request_timestamp: &str,
+) -> Vec<serde_json::Value> {
+    let resp: serde_json::Value = utils::get_json(
+        srv,
+        &format!(
+            "/default/siri/2.0/situation-exchange.json?RequestTimestamp={}",
+            request_timestamp
+        ),
+    )
+    .await;
+    resp.pointer("/Siri/ServiceDelivery/SituationExchangeDelivery/0/Situations")
+        .and_then(|s| s.as_array())
+        .cloned()
+        .unwrap()
+}
+
+async fn situations_query(srv: &mut actix_web::test::TestServer) {
+    let situations = get_situations(srv, "2018-12-15T10:00:00").await;
+    assert_eq!(situations.len(), 2);
+
+    let works = &situations[0];
+    assert_eq!(works["SituationNumber"], "emsi_works");
+    assert_eq!(works["ParticipantRef"], "default");
+    assert_eq!(works["Source"], json!({"SourceType": "feed"}));
+    assert_eq!(works["Progress"], "open");
+    assert_eq!(
+        works["ValidityPeriod"],
+        json!([
+            {"StartTime": "2018-12-15T08:00:00-08:00", "EndTime": "2018-12-15T12:00:00-08:00"},
+            {"StartTime": "2018-12-15T14:00:00-08:00"}
+        ])
+    );
+    // one of the periods is open, so is the publication window
+    assert_eq!(
+        works["PublicationWindow"],
+        json!({"StartTime": "2018-12-15T08:00:00-08:00"})
+    );
+    assert_eq!(works["EquipmentReason"], "constructionWork");
+    assert_eq!(works["Severity"], "severe");
+    assert_eq!(
+        works["Summary"],
+        json!([
+            {"Lang": "en", "Value": "works at E Main St"},
+            {"Lang": "fr", "Value": "travaux a E Main St"}
+        ])
+    );
+    assert_eq!(
+        works["Description"],
+        json!([{"Lang": "en", "Value": "the stop E Main St is not served"}])
+    );
+    assert_eq!(
+        works["Affects"],
+        json!({
+            "AffectedNetwork": [{"NetworkRef": "DTA", "AffectedLine": [{"LineRef": "CITY"}]}],
+            "AffectedStopPoint": [
+                {"StopPointRef": "EMSI", "StopPointName": "E Main St / S Irving St (Demo)"}
+            ],
+            "AffectedVehicleJourney": [{"VehicleJourneyRef": "CITY1", "LineRef": "CITY"}]
+        })
+    );
+
+    let strike = &situations[1];
+    assert_eq!(strike["SituationNumber"], "strike");
+    assert_eq!(strike["PersonnelReason"], "industrialAction");
+    assert_eq!(strike["Severity"], "normal");
+    assert!(strike["ValidityPeriod"][0]["EndTime"].is_null());
+    assert_eq!(
+        strike["Affects"],
+        json!({"AffectedNetwork": [{"NetworkRef": "DTA", "AllLines": true}]})
+    );
+
+    // between the 2 periods of the works, only the strike is active
+    let situations = get_situations(srv, "2018-12-15T13:00:00").await;
+    assert_eq!(situations.len(), 1);
+    assert_eq!(situations[0]["SituationNumber"], "strike");
+}
+
+async fn xml_query(srv: &mut actix_web::test::TestServer) {
+    let mut response = srv
+        .get("/default/siri/2.0/situation-exchange.xml?RequestTimestamp=2018-12-15T10:00:00")
+        .send()
+        .await
+        .unwrap();
+    assert!(response.status().is_success());
+    let body = String::from_utf8(response.body().await.unwrap().to_vec()).unwrap();
+    assert!(body.contains("<SituationExchangeDelivery version=\"2.0\">"));
+    assert!(body.contains("<SituationNumber>emsi_works</SituationNumber><Source><SourceType>feed</SourceType></Source><Progress>open</Progress><ValidityPeriod><StartTime>2018-12-15T08:00:00-08:00</StartTime><EndTime>2018-12-15T12:00:00-08:00</EndTime></ValidityPeriod>"));
+    assert!(body.contains(
+        "<AffectedNetwork><NetworkRef>DTA</NetworkRef><AllLines></AllLines></AffectedNetwork>"
+    ));
+}
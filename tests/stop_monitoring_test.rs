@@ -34,12 +34,12 @@ MaximumStopVisits=3",
     let passage = &vj.monitored_call.as_ref().unwrap();
     assert_eq!(
         string(&passage.aimed_arrival_time),
-        Some("2018-12-15T06:26:00".into())
+        Some("2018-12-15T06:26:00-08:00".into())
     );
     assert!(passage.expected_arrival_time.is_none());
     assert_eq!(
         string(&passage.aimed_departure_time),
-        Some("2018-12-15T06:28:00".into())
+        Some("2018-12-15T06:28:00-08:00".into())
     );
     assert!(passage.expected_departure_time.is_none());
     assert_eq!(passage.order, 5);
@@ -49,6 +49,223 @@ MaximumStopVisits=3",
     // we chain some different tests
     test_interval_filtering(&mut srv).await;
     test_beatty_stop_call(&mut srv).await;
+    test_start_time_with_offset(&mut srv).await;
+    test_unknown_stop(&mut srv).await;
+    test_xml_response(&mut srv).await;
+    test_message_identifiers(&mut srv).await;
+}
+
+// each response has its own identifier, and the identifier of the request is echoed
+async fn test_message_identifiers(srv: &mut actix_web::test::TestServer) {
+    let query = "/default/siri/2.0/stop-monitoring.json?\
+MonitoringRef=EMSI&StartTime=2018-12-15T05:22:00&MessageIdentifier=msg:1";
+    let first: serde_json::Value = utils::get_json(srv, query).await;
+    let second: serde_json::Value = utils::get_json(srv, query).await;
+    let first = &first["Siri"]["ServiceDelivery"];
+    let second = &second["Siri"]["ServiceDelivery"];
+
+    // without configuration, the producer is the dataset
+    assert_eq!(first["ProducerRef"], "default");
+    assert_eq!(first["RequestMessageRef"], "msg:1");
+    assert_eq!(
+        first["StopMonitoringDelivery"][0]["RequestMessageRef"],
+        "msg:1"
+    );
+    let identifier = first["ResponseMessageIdentifier"].as_str().unwrap();
+    assert!(identifier.starts_with("default:ResponseMessage:"));
+    assert_ne!(second["ResponseMessageIdentifier"], identifier);
+
+    // the discovery deliveries also echo the identifier of the request
+    let lines: serde_json::Value = utils::get_json(
+        srv,
+        "/default/siri/2.0/lines-discovery.json?MessageIdentifier=msg:2",
+    )
+    .await;
+    assert_eq!(lines["Siri"]["LinesDelivery"]["RequestMessageRef"], "msg:2");
+}
+
+#[actix_rt::test]
+async fn configured_producer_ref_test() {
+    let _log_guard = utils::init_log();
+    let mut srv = utils::make_test_server(vec![transpo_rt::datasets::DatasetInfo {
+        siri_producer_ref: Some("TRANSPO".to_owned()),
+        ..transpo_rt::datasets::DatasetInfo::new_default(
+            "fixtures/gtfs.zip",
+            &[mockito::server_url() + "/gtfs_rt"],
+        )
+    }])
+    .await;
+
+    let resp: serde_json::Value = utils::get_json(
+        &mut srv,
+        "/default/siri/2.0/stop-monitoring.json?MonitoringRef=EMSI&StartTime=2018-12-15T05:22:00",
+    )
+    .await;
+    let delivery = &resp["Siri"]["ServiceDelivery"];
+    assert_eq!(delivery["ProducerRef"], "TRANSPO");
+    assert!(delivery["ResponseMessageIdentifier"]
+        .as_str()
+        .unwrap()
+        .starts_with("TRANSPO:ResponseMessage:"));
+    // no identifier has been given in the request
+    assert!(delivery["RequestMessageRef"].is_null());
+}
+
+// names of the child elements of a node, in order
+fn child_names<'a>(node: roxmltree::Node<'a, '_>) -> Vec<&'a str> {
+    node.children()
+        .filter(|n| n.is_element())
+        .map(|n| n.tag_name().name())
+        .collect()
+}
+
+fn child<'a, 'input>(node: roxmltree::Node<'a, 'input>, name: &str) -> roxmltree::Node<'a, 'input> {
+    node.children()
+        .find(|n| n.is_element() && n.tag_name().name() == name)
+        .unwrap_or_else(|| panic!("no {} in {}", name, node.tag_name().name()))
+}
+
+// the same response can be given in SIRI XML
+async fn test_xml_response(srv: &mut actix_web::test::TestServer) {
+    let query = "MonitoringRef=EMSI&StartTime=2018-12-15T05:22:00&DataFreshness=Scheduled";
+    let mut response = srv
+        .get(format!("/default/siri/2.0/stop-monitoring.xml?{}", query))
+        .send()
+        .await
+        .unwrap();
+    assert!(response.status().is_success());
+    assert_eq!(
+        response.headers().get("content-type").unwrap(),
+        "application/xml"
+    );
+    let body = String::from_utf8(response.body().await.unwrap().to_vec()).unwrap();
+    assert!(body.starts_with("<?xml version=\"1.0\" encoding=\"UTF-8\"?>"));
+    let doc = roxmltree::Document::parse(&body).unwrap();
+    let siri = doc.root_element();
+    assert_eq!(siri.tag_name().name(), "Siri");
+    assert_eq!(
+        siri.tag_name().namespace(),
+        Some("http://www.siri.org.uk/siri")
+    );
+    assert_eq!(siri.attribute("version"), Some("2.0"));
+    assert_eq!(child_names(siri), vec!["ServiceDelivery"]);
+
+    let service_delivery = child(siri, "ServiceDelivery");
+    assert_eq!(
+        child_names(service_delivery).last(),
+        Some(&"StopMonitoringDelivery")
+    );
+    let delivery = child(service_delivery, "StopMonitoringDelivery");
+    assert_eq!(delivery.attribute("version"), Some("2.0"));
+    let names = child_names(delivery);
+    assert_eq!(names[..2], ["ResponseTimestamp", "Status"]);
+    assert!(names.len() > 2);
+    assert!(names[2..].iter().all(|n| *n == "MonitoredStopVisit"));
+    assert_eq!(child(delivery, "Status").text(), Some("true"));
+
+    let visit = child(delivery, "MonitoredStopVisit");
+    assert_eq!(
+        child_names(visit),
+        vec![
+            "RecordedAtTime",
+            "ItemIdentifier",
+            "MonitoringRef",
+            "MonitoredVehicleJourney"
+        ]
+    );
+    assert_eq!(child(visit, "MonitoringRef").text(), Some("EMSI"));
+
+    // the aimed times of the call are given in the order of the xsd
+    let call = delivery
+        .descendants()
+        .filter(|n| n.tag_name().name() == "MonitoredCall")
+        .find(|c| {
+            c.children().any(|n| {
+                n.tag_name().name() == "AimedArrivalTime"
+                    && n.text() == Some("2018-12-15T06:26:00-08:00")
+            })
+        })
+        .unwrap();
+    let names = child_names(call);
+    assert_eq!(names[..2], ["Order", "StopPointName"]);
+    assert_eq!(
+        names[names.len() - 2..],
+        ["AimedArrivalTime", "AimedDepartureTime"]
+    );
+    assert_eq!(
+        child(call, "AimedDepartureTime").text(),
+        Some("2018-12-15T06:28:00-08:00")
+    );
+
+    // the xml can also be requested with the Accept header
+    let response = srv
+        .get(format!("/default/siri/2.0/stop-monitoring.json?{}", query))
+        .header("Accept", "application/xml")
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(
+        response.headers().get("content-type").unwrap(),
+        "application/xml"
+    );
+
+    // and the errors are also given in xml
+    let mut response = srv
+        .get("/default/siri/2.0/stop-monitoring.xml?MonitoringRef=unknown_stop")
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), actix_web::http::StatusCode::NOT_FOUND);
+    let body = String::from_utf8(response.body().await.unwrap().to_vec()).unwrap();
+    let doc = roxmltree::Document::parse(&body).unwrap();
+    let delivery = child(
+        child(doc.root_element(), "ServiceDelivery"),
+        "StopMonitoringDelivery",
+    );
+    assert_eq!(
+        child_names(delivery),
+        vec!["ResponseTimestamp", "Status", "ErrorCondition"]
+    );
+    assert_eq!(child(delivery, "Status").text(), Some("false"));
+    assert_eq!(
+        child_names(child(delivery, "ErrorCondition")),
+        vec!["InvalidDataReferencesError"]
+    );
+}
+
+// when the stop is not known, we should get a siri error
+async fn test_unknown_stop(srv: &mut actix_web::test::TestServer) {
+    let (status, resp): (_, serde_json::Value) = utils::get_json_with_status(
+        srv,
+        "/default/siri/2.0/stop-monitoring.json?MonitoringRef=unknown_stop",
+    )
+    .await;
+
+    assert_eq!(status, actix_web::http::StatusCode::NOT_FOUND);
+    let delivery = resp
+        .pointer("/Siri/ServiceDelivery/StopMonitoringDelivery/0")
+        .unwrap();
+    assert_eq!(delivery.pointer("/Status"), Some(&serde_json::json!(false)));
+    assert_eq!(
+        delivery.pointer("/ErrorCondition"),
+        Some(&serde_json::json!({
+            "InvalidDataReferencesError": {
+                "ErrorText": "impossible to find stop: 'unknown_stop'"
+            }
+        }))
+    );
+    assert_eq!(
+        delivery.pointer("/MonitoredStopVisit"),
+        Some(&serde_json::json!([]))
+    );
+
+    // a missing mandatory parameter is also reported as a siri error
+    let (status, resp): (_, serde_json::Value) =
+        utils::get_json_with_status(srv, "/default/siri/2.0/stop-monitoring.json").await;
+    assert_eq!(status, actix_web::http::StatusCode::BAD_REQUEST);
+    assert!(resp
+        .pointer("/Siri/ServiceDelivery/StopMonitoringDelivery/0/ErrorCondition/OtherError")
+        .is_some());
 }
 
 // test stop_monitoring on BEATTY_AIRPORT
@@ -72,11 +289,11 @@ async fn test_beatty_stop_call(srv: &mut actix_web::test::TestServer) {
     let first_passage = &vj.monitored_call.as_ref().unwrap();
     assert_eq!(
         string(&first_passage.aimed_arrival_time),
-        Some("2018-12-15T06:20:00".to_owned())
+        Some("2018-12-15T06:20:00-08:00".to_owned())
     );
     assert_eq!(
         string(&first_passage.aimed_departure_time),
-        Some("2018-12-15T06:20:00".to_owned())
+        Some("2018-12-15T06:20:00-08:00".to_owned())
     );
     assert!(first_passage.expected_arrival_time.is_none());
     assert!(first_passage.expected_departure_time.is_none());
@@ -92,11 +309,11 @@ async fn test_beatty_stop_call(srv: &mut actix_web::test::TestServer) {
     let second_passage = &vj.monitored_call.as_ref().unwrap();
     assert_eq!(
         string(&second_passage.aimed_arrival_time),
-        Some("2018-12-15T08:00:00".to_owned())
+        Some("2018-12-15T08:00:00-08:00".to_owned())
     );
     assert_eq!(
         string(&second_passage.aimed_departure_time),
-        Some("2018-12-15T08:00:00".to_owned())
+        Some("2018-12-15T08:00:00-08:00".to_owned())
     );
     assert!(second_passage.expected_arrival_time.is_none());
     assert!(second_passage.expected_departure_time.is_none());
@@ -119,11 +336,11 @@ async fn test_beatty_stop_call(srv: &mut actix_web::test::TestServer) {
     let first_passage = &vj.monitored_call.as_ref().unwrap();
     assert_eq!(
         string(&first_passage.aimed_arrival_time),
-        Some("2018-12-15T08:00:00".to_owned())
+        Some("2018-12-15T08:00:00-08:00".to_owned())
     );
     assert_eq!(
         string(&first_passage.aimed_departure_time),
-        Some("2018-12-15T08:00:00".to_owned())
+        Some("2018-12-15T08:00:00-08:00".to_owned())
     );
     assert!(first_passage.expected_arrival_time.is_none());
     assert!(first_passage.expected_departure_time.is_none());
@@ -137,11 +354,11 @@ async fn test_beatty_stop_call(srv: &mut actix_web::test::TestServer) {
     let second_passage = &vj.monitored_call.as_ref().unwrap();
     assert_eq!(
         string(&second_passage.aimed_arrival_time),
-        Some("2018-12-15T12:15:00".to_owned())
+        Some("2018-12-15T12:15:00-08:00".to_owned())
     );
     assert_eq!(
         string(&second_passage.aimed_departure_time),
-        Some("2018-12-15T12:15:00".to_owned())
+        Some("2018-12-15T12:15:00-08:00".to_owned())
     );
     assert!(second_passage.expected_arrival_time.is_none());
     assert!(second_passage.expected_departure_time.is_none());
@@ -166,6 +383,27 @@ PreviewInterval=PT1H",
     assert_eq!(sm.monitored_stop_visit.len(), 1);
 }
 
+// the StartTime can be given with an offset, else it is in the timezone of the dataset
+async fn test_start_time_with_offset(srv: &mut actix_web::test::TestServer) {
+    let resp: SiriResponse = utils::get_json(srv, "/default/siri/2.0/stop-monitoring.json?MonitoringRef=BEATTY_AIRPORT&StartTime=2018-12-15T14:30:00Z&DataFreshness=Scheduled").await;
+    let spd = resp.siri.service_delivery.unwrap();
+    let sm = spd.stop_monitoring_delivery.iter().next().unwrap();
+
+    // 14:30 UTC is 06:30 in Los Angeles, the first passage is at 08:00 (and not at 12:15)
+    let first_passage = &sm.monitored_stop_visit[0];
+    assert_eq!(
+        string(
+            &first_passage
+                .monitored_vehicle_journey
+                .monitored_call
+                .as_ref()
+                .unwrap()
+                .aimed_arrival_time
+        ),
+        Some("2018-12-15T08:00:00-08:00".to_owned())
+    );
+}
+
 fn create_mock_feed_message() -> transit_realtime::FeedMessage {
     use transpo_rt::transit_realtime::*;
     FeedMessage {
@@ -224,19 +462,19 @@ async fn sp_monitoring_realtime_integration_test() {
     let passage = &vj.monitored_call.as_ref().unwrap();
     assert_eq!(
         string(&passage.aimed_arrival_time),
-        Some("2018-12-15T06:26:00".into())
+        Some("2018-12-15T06:26:00-08:00".into())
     );
     assert_eq!(
         string(&passage.expected_arrival_time),
-        Some("2018-12-15T06:26:30".into())
+        Some("2018-12-15T06:26:30-08:00".into())
     );
     assert_eq!(
         string(&passage.aimed_departure_time),
-        Some("2018-12-15T06:28:00".into())
+        Some("2018-12-15T06:28:00-08:00".into())
     );
     assert_eq!(
         string(&passage.expected_departure_time),
-        Some("2018-12-15T06:28:30".into())
+        Some("2018-12-15T06:28:30-08:00".into())
     );
     assert_eq!(passage.order, 5);
     assert_eq!(passage.stop_point_name, "E Main St / S Irving St (Demo)");
@@ -9,6 +9,9 @@ async fn sp_discovery_integration_test() {
     let mut srv = utils::make_simple_test_server().await;
 
     filter_query(&mut srv).await;
+    search_query(&mut srv).await;
+    proximity_query(&mut srv).await;
+    line_and_mode_query(&mut srv).await;
     limit_query(&mut srv).await;
 }
 
@@ -33,15 +36,135 @@ async fn filter_query(srv: &mut actix_web::test::TestServer) {
         assert_eq!(stop1.location.longitude, -116.762_18_f64);
         assert_eq!(stop1.location.latitude, 36.905_697_f64);
     }
+    assert_eq!(stop1.stop_area_ref, "Navitia:EMSI");
+    assert_eq!(stop1.stop_area_name, "E Main St / S Irving St (Demo)");
+    // the lines are given, not the routes
+    assert_eq!(stop1.lines.len(), 1);
+    assert_eq!(stop1.lines[0].line_ref, "CITY");
+    assert_eq!(stop1.lines[0].line_name, "City");
+    assert_eq!(stop1.lines[0].line_colour, None);
+    assert_eq!(stop1.vehicle_mode, vec!["bus".to_owned()]);
+    assert_eq!(stop1.wheelchair_access, "unknown");
+}
+
+async fn stop_point_ids(srv: &mut actix_web::test::TestServer, query: &str) -> BTreeSet<String> {
+    get_stop_points(srv, query)
+        .await
+        .into_iter()
+        .map(|s| s.0)
+        .collect()
+}
+
+async fn line_and_mode_query(srv: &mut actix_web::test::TestServer) {
+    assert_eq!(
+        stop_point_ids(srv, "LineRef=CITY").await,
+        vec!["STAGECOACH", "NANAA", "NADAV", "DADAN", "EMSI"]
+            .into_iter()
+            .map(|s| s.to_owned())
+            .collect()
+    );
+    assert!(stop_point_ids(srv, "LineRef=unknown").await.is_empty());
+    assert_eq!(
+        stop_point_ids(srv, "LineRef=CITY&q=ave").await,
+        vec!["NANAA", "NADAV", "DADAN"]
+            .into_iter()
+            .map(|s| s.to_owned())
+            .collect()
+    );
     assert_eq!(
-        stop1
-            .lines
-            .iter()
-            .map(|l| l.line_ref.clone())
-            .collect::<BTreeSet<_>>(),
-        vec!["CITY_R".into(), "CITY".into()].into_iter().collect()
+        stop_point_ids(srv, "VehicleMode=bus&limit=100").await.len(),
+        9
     );
-    //TODO more tests
+    assert!(stop_point_ids(srv, "VehicleMode=tram").await.is_empty());
+    // the accessibility of the stops is unknown in the dataset
+    assert!(stop_point_ids(srv, "WheelchairAccessible=true")
+        .await
+        .is_empty());
+    assert!(stop_point_ids(srv, "WheelchairAccessible=false")
+        .await
+        .is_empty());
+}
+
+async fn search(srv: &mut actix_web::test::TestServer, q: &str) -> Vec<String> {
+    let resp: SiriResponse = utils::get_json(
+        srv,
+        &format!("/default/siri/2.0/stoppoints-discovery.json?q={}", q),
+    )
+    .await;
+    resp.siri
+        .stop_points_delivery
+        .unwrap()
+        .annotated_stop_point
+        .into_iter()
+        .map(|s| s.stop_point_ref)
+        .collect()
+}
+
+async fn search_query(srv: &mut actix_web::test::TestServer) {
+    // the accents and the case are ignored
+    assert_eq!(search(srv, "%C3%8Frving").await, vec!["EMSI"]);
+    // a small typo is tolerated
+    assert_eq!(search(srv, "irvng").await, vec!["EMSI"]);
+    // all the words must match, in any order
+    assert_eq!(search(srv, "ave%20north").await, vec!["NADAV", "NANAA"]);
+    assert!(search(srv, "north%20irving").await.is_empty());
+}
+
+async fn get_stop_points(
+    srv: &mut actix_web::test::TestServer,
+    query: &str,
+) -> Vec<(String, Option<f64>)> {
+    let resp: SiriResponse = utils::get_json(
+        srv,
+        &format!("/default/siri/2.0/stoppoints-discovery.json?{}", query),
+    )
+    .await;
+    resp.siri
+        .stop_points_delivery
+        .unwrap()
+        .annotated_stop_point
+        .into_iter()
+        .map(|s| (s.stop_point_ref, s.distance))
+        .collect()
+}
+
+async fn proximity_query(srv: &mut actix_web::test::TestServer) {
+    let around = "Latitude=36.91&Longitude=-116.765&Radius=1000";
+    // the stop points are sorted by distance
+    let stop_points = get_stop_points(srv, around).await;
+    assert_eq!(
+        stop_points.iter().map(|s| s.0.as_str()).collect::<Vec<_>>(),
+        vec!["DADAN", "EMSI", "NADAV", "NANAA"]
+    );
+    let dadan_distance = stop_points[0].1.unwrap();
+    assert!(dadan_distance > 280. && dadan_distance < 310.);
+
+    // the proximity search can be combined with a text search and a bounding box
+    let stop_points = get_stop_points(srv, &format!("{}&q=ave", around)).await;
+    assert_eq!(
+        stop_points.iter().map(|s| s.0.as_str()).collect::<Vec<_>>(),
+        vec!["DADAN", "NADAV", "NANAA"]
+    );
+    let stop_points = get_stop_points(
+        srv,
+        &format!("{}&BoundingBoxStructure.UpperLeft.Latitude=36.912", around),
+    )
+    .await;
+    assert_eq!(
+        stop_points.iter().map(|s| s.0.as_str()).collect::<Vec<_>>(),
+        vec!["DADAN", "EMSI"]
+    );
+
+    // the default radius is 500m
+    let stop_points = get_stop_points(srv, "Latitude=36.91&Longitude=-116.765").await;
+    assert_eq!(
+        stop_points.iter().map(|s| s.0.as_str()).collect::<Vec<_>>(),
+        vec!["DADAN"]
+    );
+
+    // without proximity search, there is no distance
+    let stop_points = get_stop_points(srv, "q=irving").await;
+    assert_eq!(stop_points, vec![("EMSI".to_owned(), None)]);
 }
 
 async fn limit_query(srv: &mut actix_web::test::TestServer) {
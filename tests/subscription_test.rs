@@ -0,0 +1,189 @@
+use serde_json::json;
+use transpo_rt::transit_realtime;
+mod utils;
+
+const CONSUMER_PATH: &str = "/consumer";
+
+fn create_mock_feed_message() -> transit_realtime::FeedMessage {
+    use transpo_rt::transit_realtime::*;
+    FeedMessage {
+        header: FeedHeader {
+            gtfs_realtime_version: "2.0".into(),
+            incrementality: Some(0i32),
+            timestamp: Some(1u64),
+        },
+        entity: vec![FeedEntity {
+            id: "delay_on_city1".into(),
+            trip_update: Some(TripUpdate {
+                trip: TripDescriptor {
+                    trip_id: Some("CITY1".into()),
+                    start_date: Some("20181215".into()),
+                    ..Default::default()
+                },
+                stop_time_update: vec![utils::make_stu(
+                    "EMSI",
+                    5,
+                    Some("2018-12-15T06:26:30-08:00"),
+                    Some("2018-12-15T06:28:30-08:00"),
+                )],
+                ..Default::default()
+            }),
+            ..Default::default()
+        }],
+    }
+}
+
+async fn post_json(
+    srv: &mut actix_web::test::TestServer,
+    route: &str,
+    body: &serde_json::Value,
+) -> (u16, serde_json::Value) {
+    let mut response = srv.post(route).send_json(body).await.unwrap();
+    (response.status().as_u16(), response.json().await.unwrap())
+}
+
+fn termination_time() -> String {
+    (chrono::Utc::now() + chrono::Duration::hours(1)).to_rfc3339()
+}
+
+#[actix_rt::test]
+async fn subscription_integration_test() {
+    let _log_guard = utils::init_log();
+    let gtfs_rt = create_mock_feed_message();
+    let _server = utils::run_simple_gtfs_rt_server(gtfs_rt);
+
+    let mut srv = utils::make_simple_test_server().await;
+
+    subscribe_estimated_timetable(&mut srv).await;
+    invalid_subscriptions(&mut srv).await;
+    terminate_subscription(&mut srv).await;
+}
+
+// the first notification is pushed right after the subscription, with all the updated vehicle journeys
+async fn subscribe_estimated_timetable(srv: &mut actix_web::test::TestServer) {
+    let consumer = mockito::mock("POST", CONSUMER_PATH)
+        .match_body(mockito::Matcher::Regex(
+            r#""DatedVehicleJourneyRef":"CITY1:2018-12-15""#.to_owned(),
+        ))
+        .with_status(200)
+        .expect(1)
+        .create();
+
+    let (status, resp) = post_json(
+        srv,
+        "/default/siri/2.0/subscribe.json",
+        &json!({
+            "Siri": {
+                "SubscriptionRequest": {
+                    "RequestorRef": "consumer",
+                    "MessageIdentifier": "msg:1",
+                    "ConsumerAddress": mockito::server_url() + CONSUMER_PATH,
+                    "EstimatedTimetableSubscriptionRequest": [{
+                        "SubscriptionIdentifier": "sub:et",
+                        "InitialTerminationTime": termination_time(),
+                        "ChangeBeforeUpdates": "PT1M",
+                        // the lines can also be given with the ids of the idf profile
+                        "EstimatedTimetableRequest": {"LineRef": ["CITY", "STIF:Line::CITY:"]}
+                    }]
+                }
+            }
+        }),
+    )
+    .await;
+
+    assert_eq!(status, 200);
+    let response = &resp["Siri"]["SubscriptionResponse"];
+    assert_eq!(response["RequestMessageRef"], "msg:1");
+    assert_eq!(response["ResponderRef"], "default");
+    assert_eq!(response["ResponseStatus"][0]["SubscriptionRef"], "sub:et");
+    assert_eq!(response["ResponseStatus"][0]["Status"], true);
+
+    // the notification is pushed asynchronously
+    actix_rt::time::delay_for(std::time::Duration::from_millis(500)).await;
+    consumer.assert();
+}
+
+async fn invalid_subscriptions(srv: &mut actix_web::test::TestServer) {
+    let (status, resp) = post_json(
+        srv,
+        "/default/siri/2.0/subscribe.json",
+        &json!({
+            "Siri": {
+                "SubscriptionRequest": {
+                    "RequestorRef": "consumer",
+                    "ConsumerAddress": mockito::server_url() + CONSUMER_PATH,
+                    "StopMonitoringSubscriptionRequest": [{
+                        "SubscriptionIdentifier": "sub:unknown_stop",
+                        "InitialTerminationTime": termination_time(),
+                        "StopMonitoringRequest": {"MonitoringRef": "unknown_stop"}
+                    }],
+                    "GeneralMessageSubscriptionRequest": [{
+                        "SubscriptionIdentifier": "sub:expired",
+                        "InitialTerminationTime": "2018-12-15T10:00:00+01:00"
+                    }]
+                }
+            }
+        }),
+    )
+    .await;
+
+    // each subscription has its own status
+    assert_eq!(status, 200);
+    let response_status = &resp["Siri"]["SubscriptionResponse"]["ResponseStatus"];
+    assert_eq!(response_status[0]["SubscriptionRef"], "sub:unknown_stop");
+    assert_eq!(response_status[0]["Status"], false);
+    assert_eq!(
+        response_status[0]["ErrorCondition"]["InvalidDataReferencesError"]["ErrorText"],
+        "impossible to find stop: 'unknown_stop'"
+    );
+    assert_eq!(response_status[1]["SubscriptionRef"], "sub:expired");
+    assert_eq!(response_status[1]["Status"], false);
+    assert!(response_status[1]["ErrorCondition"]["OtherError"].is_object());
+
+    // an invalid body is reported in the siri response
+    let (status, resp) = post_json(
+        srv,
+        "/default/siri/2.0/subscribe.json",
+        &json!({"Siri": {"SubscriptionRequest": {"RequestorRef": "consumer"}}}),
+    )
+    .await;
+    assert_eq!(status, 400);
+    let response_status = &resp["Siri"]["SubscriptionResponse"]["ResponseStatus"];
+    assert_eq!(response_status[0]["Status"], false);
+    assert!(response_status[0]["ErrorCondition"]["OtherError"].is_object());
+}
+
+async fn terminate_subscription(srv: &mut actix_web::test::TestServer) {
+    let terminate_request = json!({
+        "Siri": {
+            "TerminateSubscriptionRequest": {
+                "RequestorRef": "consumer",
+                "SubscriptionRef": ["sub:et"]
+            }
+        }
+    });
+    let (status, resp) = post_json(
+        srv,
+        "/default/siri/2.0/terminate-subscription.json",
+        &terminate_request,
+    )
+    .await;
+    assert_eq!(status, 200);
+    let termination_status =
+        &resp["Siri"]["TerminateSubscriptionResponse"]["TerminationResponseStatus"];
+    assert_eq!(termination_status[0]["SubscriptionRef"], "sub:et");
+    assert_eq!(termination_status[0]["Status"], true);
+
+    // the subscription does not exist anymore
+    let (status, resp) = post_json(
+        srv,
+        "/default/siri/2.0/terminate-subscription.json",
+        &terminate_request,
+    )
+    .await;
+    assert_eq!(status, 200);
+    let termination_status =
+        &resp["Siri"]["TerminateSubscriptionResponse"]["TerminationResponseStatus"];
+    assert_eq!(termination_status[0]["Status"], false);
+    assert!(termination_status[0]["ErrorCondition"]["InvalidDataReferencesError"].is_object());
+}
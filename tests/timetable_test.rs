@@ -0,0 +1,221 @@
+use actix_web::http::StatusCode;
+use serde_json::Value;
+mod utils;
+
+#[actix_rt::test]
+async fn timetable_integration_test() {
+    let _log_guard = utils::init_log();
+    let mut srv = utils::make_simple_test_server().await;
+
+    // Note: to reduce the number of time the dataset is loaded we chain the tests
+    production_timetable_by_line(&mut srv).await;
+    production_timetable_all_lines(&mut srv).await;
+    stop_timetable(&mut srv).await;
+    beyond_data_horizon(&mut srv).await;
+    invalid_requests(&mut srv).await;
+    xml_timetables(&mut srv).await;
+}
+
+async fn production_timetable_by_line(srv: &mut actix_web::test::TestServer) {
+    let resp: Value = utils::get_json(
+        srv,
+        "/default/siri/2.0/production-timetable.json?LineRef=AB\
+&StartTime=2018-12-15T07:00:00&EndTime=2018-12-15T10:00:00",
+    )
+    .await;
+    let frames = &resp["Siri"]["ServiceDelivery"]["ProductionTimetableDelivery"][0]
+        ["DatedTimetableVersionFrame"];
+    assert_eq!(frames.as_array().unwrap().len(), 1);
+    assert_eq!(frames[0]["LineRef"], "AB");
+
+    // AB2 only leaves at 12:05, it is not in the period
+    let vjs = frames[0]["DatedVehicleJourney"].as_array().unwrap();
+    assert_eq!(vjs.len(), 1);
+    assert_eq!(vjs[0]["DatedVehicleJourneyCode"], "AB1:2018-12-15");
+    assert_eq!(vjs[0]["OperatorRef"], "DTA");
+    assert_eq!(
+        vjs[0]["DatedCalls"],
+        serde_json::json!([
+            {
+                "StopPointRef": "BEATTY_AIRPORT",
+                "Order": 1,
+                "StopPointName": "Nye County Airport (Demo)",
+                "AimedArrivalTime": "2018-12-15T08:00:00-08:00",
+                "AimedDepartureTime": "2018-12-15T08:00:00-08:00",
+            },
+            {
+                "StopPointRef": "BULLFROG",
+                "Order": 2,
+                "StopPointName": "Bullfrog (Demo)",
+                "AimedArrivalTime": "2018-12-15T08:10:00-08:00",
+                "AimedDepartureTime": "2018-12-15T08:15:00-08:00",
+            },
+        ])
+    );
+}
+
+async fn production_timetable_all_lines(srv: &mut actix_web::test::TestServer) {
+    // without EndTime, the period ends with the data horizon
+    let resp: Value = utils::get_json(
+        srv,
+        "/default/siri/2.0/production-timetable.json?StartTime=2018-12-15T00:00:00",
+    )
+    .await;
+    let frames = resp["Siri"]["ServiceDelivery"]["ProductionTimetableDelivery"][0]
+        ["DatedTimetableVersionFrame"]
+        .as_array()
+        .unwrap()
+        .clone();
+    assert_eq!(
+        frames
+            .iter()
+            .map(|f| f["LineRef"].as_str().unwrap())
+            .collect::<Vec<_>>(),
+        vec!["AAMV", "AB", "BFC", "CITY", "STBA"]
+    );
+    let ab_vjs: Vec<_> = frames[1]["DatedVehicleJourney"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|vj| vj["DatedVehicleJourneyCode"].as_str().unwrap())
+        .collect();
+    assert_eq!(ab_vjs, vec!["AB1:2018-12-15", "AB2:2018-12-15"]);
+}
+
+async fn stop_timetable(srv: &mut actix_web::test::TestServer) {
+    let resp: Value = utils::get_json(
+        srv,
+        "/default/siri/2.0/stop-timetable.json?MonitoringRef=BEATTY_AIRPORT\
+&StartTime=2018-12-15T05:00:00&EndTime=2018-12-15T09:00:00",
+    )
+    .await;
+    let visits = resp["Siri"]["ServiceDelivery"]["StopTimetableDelivery"][0]["TimetabledStopVisit"]
+        .as_array()
+        .unwrap()
+        .clone();
+    // STBA at 6:20, then AB1 and AAMV1 at 8:00
+    assert_eq!(visits.len(), 3);
+    assert_eq!(visits[0]["MonitoringRef"], "BEATTY_AIRPORT");
+    assert_eq!(visits[0]["ItemIdentifier"], "BEATTY_AIRPORT:STBA");
+    let vj = &visits[0]["TargetedVehicleJourney"];
+    assert_eq!(vj["LineRef"], "STBA");
+    assert_eq!(vj["OperatorRef"], "DTA");
+    assert_eq!(
+        vj["FramedVehicleJourneyRef"],
+        serde_json::json!({"DataFrameRef": "2018-12-15", "DatedVehicleJourneyRef": "STBA"})
+    );
+    assert_eq!(vj["TargetedCall"]["Order"], 2);
+    assert_eq!(
+        vj["TargetedCall"]["AimedArrivalTime"],
+        "2018-12-15T06:20:00-08:00"
+    );
+
+    // the visits can be filtered by line
+    let resp: Value = utils::get_json(
+        srv,
+        "/default/siri/2.0/stop-timetable.json?MonitoringRef=BEATTY_AIRPORT&LineRef=AB\
+&StartTime=2018-12-15T05:00:00&EndTime=2018-12-15T09:00:00",
+    )
+    .await;
+    let visits =
+        &resp["Siri"]["ServiceDelivery"]["StopTimetableDelivery"][0]["TimetabledStopVisit"];
+    assert_eq!(visits.as_array().unwrap().len(), 1);
+    assert_eq!(visits[0]["ItemIdentifier"], "BEATTY_AIRPORT:AB1");
+}
+
+// the timetables are only computed for the generation period (2018-12-15 in the tests)
+async fn beyond_data_horizon(srv: &mut actix_web::test::TestServer) {
+    for query in &[
+        "/default/siri/2.0/production-timetable.json?StartTime=2018-12-20T08:00:00",
+        "/default/siri/2.0/production-timetable.json?StartTime=2018-12-15T08:00:00\
+&EndTime=2018-12-17T08:00:00",
+        "/default/siri/2.0/stop-timetable.json?MonitoringRef=BEATTY_AIRPORT\
+&StartTime=2018-12-14T08:00:00&EndTime=2018-12-15T08:00:00",
+    ] {
+        let (status, resp): (_, Value) = utils::get_json_with_status(srv, query).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST, "{}", query);
+        let delivery = &resp["Siri"]["ServiceDelivery"];
+        let error = delivery["ProductionTimetableDelivery"][0]["ErrorCondition"]
+            .as_object()
+            .or_else(|| delivery["StopTimetableDelivery"][0]["ErrorCondition"].as_object())
+            .unwrap();
+        assert!(error.contains_key("BeyondDataHorizon"), "{}", query);
+    }
+}
+
+async fn invalid_requests(srv: &mut actix_web::test::TestServer) {
+    let (status, resp): (_, Value) = utils::get_json_with_status(
+        srv,
+        "/default/siri/2.0/production-timetable.json?LineRef=unknown_line\
+&StartTime=2018-12-15T08:00:00",
+    )
+    .await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+    assert_eq!(
+        resp.pointer(
+            "/Siri/ServiceDelivery/ProductionTimetableDelivery/0/ErrorCondition\
+/InvalidDataReferencesError/ErrorText"
+        ),
+        Some(&serde_json::json!(
+            "impossible to find line: 'unknown_line'"
+        ))
+    );
+
+    let (status, resp): (_, Value) = utils::get_json_with_status(
+        srv,
+        "/default/siri/2.0/stop-timetable.json?MonitoringRef=unknown_stop\
+&StartTime=2018-12-15T08:00:00",
+    )
+    .await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+    assert!(resp
+        .pointer(
+            "/Siri/ServiceDelivery/StopTimetableDelivery/0/ErrorCondition\
+/InvalidDataReferencesError"
+        )
+        .is_some());
+
+    // the end of the window cannot be before its start
+    let (status, resp): (_, Value) = utils::get_json_with_status(
+        srv,
+        "/default/siri/2.0/stop-timetable.json?MonitoringRef=BEATTY_AIRPORT\
+&StartTime=2018-12-15T08:00:00&EndTime=2018-12-15T07:00:00",
+    )
+    .await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    assert!(resp
+        .pointer("/Siri/ServiceDelivery/StopTimetableDelivery/0/ErrorCondition/OtherError")
+        .is_some());
+}
+
+async fn xml_timetables(srv: &mut actix_web::test::TestServer) {
+    let mut response = srv
+        .get(
+            "/default/siri/2.0/stop-timetable.xml?MonitoringRef=BEATTY_AIRPORT\
+&StartTime=2018-12-15T06:00:00&EndTime=2018-12-15T07:00:00",
+        )
+        .send()
+        .await
+        .unwrap();
+    assert!(response.status().is_success());
+    let body = String::from_utf8(response.body().await.unwrap().to_vec()).unwrap();
+    assert!(body.contains("<StopTimetableDelivery version=\"2.0\">"));
+    assert!(body.contains(
+        "<TargetedCall><Order>2</Order><StopPointName>Nye County Airport (Demo)</StopPointName>\
+<AimedArrivalTime>2018-12-15T06:20:00-08:00</AimedArrivalTime>\
+<AimedDepartureTime>2018-12-15T06:20:00-08:00</AimedDepartureTime></TargetedCall>"
+    ));
+
+    let mut response = srv
+        .get(
+            "/default/siri/2.0/production-timetable.xml?LineRef=AB\
+&StartTime=2018-12-15T07:00:00&EndTime=2018-12-15T10:00:00",
+        )
+        .send()
+        .await
+        .unwrap();
+    assert!(response.status().is_success());
+    let body = String::from_utf8(response.body().await.unwrap().to_vec()).unwrap();
+    assert!(body.contains("<ProductionTimetableDelivery version=\"2.0\">"));
+    assert!(body.contains("<DatedVehicleJourneyCode>AB1:2018-12-15</DatedVehicleJourneyCode>"));
+}
@@ -0,0 +1,94 @@
+use serde_json::Value;
+use transpo_rt::datasets::DatasetInfo;
+use transpo_rt::transit_realtime;
+mod utils;
+
+const GTFS_RT_PATH: &str = "/two_timezones_gtfs_rt";
+
+// the NEW_YORK1 trip is found with its route and its start time (in the New York timezone)
+fn create_mock_feed_message() -> transit_realtime::FeedMessage {
+    use transpo_rt::transit_realtime::*;
+    FeedMessage {
+        header: FeedHeader {
+            gtfs_realtime_version: "2.0".into(),
+            incrementality: Some(0i32),
+            timestamp: Some(1u64),
+        },
+        entity: vec![FeedEntity {
+            id: "delay_on_new_york1".into(),
+            trip_update: Some(TripUpdate {
+                trip: TripDescriptor {
+                    route_id: Some("NEW_YORK".into()),
+                    direction_id: Some(0),
+                    start_date: Some("20181215".into()),
+                    start_time: Some("08:00:00".into()),
+                    ..Default::default()
+                },
+                stop_time_update: vec![utils::make_stu(
+                    "NEW_YORK_B",
+                    2,
+                    Some("2018-12-15T08:35:00-05:00"),
+                    Some("2018-12-15T08:35:00-05:00"),
+                )],
+                ..Default::default()
+            }),
+            ..Default::default()
+        }],
+    }
+}
+
+// aimed and expected arrival times of the first passage at a stop
+async fn arrival_times(
+    srv: &mut actix_web::test::TestServer,
+    stop: &str,
+    start_time: &str,
+) -> (Value, Value) {
+    let resp: Value = utils::get_json(
+        srv,
+        &format!(
+            "/default/siri/2.0/stop-monitoring.json?MonitoringRef={}&StartTime={}",
+            stop, start_time
+        ),
+    )
+    .await;
+    let call = resp
+        .pointer(
+            "/Siri/ServiceDelivery/StopMonitoringDelivery/0/MonitoredStopVisit/0/MonitoredVehicleJourney/MonitoredCall",
+        )
+        .unwrap();
+    (
+        call["AimedArrivalTime"].clone(),
+        call["ExpectedArrivalTime"].clone(),
+    )
+}
+
+// The dataset has 2 networks, in Paris (the timezone of the dataset) and New York.
+// The times are given in the timezone of the network of the trip,
+// or in the timezone of the stop if it has one (NEW_YORK_B is in the timezone of Paris)
+#[actix_rt::test]
+async fn two_timezones_test() {
+    let _log_guard = utils::init_log();
+    let _server = utils::run_gtfs_rt_server(GTFS_RT_PATH, create_mock_feed_message());
+    let mut srv = utils::make_test_server(vec![DatasetInfo::new_default(
+        "fixtures/two_timezones.zip",
+        &[mockito::server_url() + GTFS_RT_PATH],
+    )])
+    .await;
+
+    assert_eq!(
+        arrival_times(&mut srv, "PARIS_A", "2018-12-15T08:00:00Z").await,
+        (Value::from("2018-12-15T10:00:00+01:00"), Value::Null)
+    );
+    assert_eq!(
+        arrival_times(&mut srv, "NEW_YORK_A", "2018-12-15T12:00:00Z").await,
+        (Value::from("2018-12-15T08:00:00-05:00"), Value::Null)
+    );
+    // the delay given by the gtfs-rt is also displayed in the timezone of the stop
+    assert_eq!(
+        arrival_times(&mut srv, "NEW_YORK_B", "2018-12-15T12:00:00Z").await,
+        (
+            Value::from("2018-12-15T14:30:00+01:00"),
+            Value::from("2018-12-15T14:35:00+01:00")
+        )
+    );
+}
@@ -0,0 +1,170 @@
+use transpo_rt::transit_realtime;
+mod utils;
+
+fn create_mock_feed_message() -> transit_realtime::FeedMessage {
+    use transpo_rt::transit_realtime::*;
+    let mut skipped_stop = utils::make_stu("NADAV", 3, None, None);
+    skipped_stop.schedule_relationship =
+        Some(trip_update::stop_time_update::ScheduleRelationship::Skipped as i32);
+    FeedMessage {
+        header: FeedHeader {
+            gtfs_realtime_version: "2.0".into(),
+            incrementality: Some(0i32),
+            timestamp: Some(1u64),
+        },
+        entity: vec![
+            FeedEntity {
+                id: "delay_on_city2".into(),
+                trip_update: Some(TripUpdate {
+                    trip: TripDescriptor {
+                        trip_id: Some("CITY2".into()),
+                        start_date: Some("20181215".into()),
+                        ..Default::default()
+                    },
+                    stop_time_update: vec![
+                        utils::make_stu(
+                            "DADAN",
+                            2,
+                            Some("2018-12-15T06:36:00-08:00"),
+                            Some("2018-12-15T06:38:00-08:00"),
+                        ),
+                        skipped_stop,
+                    ],
+                    ..Default::default()
+                }),
+                ..Default::default()
+            },
+            FeedEntity {
+                id: "city2_position".into(),
+                vehicle: Some(VehiclePosition {
+                    trip: Some(TripDescriptor {
+                        trip_id: Some("CITY2".into()),
+                        start_date: Some("20181215".into()),
+                        ..Default::default()
+                    }),
+                    position: Some(Position {
+                        latitude: 36.9,
+                        longitude: -116.7,
+                        ..Default::default()
+                    }),
+                    occupancy_status: Some(
+                        vehicle_position::OccupancyStatus::StandingRoomOnly as i32,
+                    ),
+                    ..Default::default()
+                }),
+                ..Default::default()
+            },
+            FeedEntity {
+                id: "ab1_cancelled".into(),
+                trip_update: Some(TripUpdate {
+                    trip: TripDescriptor {
+                        trip_id: Some("AB1".into()),
+                        start_date: Some("20181215".into()),
+                        schedule_relationship: Some(
+                            trip_descriptor::ScheduleRelationship::Canceled as i32,
+                        ),
+                        ..Default::default()
+                    },
+                    ..Default::default()
+                }),
+                ..Default::default()
+            },
+        ],
+    }
+}
+
+#[actix_rt::test]
+async fn trip_integration_test() {
+    let _log_guard = utils::init_log();
+    let gtfs_rt = create_mock_feed_message();
+    let _server = utils::run_simple_gtfs_rt_server(gtfs_rt);
+
+    let mut srv = utils::make_simple_test_server().await;
+
+    realtime_trip(&mut srv).await;
+    cancelled_trip(&mut srv).await;
+    unknown_trip(&mut srv).await;
+    trip_link(&mut srv).await;
+}
+
+async fn realtime_trip(srv: &mut actix_web::test::TestServer) {
+    let trip: serde_json::Value =
+        utils::get_json(srv, "/default/trips/CITY2?date=2018-12-15").await;
+    assert_eq!(trip["DatedVehicleJourneyRef"], "CITY2");
+    assert_eq!(trip["DataFrameRef"], "2018-12-15");
+    assert_eq!(trip["LineRef"], "CITY");
+    assert_eq!(trip["LineName"], "City");
+    assert_eq!(trip["RouteRef"], "CITY_R");
+    assert_eq!(trip["OperatorRef"], "DTA");
+    assert_eq!(trip["Cancellation"], false);
+    assert_eq!(trip["Occupancy"], "standingAvailable");
+    let location = &trip["VehicleLocation"];
+    assert!((location["Latitude"].as_f64().unwrap() - 36.9).abs() < 1e-5);
+    assert!((location["Longitude"].as_f64().unwrap() + 116.7).abs() < 1e-5);
+
+    let calls = trip["Calls"].as_array().unwrap();
+    assert_eq!(
+        calls
+            .iter()
+            .map(|c| c["StopPointRef"].as_str().unwrap())
+            .collect::<Vec<_>>(),
+        vec!["EMSI", "DADAN", "NADAV", "NANAA", "STAGECOACH"]
+    );
+    assert_eq!(calls[0]["AimedArrivalTime"], "2018-12-15T06:28:00-08:00");
+    assert!(calls[0]["ExpectedArrivalTime"].is_null());
+    assert_eq!(calls[1]["AimedArrivalTime"], "2018-12-15T06:35:00-08:00");
+    assert_eq!(calls[1]["ExpectedArrivalTime"], "2018-12-15T06:36:00-08:00");
+    assert_eq!(
+        calls[1]["ExpectedDepartureTime"],
+        "2018-12-15T06:38:00-08:00"
+    );
+    assert_eq!(calls[1]["Skipped"], false);
+    assert_eq!(calls[2]["Skipped"], true);
+}
+
+async fn cancelled_trip(srv: &mut actix_web::test::TestServer) {
+    let trip: serde_json::Value = utils::get_json(srv, "/default/trips/AB1?date=2018-12-15").await;
+    assert_eq!(trip["Cancellation"], true);
+    assert_eq!(trip["Calls"].as_array().unwrap().len(), 2);
+    // no vehicle position for this trip
+    assert!(trip["VehicleLocation"].is_null());
+    assert!(trip["Occupancy"].is_null());
+}
+
+async fn unknown_trip(srv: &mut actix_web::test::TestServer) {
+    assert_eq!(
+        utils::get_status(srv, "/default/trips/unknown?date=2018-12-15").await,
+        actix_web::http::StatusCode::NOT_FOUND
+    );
+    // the dataset is only loaded for the 2018-12-15
+    assert_eq!(
+        utils::get_status(srv, "/default/trips/CITY2?date=2018-12-20").await,
+        actix_web::http::StatusCode::NOT_FOUND
+    );
+}
+
+// each stop visit of the stop monitoring has a link to its trip
+async fn trip_link(srv: &mut actix_web::test::TestServer) {
+    let resp: serde_json::Value = utils::get_json(
+        srv,
+        "/default/siri/2.0/stop-monitoring.json?MonitoringRef=DADAN&StartTime=2018-12-15T06:30:00",
+    )
+    .await;
+    let visit = resp
+        .pointer("/Siri/ServiceDelivery/StopMonitoringDelivery/0/MonitoredStopVisit/0")
+        .unwrap();
+    assert_eq!(
+        visit["MonitoredVehicleJourney"]["FramedVehicleJourneyRef"],
+        serde_json::json!({"DataFrameRef": "2018-12-15", "DatedVehicleJourneyRef": "CITY2"})
+    );
+    let href = visit["_links"]["trip"]["href"].as_str().unwrap();
+    assert!(
+        href.ends_with("/default/trips/CITY2/?date=2018-12-15"),
+        "{}",
+        href
+    );
+
+    let path = &href[href.find("/default/").unwrap()..];
+    let trip: serde_json::Value = utils::get_json(srv, path).await;
+    assert_eq!(trip["DatedVehicleJourneyRef"], "CITY2");
+}
@@ -1,4 +1,8 @@
+use actix::Addr;
 use prost::Message;
+use transpo_rt::actors::{
+    DatasetLookup, DatasetsRegistry, GetDatasetActors, GetDatasets, GetReloaderState,
+};
 use transpo_rt::datasets::{DatasetInfo, Datasets};
 
 const SERVER_PATH: &str = "/gtfs_rt";
@@ -19,19 +23,104 @@ pub async fn make_simple_test_server() -> actix_web::test::TestServer {
     .await
 }
 
+#[allow(dead_code)]
 pub async fn make_test_server(datasets_info: Vec<DatasetInfo>) -> actix_web::test::TestServer {
-    let period = transpo_rt::datasets::Period {
-        begin: chrono::NaiveDate::from_ymd(2018, 12, 15),
-        horizon: chrono::Duration::days(1),
-    };
+    make_test_server_with_admin(datasets_info, None).await
+}
+
+/// create a test server, with the admin api if a token is given
+#[allow(dead_code)]
+pub async fn make_test_server_with_admin(
+    datasets_info: Vec<DatasetInfo>,
+    admin_token: Option<&str>,
+) -> actix_web::test::TestServer {
+    start_test_server(create_test_actors(datasets_info).await, admin_token)
+}
+
+/// create the actors of the datasets, with the period of the tests
+/// and wait for the datasets to be loaded
+#[allow(dead_code)]
+pub async fn create_test_actors(datasets_info: Vec<DatasetInfo>) -> Addr<DatasetsRegistry> {
+    let registry = start_test_actors(datasets_info);
+    wait_for_datasets(&registry).await;
+    registry
+}
 
+/// create the actors of the datasets, with the period of the tests
+/// Note: the datasets are loaded in the background
+#[allow(dead_code)]
+pub fn start_test_actors(datasets_info: Vec<DatasetInfo>) -> Addr<DatasetsRegistry> {
     let dataset_infos = Datasets {
-        datasets: datasets_info.clone(),
+        datasets: datasets_info,
     };
-    let actors = transpo_rt::server::create_all_actors(dataset_infos, &period).await;
+    transpo_rt::server::create_all_actors(dataset_infos, &test_period(), 2, test_allowed_hosts())
+}
+
+/// the mock servers of the tests are on the loopback address,
+/// the notifications of the subscriptions can be pushed to them
+#[allow(dead_code)]
+pub fn test_allowed_hosts() -> Vec<String> {
+    vec!["127.0.0.1".to_owned()]
+}
+
+/// the generation period of the tests
+#[allow(dead_code)]
+pub fn test_period() -> transpo_rt::datasets::GenerationPeriod {
+    transpo_rt::datasets::GenerationPeriod::Fixed(transpo_rt::datasets::Period {
+        begin: chrono::NaiveDate::from_ymd(2018, 12, 15),
+        horizon: chrono::Duration::days(1),
+    })
+}
+
+/// the datasets are loaded in the background, we wait for all of them to be ready
+/// and for their gtfs_rt to have been fetched a first time
+pub async fn wait_for_datasets(registry: &Addr<DatasetsRegistry>) {
+    for _ in 0..600 {
+        if datasets_loaded(registry).await {
+            return;
+        }
+        actix_rt::time::delay_for(std::time::Duration::from_millis(50)).await;
+    }
+    panic!("the datasets have not been loaded");
+}
+
+async fn datasets_loaded(registry: &Addr<DatasetsRegistry>) -> bool {
+    for (dataset_info, _) in registry.send(GetDatasets).await.unwrap() {
+        let actors = match registry
+            .send(GetDatasetActors(dataset_info.id))
+            .await
+            .unwrap()
+        {
+            DatasetLookup::Ready(_, actors) => actors,
+            _ => return false,
+        };
+        let realtime_state = actors
+            .realtime_reloader
+            .send(GetReloaderState)
+            .await
+            .unwrap();
+        if realtime_state.last_success.is_none() && realtime_state.last_failure.is_none() {
+            return false;
+        }
+    }
+    true
+}
+
+/// start a test server on the actors of some datasets, with the admin api if a token is given
+#[allow(dead_code)]
+pub fn start_test_server(
+    actors: Addr<DatasetsRegistry>,
+    admin_token: Option<&str>,
+) -> actix_web::test::TestServer {
+    let admin_token = admin_token.map(str::to_owned);
     actix_web::test::start(move || {
         actix_web::App::new()
             .wrap(actix_web::middleware::normalize::NormalizePath::default())
+            .configure(|cfg| {
+                if let Some(token) = &admin_token {
+                    transpo_rt::server::init_admin_routes(cfg, &actors, token)
+                }
+            })
             .configure(|cfg| transpo_rt::server::init_routes(cfg, &actors))
     })
 }
@@ -49,6 +138,17 @@ pub async fn get_json<T: serde::de::DeserializeOwned>(
     response.json().await.unwrap()
 }
 
+/// utils to query a route and get the response's status and json
+/// useful to check the errors of the siri routes, that are also in json
+#[allow(dead_code)]
+pub async fn get_json_with_status<T: serde::de::DeserializeOwned>(
+    srv: &mut actix_web::test::TestServer,
+    route: &str,
+) -> (actix_web::http::StatusCode, T) {
+    let mut response = srv.get(route).send().await.unwrap();
+    (response.status(), response.json().await.unwrap())
+}
+
 #[allow(dead_code)]
 pub async fn get_status(
     srv: &mut actix_web::test::TestServer,